@@ -0,0 +1,163 @@
+// 命令行参数解析
+
+use starrailassistant_lib::headless::DEFAULT_INSTANCE;
+
+pub const USAGE: &str = "\
+SRA-CE 命令行（无界面模式）
+
+用法:
+  sra-ce-cli config list                       列出所有配置
+  sra-ce-cli config show <名称>                 显示配置内容（密码已隐藏）
+  sra-ce-cli config get <名称> <键>             读取配置项，键可用 . 访问嵌套项，如 EnabledTasks.0
+  sra-ce-cli config set <名称> <键> <值>        修改配置项，值按 JSON 解析，解析失败时作为字符串
+  sra-ce-cli config create <名称>               创建默认配置
+  sra-ce-cli config delete <名称>               删除配置
+  sra-ce-cli start                             启动 SRA-cli 并输出日志，直到进程退出
+  sra-ce-cli run [配置...]                      执行指定配置，未指定时按任务顺序执行
+  sra-ce-cli daemon                            常驻运行 SRA-cli、计划任务、远程控制和 IPC 接口
+
+选项:
+  --instance <ID>        SRA-cli 实例（默认 default）
+  --profile <名称>       启动配置
+  --args <参数>          SRA-cli 额外参数
+  --retries <次数>       run: 每个配置出错后的重试次数（默认 1）
+  --post-actions         run: 结束后执行配置中的完成后操作（按任务顺序执行时默认开启）
+  --no-post-actions      run: 不执行完成后操作
+  --keep-running         run: 结束后不停止 SRA-cli
+  --json                 日志按 JSON 逐行输出
+  --quiet                不输出日志
+  -h, --help             显示帮助
+
+退出码:
+  0 全部成功  1 有配置失败  2 因失败策略中止  3 已取消  4 运行出错  64 参数错误";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    ConfigList,
+    ConfigShow { name: String },
+    ConfigGet { name: String, key: String },
+    ConfigSet { name: String, key: String, value: String },
+    ConfigCreate { name: String },
+    ConfigDelete { name: String },
+    Start,
+    Run { configs: Vec<String> },
+    Daemon,
+    Help,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Options {
+    pub instance: String,
+    pub profile: Option<String>,
+    pub arguments: Option<String>,
+    pub retries: Option<u32>,
+    pub post_actions: Option<bool>,
+    pub keep_running: bool,
+    pub json: bool,
+    pub quiet: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            instance: DEFAULT_INSTANCE.to_string(),
+            profile: None,
+            arguments: None,
+            retries: None,
+            post_actions: None,
+            keep_running: false,
+            json: false,
+            quiet: false,
+        }
+    }
+}
+
+// 解析参数（不含程序名）
+pub fn parse(args: &[String]) -> Result<(Command, Options), String> {
+    let mut options = Options::default();
+    let mut positional = Vec::new();
+    let mut iter = args.iter();
+
+    while let Some(arg) = iter.next() {
+        let mut value = |name: &str| iter.next().cloned().ok_or_else(|| format!("{} 缺少参数值", name));
+        match arg.as_str() {
+            "-h" | "--help" => return Ok((Command::Help, options)),
+            "--instance" => options.instance = value(arg)?,
+            "--profile" => options.profile = Some(value(arg)?),
+            "--args" => options.arguments = Some(value(arg)?),
+            "--retries" => {
+                let retries = value(arg)?;
+                options.retries = Some(retries.parse().map_err(|_| format!("无效的重试次数: {}", retries))?);
+            }
+            "--post-actions" => options.post_actions = Some(true),
+            "--no-post-actions" => options.post_actions = Some(false),
+            "--keep-running" => options.keep_running = true,
+            "--json" => options.json = true,
+            "--quiet" => options.quiet = true,
+            _ if arg.starts_with("--") => return Err(format!("未知选项: {}", arg)),
+            _ => positional.push(arg.clone()),
+        }
+    }
+
+    let command = match positional.split_first() {
+        None => Command::Help,
+        Some((name, rest)) => match (name.as_str(), rest) {
+            ("config", rest) => parse_config(rest)?,
+            ("start", []) => Command::Start,
+            ("run", configs) => Command::Run { configs: configs.to_vec() },
+            ("daemon", []) => Command::Daemon,
+            ("help", _) => Command::Help,
+            ("start" | "daemon", _) => return Err(format!("{} 不接受参数", name)),
+            _ => return Err(format!("未知命令: {}", name)),
+        },
+    };
+    Ok((command, options))
+}
+
+fn parse_config(args: &[String]) -> Result<Command, String> {
+    let args: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
+    Ok(match args.as_slice() {
+        ["list"] => Command::ConfigList,
+        ["show", name] => Command::ConfigShow { name: name.to_string() },
+        ["get", name, key] => Command::ConfigGet { name: name.to_string(), key: key.to_string() },
+        ["set", name, key, value] => Command::ConfigSet {
+            name: name.to_string(),
+            key: key.to_string(),
+            value: value.to_string(),
+        },
+        ["create", name] => Command::ConfigCreate { name: name.to_string() },
+        ["delete", name] => Command::ConfigDelete { name: name.to_string() },
+        _ => return Err("config 用法: list | show <名称> | get <名称> <键> | set <名称> <键> <值> | create <名称> | delete <名称>".to_string()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_str(args: &[&str]) -> Result<(Command, Options), String> {
+        parse(&args.iter().map(|s| s.to_string()).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn test_parse_commands_and_options() {
+        let (command, options) = parse_str(&["run", "A", "--retries", "2", "B", "--no-post-actions", "--instance", "alt"]).unwrap();
+        assert_eq!(command, Command::Run { configs: vec!["A".to_string(), "B".to_string()] });
+        assert_eq!(options.retries, Some(2));
+        assert_eq!(options.post_actions, Some(false));
+        assert_eq!(options.instance, "alt");
+
+        let (command, _) = parse_str(&["config", "set", "A", "EnabledTasks.0", "true"]).unwrap();
+        assert_eq!(command, Command::ConfigSet {
+            name: "A".to_string(),
+            key: "EnabledTasks.0".to_string(),
+            value: "true".to_string(),
+        });
+        assert_eq!(parse_str(&[]).unwrap().0, Command::Help);
+
+        assert!(parse_str(&["config", "get", "A"]).is_err());
+        assert!(parse_str(&["start", "extra"]).is_err());
+        assert!(parse_str(&["run", "--retries"]).is_err());
+        assert!(parse_str(&["run", "--bogus"]).is_err());
+    }
+}
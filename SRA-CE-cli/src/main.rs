@@ -0,0 +1,304 @@
+// SRA-CE 命令行入口
+// 无界面地管理配置、启动 SRA-cli 和执行任务，便于在计划任务、SSH 或脚本中使用；
+// 退出码反映执行结果（见 args::USAGE）
+
+mod args;
+
+use args::{Command, Options};
+use starrailassistant_lib::headless::{self, EntryStatus, LaunchRequest, LogMessage, PlanEntry, PlanReport, PlanResult, RunPlan, SraStatus};
+use std::process::ExitCode;
+use std::thread;
+use std::time::Duration;
+
+const EXIT_FAILED: u8 = 1;
+const EXIT_ABORTED: u8 = 2;
+const EXIT_CANCELLED: u8 = 3;
+const EXIT_ERROR: u8 = 4;
+const EXIT_USAGE: u8 = 64;
+
+// 进程状态轮询间隔
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+fn main() -> ExitCode {
+    let raw: Vec<String> = std::env::args().skip(1).collect();
+    let (command, options) = match args::parse(&raw) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, args::USAGE);
+            return ExitCode::from(EXIT_USAGE);
+        }
+    };
+
+    match execute(command, &options) {
+        Ok(code) => ExitCode::from(code),
+        Err(e) => {
+            eprintln!("错误: {}", e);
+            ExitCode::from(EXIT_ERROR)
+        }
+    }
+}
+
+fn execute(command: Command, options: &Options) -> Result<u8, String> {
+    match command {
+        Command::Help => {
+            println!("{}", args::USAGE);
+            Ok(0)
+        }
+        Command::ConfigList => {
+            for name in headless::config_list()? {
+                println!("{}", name);
+            }
+            Ok(0)
+        }
+        Command::ConfigShow { name } => {
+            let mut config = headless::load_config(&name)?;
+            mask_secrets(&mut config);
+            print_json(&config)
+        }
+        Command::ConfigGet { name, key } => {
+            let mut config = headless::load_config(&name)?;
+            mask_secrets(&mut config);
+            let value = lookup(&config, &key).ok_or_else(|| format!("配置 {} 中没有 {}", name, key))?;
+            print_json(value)
+        }
+        Command::ConfigSet { name, key, value } => {
+            let mut config = headless::load_config(&name)?;
+            // 值不是合法 JSON 时按字符串处理，如 set A StartGamePath D:\Games
+            let value = serde_json::from_str(&value).unwrap_or(serde_json::Value::String(value));
+            set_value(&mut config, &key, value)?;
+            headless::save_config(config)?;
+            Ok(0)
+        }
+        Command::ConfigCreate { name } => {
+            headless::create_config(&name)?;
+            println!("已创建配置 {}", name);
+            Ok(0)
+        }
+        Command::ConfigDelete { name } => {
+            headless::delete_config(&name)?;
+            println!("已删除配置 {}", name);
+            Ok(0)
+        }
+        Command::Start => {
+            headless::init()?;
+            let _logs = stream_logs(options);
+            headless::start(&options.instance, launch_request(options))?;
+            wait_for_exit(&options.instance);
+            let status = headless::instance_status(&options.instance)?;
+            Ok(if status == SraStatus::Error.as_str() { EXIT_ERROR } else { 0 })
+        }
+        Command::Run { configs } => {
+            headless::init()?;
+            let _logs = stream_logs(options);
+            run(configs, options)
+        }
+        Command::Daemon => {
+            headless::init()?;
+            let _logs = stream_logs(options);
+            headless::start(&options.instance, launch_request(options))?;
+            headless::start_services();
+            loop {
+                thread::sleep(Duration::from_secs(60));
+            }
+        }
+    }
+}
+
+fn run(configs: Vec<String>, options: &Options) -> Result<u8, String> {
+    let from_task_order = configs.is_empty();
+    let mut plan = if from_task_order {
+        headless::task_order_plan(&options.instance)?
+    } else {
+        RunPlan {
+            name: configs.join(", "),
+            instance_id: Some(options.instance.clone()),
+            entries: configs.into_iter().map(|config| PlanEntry { config, ..Default::default() }).collect(),
+            post_actions: false,
+        }
+    };
+    if let Some(retries) = options.retries {
+        for entry in plan.entries.iter_mut() {
+            entry.retries = retries;
+        }
+    }
+
+    if headless::instance_status(&options.instance)? == SraStatus::NotRunning.as_str() {
+        headless::start(&options.instance, launch_request(options))?;
+    }
+    let result = headless::run_plan(plan, options.post_actions.unwrap_or(from_task_order));
+    if !options.keep_running {
+        let _ = headless::stop(&options.instance);
+    }
+    let report = result?;
+    print_report(&report);
+
+    Ok(match report.result {
+        PlanResult::Succeeded => 0,
+        PlanResult::Failed => EXIT_FAILED,
+        PlanResult::Aborted => EXIT_ABORTED,
+        PlanResult::Cancelled => EXIT_CANCELLED,
+        PlanResult::Running => EXIT_ERROR,
+    })
+}
+
+fn launch_request(options: &Options) -> LaunchRequest {
+    LaunchRequest {
+        profile: options.profile.clone(),
+        arguments: options.arguments.clone(),
+    }
+}
+
+// 等待 SRA-cli 退出（崩溃后由看门狗重启的不算退出）
+fn wait_for_exit(instance_id: &str) {
+    loop {
+        thread::sleep(POLL_INTERVAL);
+        match headless::instance_status(instance_id) {
+            Ok(status) if status != SraStatus::NotRunning.as_str() && status != SraStatus::Error.as_str() => {}
+            _ => return,
+        }
+    }
+}
+
+// 日志订阅，离开作用域时取消
+struct LogStream(Option<u64>);
+
+impl Drop for LogStream {
+    fn drop(&mut self) {
+        if let Some(id) = self.0 {
+            headless::unsubscribe(id);
+        }
+    }
+}
+
+fn stream_logs(options: &Options) -> LogStream {
+    if options.quiet {
+        return LogStream(None);
+    }
+    let json = options.json;
+    LogStream(Some(headless::subscribe_logs(move |message| {
+        if json {
+            if let Ok(line) = serde_json::to_string(message) {
+                println!("{}", line);
+            }
+        } else {
+            println!("{}", format_log(message));
+        }
+    })))
+}
+
+// 14:20:07 [INFO] [进程端] [default] 消息内容
+fn format_log(message: &LogMessage) -> String {
+    let time = message.time.get(11..19).unwrap_or(&message.time);
+    match message.instance {
+        Some(ref instance) => format!("{} [{}] [{}] [{}] {}", time, message.level.as_str(), message.source.as_str(), instance, message.message),
+        None => format!("{} [{}] [{}] {}", time, message.level.as_str(), message.source.as_str(), message.message),
+    }
+}
+
+fn print_report(report: &PlanReport) {
+    println!("计划「{}」结束: {:?}", report.name, report.result);
+    for entry in &report.entries {
+        let status = match entry.status {
+            EntryStatus::Succeeded => "成功",
+            EntryStatus::Failed => "失败",
+            EntryStatus::Skipped => "跳过",
+            EntryStatus::Pending | EntryStatus::Running => "未完成",
+        };
+        match entry.message {
+            Some(ref message) => println!("  {}\t{}\t第 {} 次\t{}", entry.config, status, entry.attempts, message),
+            None => println!("  {}\t{}\t第 {} 次", entry.config, status, entry.attempts),
+        }
+    }
+}
+
+fn print_json(value: &serde_json::Value) -> Result<u8, String> {
+    let content = serde_json::to_string_pretty(value).map_err(|e| format!("Failed to serialize: {}", e))?;
+    println!("{}", content);
+    Ok(0)
+}
+
+// 隐藏账号密码
+fn mask_secrets(config: &mut serde_json::Value) {
+    for key in headless::CONFIG_SECRET_KEYS {
+        if let Some(value) = config.get_mut(key) {
+            if value.as_str().is_some_and(|s| !s.is_empty()) {
+                *value = serde_json::Value::String("******".to_string());
+            }
+        }
+    }
+}
+
+// 按 a.b.0 形式的路径读取
+fn lookup<'a>(config: &'a serde_json::Value, key: &str) -> Option<&'a serde_json::Value> {
+    key.split('.').try_fold(config, |value, part| match value {
+        serde_json::Value::Array(items) => items.get(part.parse::<usize>().ok()?),
+        _ => value.get(part),
+    })
+}
+
+// 按路径修改，对象的最后一级不存在时新增；Name 不能修改（配置文件名由其决定）
+fn set_value(config: &mut serde_json::Value, key: &str, value: serde_json::Value) -> Result<(), String> {
+    if key == "Name" {
+        return Err("不能修改配置名称".to_string());
+    }
+    let (parent_key, last) = match key.rsplit_once('.') {
+        Some((parent, last)) => (Some(parent), last),
+        None => (None, key),
+    };
+    let mut parent = config;
+    if let Some(parent_key) = parent_key {
+        for part in parent_key.split('.') {
+            parent = match parent {
+                serde_json::Value::Array(items) => part.parse::<usize>().ok().and_then(|i| items.get_mut(i)),
+                _ => parent.get_mut(part),
+            }
+            .ok_or_else(|| format!("配置中没有 {}", parent_key))?;
+        }
+    }
+    match parent {
+        serde_json::Value::Array(items) => {
+            let slot = last
+                .parse::<usize>()
+                .ok()
+                .and_then(|i| items.get_mut(i))
+                .ok_or_else(|| format!("{} 超出范围", key))?;
+            *slot = value;
+        }
+        serde_json::Value::Object(map) => {
+            map.insert(last.to_string(), value);
+        }
+        _ => return Err(format!("{} 不是对象或数组", parent_key.unwrap_or(key))),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_config_paths() {
+        let mut config = json!({ "Name": "A", "EnabledTasks": [false, false], "StartGamePath": "" });
+        set_value(&mut config, "EnabledTasks.1", json!(true)).unwrap();
+        set_value(&mut config, "StartGamePath", json!("D:\\Games")).unwrap();
+        assert_eq!(lookup(&config, "EnabledTasks.1"), Some(&json!(true)));
+        assert_eq!(lookup(&config, "StartGamePath"), Some(&json!("D:\\Games")));
+
+        assert!(set_value(&mut config, "EnabledTasks.5", json!(true)).is_err());
+        assert!(set_value(&mut config, "Missing.Key", json!(1)).is_err());
+        assert!(set_value(&mut config, "Name", json!("B")).is_err());
+        assert_eq!(lookup(&config, "EnabledTasks.x"), None);
+
+        // config show / get 都不输出明文账号密码（空值保持不变）
+        for key in headless::CONFIG_SECRET_KEYS {
+            let mut secret = json!({ key: "hunter22", "Name": "A" });
+            mask_secrets(&mut secret);
+            assert_eq!(lookup(&secret, key), Some(&json!("******")), "{}", key);
+            assert_eq!(lookup(&secret, "Name"), Some(&json!("A")));
+            let mut empty = json!({ key: "" });
+            mask_secrets(&mut empty);
+            assert_eq!(lookup(&empty, key), Some(&json!("")));
+        }
+    }
+}
@@ -0,0 +1,88 @@
+// 命令行参数解析
+// 按 shell 风格拆分参数，支持引号包含空格的路径和配置名
+
+// 拆分参数字符串
+// - 空白分隔参数
+// - 双引号和单引号内的空白保留；单引号内不处理转义
+// - 反斜杠只转义紧随的引号和反斜杠自身，其余情况按原样保留（兼容 Windows 路径）
+pub fn split_args(input: &str) -> Result<Vec<String>, String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_arg = false; // 当前是否有参数（"" 也算一个空参数）
+    let mut quote: Option<char> = None;
+    let mut chars = input.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match quote {
+            Some('\'') => {
+                if c == '\'' {
+                    quote = None;
+                } else {
+                    current.push(c);
+                }
+            }
+            Some(q) => {
+                if c == q {
+                    quote = None;
+                } else if c == '\\' && matches!(chars.peek(), Some('"') | Some('\\')) {
+                    current.push(chars.next().unwrap());
+                } else {
+                    current.push(c);
+                }
+            }
+            None => {
+                if c.is_whitespace() {
+                    if in_arg {
+                        args.push(std::mem::take(&mut current));
+                        in_arg = false;
+                    }
+                } else if c == '"' || c == '\'' {
+                    quote = Some(c);
+                    in_arg = true;
+                } else if c == '\\' && matches!(chars.peek(), Some('"') | Some('\'')) {
+                    current.push(chars.next().unwrap());
+                    in_arg = true;
+                } else {
+                    current.push(c);
+                    in_arg = true;
+                }
+            }
+        }
+    }
+
+    if let Some(q) = quote {
+        return Err(format!("参数中的引号 {} 未闭合: {}", q, input));
+    }
+    if in_arg {
+        args.push(current);
+    }
+    Ok(args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_plain_and_quoted() {
+        assert_eq!(
+            split_args(r#"--config "My Config" --path 'C:\Program Files\SRA' -v"#).unwrap(),
+            vec!["--config", "My Config", "--path", r"C:\Program Files\SRA", "-v"]
+        );
+        assert_eq!(split_args("  a   b  ").unwrap(), vec!["a", "b"]);
+        assert!(split_args("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_split_escapes_and_empty_args() {
+        assert_eq!(split_args(r#"say "he said \"hi\"" """#).unwrap(), vec!["say", r#"he said "hi""#, ""]);
+        assert_eq!(split_args(r"C:\Users\me\SRA-cli.exe").unwrap(), vec![r"C:\Users\me\SRA-cli.exe"]);
+        assert_eq!(split_args(r#"--name=a" b"c"#).unwrap(), vec!["--name=a bc"]);
+    }
+
+    #[test]
+    fn test_unterminated_quote_is_error() {
+        assert!(split_args(r#"--config "Default"#).is_err());
+        assert!(split_args("'abc").is_err());
+    }
+}
@@ -0,0 +1,180 @@
+// 进程后端抽象
+// 将进程的启动、stdin 写入和 stdout/stderr 读取放在 trait 之后，
+// 默认使用系统进程，测试和无 SRA-cli 的开发环境可以替换为模拟后端
+
+use crate::mock_backend::MockBackend;
+use crate::resolver;
+use std::io::{self, Read, Write};
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, Mutex};
+
+// 设置后使用模拟后端，值为 SRA-cli 交互脚本的路径
+pub const MOCK_SCRIPT_ENV: &str = "SRA_CE_MOCK_SCRIPT";
+
+// 进程启动参数
+#[derive(Debug, Clone)]
+pub struct LaunchSpec {
+    pub program: PathBuf,
+    pub args: Vec<String>,
+    pub working_dir: PathBuf,
+    pub env: Vec<(String, String)>,
+}
+
+// 进程退出信息
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ExitInfo {
+    pub code: Option<i32>,
+}
+
+// 已启动的进程
+pub trait ProcessHandle: Send {
+    fn id(&self) -> u32;
+    fn take_stdout(&mut self) -> Option<Box<dyn Read + Send>>;
+    fn take_stderr(&mut self) -> Option<Box<dyn Read + Send>>;
+    // 写入并刷新 stdin
+    fn write_stdin(&mut self, data: &[u8]) -> io::Result<()>;
+    // 非阻塞检查进程是否已退出
+    fn try_wait(&mut self) -> io::Result<Option<ExitInfo>>;
+    fn kill(&mut self) -> io::Result<()>;
+    // 结束进程及其子进程（默认只结束进程本身）
+    fn kill_tree(&mut self) -> io::Result<()> {
+        self.kill()
+    }
+    fn wait(&mut self) -> io::Result<ExitInfo>;
+}
+
+// 进程后端
+pub trait ProcessBackend: Send + Sync {
+    fn name(&self) -> &str;
+
+    // 确定可执行文件与工作目录
+    fn resolve_program(&self) -> Result<(PathBuf, PathBuf), String> {
+        let report = resolver::resolve_sra_cli()?;
+        match report.resolved {
+            Some(resolved) => Ok((resolved.path, resolved.working_dir)),
+            None => Err(format!(
+                "无法找到文件 SRA-cli.exe，请检查安装完整性。\n已检查的路径:\n{}",
+                report.summary()
+            )),
+        }
+    }
+
+    fn spawn(&self, spec: &LaunchSpec) -> Result<Box<dyn ProcessHandle>, String>;
+}
+
+// 系统进程后端（std::process）
+pub struct SystemBackend;
+
+struct SystemProcess {
+    child: Child,
+}
+
+impl ProcessHandle for SystemProcess {
+    fn id(&self) -> u32 {
+        self.child.id()
+    }
+
+    fn take_stdout(&mut self) -> Option<Box<dyn Read + Send>> {
+        self.child.stdout.take().map(|s| Box::new(s) as Box<dyn Read + Send>)
+    }
+
+    fn take_stderr(&mut self) -> Option<Box<dyn Read + Send>> {
+        self.child.stderr.take().map(|s| Box::new(s) as Box<dyn Read + Send>)
+    }
+
+    fn write_stdin(&mut self, data: &[u8]) -> io::Result<()> {
+        let stdin = self.child.stdin.as_mut()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::BrokenPipe, "stdin 不可用"))?;
+        stdin.write_all(data)?;
+        stdin.flush()
+    }
+
+    fn try_wait(&mut self) -> io::Result<Option<ExitInfo>> {
+        Ok(self.child.try_wait()?.map(|status| ExitInfo { code: status.code() }))
+    }
+
+    fn kill(&mut self) -> io::Result<()> {
+        self.child.kill()
+    }
+
+    // Windows 下使用 taskkill /T 结束 SRA-cli 启动的游戏辅助进程等子进程，失败时退回只结束主进程
+    fn kill_tree(&mut self) -> io::Result<()> {
+        #[cfg(target_os = "windows")]
+        {
+            use std::os::windows::process::CommandExt;
+            const CREATE_NO_WINDOW: u32 = 0x08000000;
+            let status = Command::new("taskkill")
+                .args(["/PID", &self.child.id().to_string(), "/T", "/F"])
+                .creation_flags(CREATE_NO_WINDOW)
+                .status();
+            if matches!(status, Ok(status) if status.success()) {
+                return Ok(());
+            }
+        }
+        self.child.kill()
+    }
+
+    fn wait(&mut self) -> io::Result<ExitInfo> {
+        Ok(ExitInfo { code: self.child.wait()?.code() })
+    }
+}
+
+impl ProcessBackend for SystemBackend {
+    fn name(&self) -> &str {
+        "system"
+    }
+
+    fn spawn(&self, spec: &LaunchSpec) -> Result<Box<dyn ProcessHandle>, String> {
+        let mut command = Command::new(&spec.program);
+        command
+            .args(&spec.args)
+            .current_dir(&spec.working_dir)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        for (key, value) in &spec.env {
+            command.env(key, value);
+        }
+
+        // 生产环境下隐藏命令行窗口
+        #[cfg(target_os = "windows")]
+        {
+            if !cfg!(debug_assertions) {
+                use std::os::windows::process::CommandExt;
+                const CREATE_NO_WINDOW: u32 = 0x08000000;
+                command.creation_flags(CREATE_NO_WINDOW);
+            }
+        }
+
+        let child = command
+            .spawn()
+            .map_err(|e| format!("启动失败: {}", e))?;
+        Ok(Box::new(SystemProcess { child }))
+    }
+}
+
+// 当前使用的后端（未设置时按环境变量选择）
+static BACKEND: Mutex<Option<Arc<dyn ProcessBackend>>> = Mutex::new(None);
+
+// 替换进程后端（测试或无界面模式使用）
+pub fn set_backend(backend: Arc<dyn ProcessBackend>) {
+    if let Ok(mut guard) = BACKEND.lock() {
+        *guard = Some(backend);
+    }
+}
+
+// 获取当前进程后端
+pub fn current_backend() -> Result<Arc<dyn ProcessBackend>, String> {
+    let mut guard = BACKEND.lock().map_err(|e| format!("Lock error: {}", e))?;
+    if let Some(ref backend) = *guard {
+        return Ok(backend.clone());
+    }
+
+    let backend: Arc<dyn ProcessBackend> = match std::env::var(MOCK_SCRIPT_ENV) {
+        Ok(path) if !path.trim().is_empty() => Arc::new(MockBackend::from_file(&path)?),
+        _ => Arc::new(SystemBackend),
+    };
+    *guard = Some(backend.clone());
+    Ok(backend)
+}
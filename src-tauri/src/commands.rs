@@ -3,10 +3,21 @@
 use crate::process;
 use crate::config;
 use crate::logger;
+use crate::log_query;
 use crate::wallpaper;
 use crate::announcement;
 use crate::settings;
 use crate::shortcut;
+use crate::resolver;
+use crate::transcript;
+use crate::run_history;
+use crate::scheduler;
+use crate::reset_clock;
+use crate::post_actions;
+use crate::run_plan;
+use crate::remote_api;
+use crate::control;
+use crate::types::LaunchRequest;
 
 #[tauri::command]
 pub fn greet(name: &str) -> String {
@@ -40,40 +51,275 @@ pub fn run_elevated_command(command: String) -> Result<(), String> {
     }
 }
 
-// 进程管理命令
+// 进程管理命令（instance_id 为空时作用于默认实例，profile 为空时使用默认启动配置）
 #[tauri::command]
-pub fn start_sra_process_command(app_handle: tauri::AppHandle, arguments: Option<String>) -> Result<(), String> {
-    process::start_sra_process_command(app_handle, arguments)
+pub fn start_sra_process_command(instance_id: Option<String>, profile: Option<String>, arguments: Option<String>) -> Result<(), String> {
+    process::start_sra_process_command(&process::instance_key(instance_id), LaunchRequest { profile, arguments })
 }
 
+// 停止流程需要等待进程退出，放到线程池执行，进度通过 sra-stop-progress 事件通知
 #[tauri::command]
-pub fn stop_sra_process_command(app_handle: tauri::AppHandle) -> Result<(), String> {
-    process::stop_sra_process_command(app_handle)
+pub async fn stop_sra_process_command(instance_id: Option<String>) -> Result<(), String> {
+    let instance_id = process::instance_key(instance_id);
+    tokio::task::spawn_blocking(move || process::stop_sra_process_command(&instance_id))
+        .await
+        .map_err(|e| format!("Task join error: {}", e))?
 }
 
 #[tauri::command]
-pub fn restart_sra_process_command(app_handle: tauri::AppHandle, arguments: Option<String>) -> Result<(), String> {
-    process::restart_sra_process_command(app_handle, arguments)
+pub async fn restart_sra_process_command(instance_id: Option<String>, profile: Option<String>, arguments: Option<String>) -> Result<(), String> {
+    let instance_id = process::instance_key(instance_id);
+    tokio::task::spawn_blocking(move || process::restart_sra_process_command(&instance_id, LaunchRequest { profile, arguments }))
+        .await
+        .map_err(|e| format!("Task join error: {}", e))?
 }
 
+// 返回所有实例的状态
 #[tauri::command]
-pub fn get_sra_status() -> Result<String, String> {
+pub fn get_sra_status() -> Result<Vec<crate::types::InstanceStatus>, String> {
     process::get_sra_status()
 }
 
 #[tauri::command]
-pub fn send_input_to_sra(app_handle: tauri::AppHandle, input: String) -> Result<(), String> {
-    process::send_input_to_sra(app_handle, input)
+pub fn get_sra_state_history(instance_id: Option<String>) -> Result<Vec<crate::state::StateTransition>, String> {
+    process::get_sra_state_history(&process::instance_key(instance_id))
+}
+
+#[tauri::command]
+pub fn send_input_to_sra(instance_id: Option<String>, input: String) -> Result<(), String> {
+    process::send_input_to_sra(&process::instance_key(instance_id), input)
+}
+
+// 发送命令并等待 sra> 提示符，返回命令产生的输出（阻塞操作放到线程池执行）
+#[tauri::command]
+pub async fn send_command_and_wait(instance_id: Option<String>, input: String, timeout_ms: Option<u64>) -> Result<crate::types::CommandResponse, String> {
+    let instance_id = process::instance_key(instance_id);
+    tokio::task::spawn_blocking(move || process::send_command_and_wait(&instance_id, input, timeout_ms))
+        .await
+        .map_err(|e| format!("Task join error: {}", e))?
+}
+
+#[tauri::command]
+pub fn task_run(instance_id: Option<String>, config_name: Option<String>) -> Result<(), String> {
+    process::task_run(&process::instance_key(instance_id), config_name)
+}
+
+#[tauri::command]
+pub fn task_stop(instance_id: Option<String>) -> Result<(), String> {
+    process::task_stop(&process::instance_key(instance_id))
+}
+
+// SRA-cli 路径诊断：返回采用的路径以及每个候选被拒绝的原因
+#[tauri::command]
+pub fn diagnose_sra_cli_path() -> Result<resolver::ResolveReport, String> {
+    resolver::resolve_sra_cli()
+}
+
+#[tauri::command]
+pub fn save_sra_cli_path(path: Option<String>) -> Result<(), String> {
+    settings::save_sra_cli_path(path)
+}
+
+#[tauri::command]
+pub fn save_watchdog_settings(watchdog: settings::WatchdogSettings) -> Result<(), String> {
+    settings::save_watchdog_settings(watchdog)
+}
+
+#[tauri::command]
+pub fn save_stop_policy(stop_policy: settings::StopPolicy) -> Result<(), String> {
+    settings::save_stop_policy(stop_policy)
+}
+
+// 启动配置管理
+#[tauri::command]
+pub fn get_launch_profiles() -> Result<Vec<settings::LaunchProfile>, String> {
+    Ok(settings::load_ce_settings()?.launch_profiles)
+}
+
+#[tauri::command]
+pub fn save_launch_profile(profile: settings::LaunchProfile) -> Result<(), String> {
+    settings::save_launch_profile(profile)
+}
+
+#[tauri::command]
+pub fn delete_launch_profile(name: String) -> Result<(), String> {
+    settings::delete_launch_profile(&name)
+}
+
+#[tauri::command]
+pub fn set_default_launch_profile(name: Option<String>) -> Result<(), String> {
+    settings::set_default_launch_profile(name)
+}
+
+// 运行计划（未指定时按任务顺序生成），返回计划 ID
+#[tauri::command]
+pub fn start_run_plan(plan: Option<run_plan::RunPlan>, instance_id: Option<String>) -> Result<String, String> {
+    let plan = match plan {
+        Some(plan) => plan,
+        None => run_plan::RunPlan::from_task_order(instance_id)?,
+    };
+    run_plan::start_plan(plan)
+}
+
+#[tauri::command]
+pub fn cancel_run_plan(instance_id: Option<String>) -> Result<bool, String> {
+    run_plan::cancel_plan(&process::instance_key(instance_id))
+}
+
+#[tauri::command]
+pub fn get_active_plans() -> Result<Vec<run_plan::PlanReport>, String> {
+    Ok(run_plan::get_active_plans())
+}
+
+// 日志轮转与清理（下次启动生效）
+#[tauri::command]
+pub fn save_log_retention_settings(log_retention: settings::LogRetentionSettings) -> Result<(), String> {
+    settings::save_log_retention_settings(log_retention)
+}
+
+// 日志级别：保存后立即生效
+#[tauri::command]
+pub fn save_log_level_settings(log_levels: settings::LogLevelSettings) -> Result<(), String> {
+    settings::save_log_level_settings(log_levels.clone())?;
+    logger::set_log_levels(log_levels)
+}
+
+// 日志缓冲区容量：保存后立即生效
+#[tauri::command]
+pub fn save_log_buffer_capacity(capacity: usize) -> Result<(), String> {
+    settings::save_log_buffer_capacity(capacity)?;
+    logger::set_log_buffer_capacity(capacity)
+}
+
+// 日志脱敏规则：保存后立即生效
+#[tauri::command]
+pub fn save_redaction_settings(redaction: settings::RedactionSettings) -> Result<(), String> {
+    settings::save_redaction_settings(redaction.clone())?;
+    crate::redact::set_patterns(&redaction.patterns)
 }
 
+// 远程控制接口：保存后立即按新设置重启服务（需要等待原服务线程退出，放到线程池执行）
 #[tauri::command]
-pub fn task_run(app_handle: tauri::AppHandle, config_name: Option<String>) -> Result<(), String> {
-    process::task_run(app_handle, config_name)
+pub async fn save_remote_api_settings(enabled: bool, bind_address: String) -> Result<(), String> {
+    control::blocking(move || {
+        settings::save_remote_api_settings(enabled, bind_address)?;
+        remote_api::apply_settings()
+    })
+    .await
 }
 
+// 生成新的访问令牌（旧令牌立即失效），返回明文
 #[tauri::command]
-pub fn task_stop(app_handle: tauri::AppHandle) -> Result<(), String> {
-    process::task_stop(app_handle)
+pub async fn generate_remote_api_token() -> Result<String, String> {
+    control::blocking(|| {
+        let token = remote_api::generate_token()?;
+        remote_api::apply_settings()?;
+        Ok(token)
+    })
+    .await
+}
+
+#[tauri::command]
+pub fn get_remote_api_address() -> Result<Option<String>, String> {
+    Ok(remote_api::local_address().map(|address| address.to_string()))
+}
+
+// 本地 IPC 接口：重启程序后生效
+#[tauri::command]
+pub fn save_ipc_enabled(enabled: bool) -> Result<(), String> {
+    settings::save_ipc_enabled(enabled)
+}
+
+// 任务超时与卡死检测
+#[tauri::command]
+pub fn save_task_timeout_settings(task_timeout: settings::TaskTimeoutSettings) -> Result<(), String> {
+    settings::save_task_timeout_settings(task_timeout)
+}
+
+// 任务完成后操作
+#[tauri::command]
+pub fn save_post_action_settings(post_actions: settings::PostActionSettings) -> Result<(), String> {
+    settings::save_post_action_settings(post_actions)
+}
+
+// 任务顺序执行完成后调用，按各配置的 After* 开关倒计时执行
+#[tauri::command]
+pub fn run_post_actions(configs: Option<Vec<String>>) -> Result<post_actions::PostActions, String> {
+    let configs = match configs {
+        Some(configs) => configs,
+        None => config::load_task_order()?,
+    };
+    let actions = post_actions::actions_for_configs(&configs);
+    post_actions::schedule(actions)?;
+    Ok(actions)
+}
+
+#[tauri::command]
+pub fn cancel_post_actions() -> Result<bool, String> {
+    post_actions::cancel()
+}
+
+// 会话记录
+#[tauri::command]
+pub fn save_transcript_settings(transcript: settings::TranscriptSettings) -> Result<(), String> {
+    settings::save_transcript_settings(transcript)
+}
+
+#[tauri::command]
+pub fn list_transcripts() -> Result<Vec<transcript::TranscriptInfo>, String> {
+    transcript::list_transcripts()
+}
+
+// 离线回放会话记录，结果写入日志（解析大文件时放到线程池执行）
+#[tauri::command]
+pub async fn replay_transcript(path: String) -> Result<transcript::ReplayResult, String> {
+    tokio::task::spawn_blocking(move || transcript::replay_transcript(&path))
+        .await
+        .map_err(|e| format!("Task join error: {}", e))?
+}
+
+// 任务运行历史（按配置、实例、结果和日期范围筛选，最新的在前）
+#[tauri::command]
+pub fn query_run_history(query: Option<run_history::RunQuery>) -> Result<Vec<run_history::TaskRun>, String> {
+    run_history::query_runs(&query.unwrap_or_default())
+}
+
+// 计划任务
+#[tauri::command]
+pub fn get_schedules() -> Result<Vec<scheduler::ScheduleRule>, String> {
+    scheduler::get_schedules()
+}
+
+#[tauri::command]
+pub fn save_schedule(rule: scheduler::ScheduleRule) -> Result<scheduler::ScheduleRule, String> {
+    scheduler::save_schedule(rule)
+}
+
+#[tauri::command]
+pub fn delete_schedule(id: String) -> Result<(), String> {
+    scheduler::delete_schedule(&id)
+}
+
+#[tauri::command]
+pub fn get_upcoming_runs(limit: Option<usize>) -> Result<Vec<scheduler::UpcomingRun>, String> {
+    scheduler::get_upcoming_runs(limit.unwrap_or(10))
+}
+
+#[tauri::command]
+pub fn run_schedule_now(id: String) -> Result<(), String> {
+    scheduler::run_schedule_now(&id)
+}
+
+// 游戏日与刷新时间（未指定区服时按设置或配置的启动渠道确定）
+#[tauri::command]
+pub fn get_reset_clock(config: Option<String>, region: Option<reset_clock::ServerRegion>) -> Result<reset_clock::ResetClockInfo, String> {
+    let region = region.unwrap_or_else(|| reset_clock::resolve_region(config.as_deref()));
+    Ok(reset_clock::reset_clock_info(region, chrono::Utc::now()))
+}
+
+#[tauri::command]
+pub fn save_server_region(region: Option<reset_clock::ServerRegion>) -> Result<(), String> {
+    settings::save_server_region(region)
 }
 
 // 配置管理命令
@@ -123,6 +369,18 @@ pub fn get_all_logs() -> Result<Vec<logger::LogMessage>, String> {
     logger::get_all_logs()
 }
 
+// 增量获取日志：返回序号大于 since 的日志，从 0 开始获取全部
+#[tauri::command]
+pub fn get_logs_since(since: u64, limit: Option<usize>) -> Result<logger::LogBatch, String> {
+    logger::get_logs_since(since, limit)
+}
+
+// 在所有日志文件中查询（最新的在前）
+#[tauri::command]
+pub fn query_logs(query: Option<log_query::LogQuery>) -> Result<log_query::LogQueryResult, String> {
+    log_query::query_logs(&query.unwrap_or_default())
+}
+
 #[tauri::command]
 pub fn log_from_frontend(source: String, level: String, message: String) -> Result<(), String> {
     let log_source = match source.as_str() {
@@ -540,7 +798,9 @@ pub async fn install_backend_update(
     
     // 1. 停止SRA进程
     let _ = app_handle.emit("backend-update-progress", "stopping");
-    crate::process::stop_sra_process_command(app_handle.clone())?;
+    tokio::task::spawn_blocking(crate::process::stop_all_sra_processes)
+        .await
+        .map_err(|e| format!("Task join error: {}", e))??;
     
     // 等待进程完全停止
     tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
@@ -639,7 +899,7 @@ pub async fn install_backend_update(
     tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
     
     // 如果重启失败，尝试恢复备份
-    if let Err(e) = crate::process::start_sra_process_command(app_handle.clone(), None) {
+    if let Err(e) = crate::process::start_sra_process_command(crate::types::DEFAULT_INSTANCE, LaunchRequest::default()) {
         return Err(format!("Failed to restart backend: {}", e));
     }
     
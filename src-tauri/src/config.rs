@@ -7,14 +7,12 @@ use crate::encryption;
 
 // 获取配置目录路径
 fn get_config_dir() -> Result<PathBuf, String> {
-    let appdata = std::env::var("APPDATA").map_err(|_| "Failed to get APPDATA path")?;
-    Ok(PathBuf::from(appdata).join("SRA").join("configs"))
+    Ok(crate::paths::data_dir()?.join("configs"))
 }
 
 // 获取任务顺序文件路径
 fn get_task_order_file() -> Result<PathBuf, String> {
-    let appdata = std::env::var("APPDATA").map_err(|_| "Failed to get APPDATA path")?;
-    Ok(PathBuf::from(appdata).join("SRA").join("task-order.json"))
+    Ok(crate::paths::data_dir()?.join("task-order.json"))
 }
 
 // 将字符串中的非ASCII字符转换为Unicode转义序列
@@ -229,6 +227,7 @@ pub fn load_config(name: String) -> Result<serde_json::Value, String> {
             }
         }
     }
+    crate::redact::add_config_secrets(&config);
 
     Ok(config)
 }
@@ -247,6 +246,8 @@ pub fn save_config(config: serde_json::Value) -> Result<(), String> {
     fs::create_dir_all(&config_dir)
         .map_err(|e| format!("Failed to create config directory: {}", e))?;
 
+    crate::redact::add_config_secrets(&config);
+
     // 克隆配置以便修改
     let mut config_to_save = config.clone();
 
@@ -0,0 +1,22 @@
+// 命令执行共用的辅助函数
+// 局域网远程控制接口（remote_api.rs）、本地 IPC 接口（ipc.rs）和异步的 Tauri 命令都在 tokio 运行时中执行命令，
+// 远程控制接口还需要将结果转为 JSON
+
+use serde::Serialize;
+use serde_json::Value;
+
+// 将命令结果转为 JSON
+pub fn to_value<T: Serialize>(result: Result<T, String>) -> Result<Value, String> {
+    result.and_then(|value| serde_json::to_value(value).map_err(|e| format!("Failed to serialize: {}", e)))
+}
+
+// 可能阻塞的命令放到线程池执行
+pub async fn blocking<T, F>(command: F) -> Result<T, String>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, String> + Send + 'static,
+{
+    tokio::task::spawn_blocking(command)
+        .await
+        .map_err(|e| format!("Task join error: {}", e))?
+}
@@ -0,0 +1,177 @@
+// cron 表达式解析
+// 标准 5 段格式：分 时 日 月 周（周日为 0 或 7），支持 * 、列表 a,b、范围 a-b 和步长 */n、a-b/n，
+// 以及 @hourly / @daily / @weekly / @monthly 简写；按本地时间计算
+
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, TimeZone, Timelike};
+
+// 向后查找下一次运行时间的最大天数（覆盖 2 月 29 日等稀有日期）
+const MAX_SEARCH_DAYS: i64 = 366 * 5;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CronExpr {
+    minutes: Vec<u32>,
+    hours: Vec<u32>,
+    days: Vec<u32>,
+    months: Vec<u32>,
+    weekdays: Vec<u32>, // 0 = 周日
+    days_restricted: bool,
+    weekdays_restricted: bool,
+}
+
+impl CronExpr {
+    pub fn parse(expr: &str) -> Result<Self, String> {
+        let expanded = match expr.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            other => other,
+        };
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        if fields.len() != 5 {
+            return Err(format!("cron 表达式应为 5 段（分 时 日 月 周）: {}", expr));
+        }
+
+        let mut weekdays = parse_field(fields[4], 0, 7).map_err(|e| format!("周: {}", e))?;
+        // 7 与 0 都表示周日
+        for day in weekdays.iter_mut() {
+            if *day == 7 {
+                *day = 0;
+            }
+        }
+        weekdays.sort_unstable();
+        weekdays.dedup();
+
+        Ok(Self {
+            minutes: parse_field(fields[0], 0, 59).map_err(|e| format!("分: {}", e))?,
+            hours: parse_field(fields[1], 0, 23).map_err(|e| format!("时: {}", e))?,
+            days: parse_field(fields[2], 1, 31).map_err(|e| format!("日: {}", e))?,
+            months: parse_field(fields[3], 1, 12).map_err(|e| format!("月: {}", e))?,
+            weekdays,
+            days_restricted: fields[2] != "*",
+            weekdays_restricted: fields[4] != "*",
+        })
+    }
+
+    // 日期是否匹配（日和周都有限制时满足其一即可，与 cron 一致）
+    fn matches_date(&self, date: NaiveDate) -> bool {
+        if !self.months.contains(&date.month()) {
+            return false;
+        }
+        let day_ok = self.days.contains(&date.day());
+        let weekday_ok = self.weekdays.contains(&date.weekday().num_days_from_sunday());
+        match (self.days_restricted, self.weekdays_restricted) {
+            (true, true) => day_ok || weekday_ok,
+            (true, false) => day_ok,
+            (false, true) => weekday_ok,
+            (false, false) => true,
+        }
+    }
+
+    // 严格晚于 after 的下一次运行时间
+    pub fn next_after(&self, after: DateTime<Local>) -> Option<DateTime<Local>> {
+        let start = after.naive_local() + Duration::minutes(1);
+        let start_date = start.date();
+        for offset in 0..MAX_SEARCH_DAYS {
+            let date = start_date + Duration::days(offset);
+            if !self.matches_date(date) {
+                continue;
+            }
+            for &hour in &self.hours {
+                if offset == 0 && hour < start.hour() {
+                    continue;
+                }
+                for &minute in &self.minutes {
+                    if offset == 0 && hour == start.hour() && minute < start.minute() {
+                        continue;
+                    }
+                    // 夏令时跳过的时间不存在，继续查找
+                    let naive = date.and_hms_opt(hour, minute, 0)?;
+                    if let Some(time) = Local.from_local_datetime(&naive).earliest() {
+                        if time > after {
+                            return Some(time);
+                        }
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+// 解析一段，返回排序后的取值
+fn parse_field(field: &str, min: u32, max: u32) -> Result<Vec<u32>, String> {
+    let mut values = Vec::new();
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step.parse().map_err(|_| format!("无效的步长: {}", part))?;
+                if step == 0 {
+                    return Err(format!("步长不能为 0: {}", part));
+                }
+                (range, step)
+            }
+            None => (part, 1),
+        };
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((a, b)) = range.split_once('-') {
+            (parse_value(a, min, max)?, parse_value(b, min, max)?)
+        } else {
+            let value = parse_value(range, min, max)?;
+            // a/n 表示从 a 开始到最大值
+            if part.contains('/') { (value, max) } else { (value, value) }
+        };
+        if start > end {
+            return Err(format!("无效的范围: {}", part));
+        }
+        values.extend((start..=end).step_by(step as usize));
+    }
+    values.sort_unstable();
+    values.dedup();
+    Ok(values)
+}
+
+fn parse_value(value: &str, min: u32, max: u32) -> Result<u32, String> {
+    let parsed: u32 = value.parse().map_err(|_| format!("无效的值: {}", value))?;
+    if parsed < min || parsed > max {
+        return Err(format!("{} 超出范围 {}-{}", parsed, min, max));
+    }
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn local(s: &str) -> DateTime<Local> {
+        let naive = chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap();
+        Local.from_local_datetime(&naive).earliest().unwrap()
+    }
+
+    fn next(expr: &str, after: &str) -> String {
+        CronExpr::parse(expr).unwrap().next_after(local(after)).unwrap().format("%Y-%m-%d %H:%M").to_string()
+    }
+
+    #[test]
+    fn test_next_after() {
+        assert_eq!(next("30 4 * * *", "2025-11-29 04:29"), "2025-11-29 04:30");
+        assert_eq!(next("30 4 * * *", "2025-11-29 04:30"), "2025-11-30 04:30");
+        assert_eq!(next("*/15 9-10 * * *", "2025-11-29 10:50"), "2025-11-30 09:00");
+        // 2025-12-01 是周一
+        assert_eq!(next("0 5 * * 1", "2025-11-29 12:00"), "2025-12-01 05:00");
+        assert_eq!(next("@weekly", "2025-11-29 12:00"), "2025-11-30 00:00");
+        // 日和周同时限制时满足其一即可
+        assert_eq!(next("0 0 15 * 1", "2025-11-29 12:00"), "2025-12-01 00:00");
+        assert_eq!(next("0 0 29 2 *", "2025-03-01 00:00"), "2028-02-29 00:00");
+    }
+
+    #[test]
+    fn test_invalid_expressions() {
+        assert!(CronExpr::parse("* * * *").is_err());
+        assert!(CronExpr::parse("60 * * * *").is_err());
+        assert!(CronExpr::parse("*/0 * * * *").is_err());
+        assert!(CronExpr::parse("5-1 * * * *").is_err());
+        assert!(CronExpr::parse("0 4 * * 7").is_ok());
+    }
+}
@@ -0,0 +1,111 @@
+// 事件分发模块
+// 统一向前端（Tauri）和进程内订阅者（无界面模式、测试）发送事件
+
+use serde::Serialize;
+use std::sync::{Arc, Mutex, MutexGuard};
+#[cfg(feature = "gui")]
+use tauri::Emitter;
+
+type Subscriber = Arc<dyn Fn(&str, &serde_json::Value) + Send + Sync>;
+
+struct EventHub {
+    #[cfg(feature = "gui")]
+    app_handle: Option<tauri::AppHandle>,
+    subscribers: Vec<(u64, Subscriber)>,
+    next_id: u64,
+}
+
+static EVENT_HUB: Mutex<EventHub> = Mutex::new(EventHub {
+    #[cfg(feature = "gui")]
+    app_handle: None,
+    subscribers: Vec::new(),
+    next_id: 1,
+});
+
+// 订阅者回调在锁外执行，回调 panic 不会使锁失效；即使失效也继续使用其中的数据
+fn hub() -> MutexGuard<'static, EventHub> {
+    EVENT_HUB.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+// 设置 Tauri 应用句柄（GUI 模式下在 setup 中调用）
+#[cfg(feature = "gui")]
+pub fn set_app_handle(app_handle: tauri::AppHandle) {
+    hub().app_handle = Some(app_handle);
+}
+
+// 退出程序：GUI 模式下通过 Tauri 退出（执行正常的关闭流程），否则直接结束进程
+pub fn exit_app(code: i32) {
+    #[cfg(feature = "gui")]
+    if let Some(app_handle) = hub().app_handle.clone() {
+        app_handle.exit(code);
+        return;
+    }
+    std::process::exit(code)
+}
+
+// 订阅所有事件，返回订阅 ID（回调在发送方线程中执行，不能阻塞；回调内写日志会再次触发 log-message 事件）
+pub fn subscribe<F>(callback: F) -> u64
+where
+    F: Fn(&str, &serde_json::Value) + Send + Sync + 'static,
+{
+    let mut hub = hub();
+    let id = hub.next_id;
+    hub.next_id += 1;
+    hub.subscribers.push((id, Arc::new(callback)));
+    id
+}
+
+// 取消订阅（正在进行的发送仍可能调用一次回调）
+pub fn unsubscribe(id: u64) {
+    hub().subscribers.retain(|(sub_id, _)| *sub_id != id);
+}
+
+// 发送事件：在锁内复制订阅者和应用句柄，锁外调用，各线程的事件互不阻塞
+pub fn emit<S: Serialize + Clone>(event: &str, payload: S) {
+    let subscribers: Vec<Subscriber> = {
+        let hub = hub();
+        hub.subscribers.iter().map(|(_, callback)| callback.clone()).collect()
+    };
+
+    if !subscribers.is_empty() {
+        if let Ok(value) = serde_json::to_value(&payload) {
+            for callback in subscribers {
+                callback(event, &value);
+            }
+        }
+    }
+
+    #[cfg(feature = "gui")]
+    if let Some(app_handle) = hub().app_handle.clone() {
+        let _ = app_handle.emit(event, payload);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_panicking_subscriber_does_not_break_the_hub() {
+        let panicking = subscribe(|event, _| {
+            if event == "test-subscriber-panic" {
+                panic!("订阅者出错");
+            }
+        });
+        assert!(std::panic::catch_unwind(|| emit("test-subscriber-panic", ())).is_err());
+        unsubscribe(panicking);
+
+        // 之后的订阅和发送不受影响
+        let received = Arc::new(AtomicUsize::new(0));
+        let counter = received.clone();
+        let sub = subscribe(move |event, _| {
+            if event == "test-after-panic" {
+                counter.fetch_add(1, Ordering::SeqCst);
+            }
+        });
+        emit("test-after-panic", ());
+        unsubscribe(sub);
+        assert_eq!(received.load(Ordering::SeqCst), 1);
+    }
+}
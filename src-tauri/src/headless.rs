@@ -0,0 +1,121 @@
+// 无界面模式
+// 供命令行程序（SRA-CE-cli）使用：不创建窗口，复用配置、进程、日志和设置模块，
+// 日志通过进程内事件订阅转发给调用方
+
+use crate::config;
+use crate::events;
+use crate::ipc;
+use crate::logger;
+use crate::post_actions;
+use crate::process;
+use crate::remote_api;
+use crate::scheduler;
+use crate::task_timeout;
+
+pub use crate::logger::{LogLevel, LogMessage, LogSource};
+pub use crate::redact::CONFIG_SECRET_KEYS;
+pub use crate::run_plan::{EntryCondition, EntryReport, EntryStatus, FailurePolicy, PlanEntry, PlanReport, PlanResult, RunPlan};
+pub use crate::types::{LaunchRequest, SraStatus, DEFAULT_INSTANCE};
+
+// 初始化日志系统和任务超时检测；此后完成后操作不再执行退出程序
+pub fn init() -> Result<(), String> {
+    post_actions::set_headless(true);
+    logger::init_logger()?;
+    task_timeout::start_monitor();
+    Ok(())
+}
+
+// 启动计划任务调度、远程控制接口和本地 IPC 接口（常驻模式，按设置启用）
+pub fn start_services() {
+    scheduler::start_scheduler();
+    remote_api::start_server();
+    ipc::start_server();
+}
+
+// 订阅日志（包括 SRA-cli 输出解析后的日志），返回订阅 ID；回调内不能写日志
+pub fn subscribe_logs<F>(callback: F) -> u64
+where
+    F: Fn(&LogMessage) + Send + Sync + 'static,
+{
+    events::subscribe(move |event, payload| {
+        if event != "log-message" {
+            return;
+        }
+        if let Ok(message) = serde_json::from_value::<LogMessage>(payload.clone()) {
+            callback(&message);
+        }
+    })
+}
+
+pub fn unsubscribe(id: u64) {
+    events::unsubscribe(id);
+}
+
+// 配置
+
+pub fn config_list() -> Result<Vec<String>, String> {
+    config::get_config_list()
+}
+
+pub fn load_config(name: &str) -> Result<serde_json::Value, String> {
+    config::load_config(name.to_string())
+}
+
+pub fn save_config(config: serde_json::Value) -> Result<(), String> {
+    config::save_config(config)
+}
+
+// 创建配置（已存在时失败，避免覆盖）
+pub fn create_config(name: &str) -> Result<(), String> {
+    if config::get_config_list()?.iter().any(|c| c == name) {
+        return Err(format!("配置 {} 已存在", name));
+    }
+    config::create_new_config(name.to_string())
+}
+
+pub fn delete_config(name: &str) -> Result<(), String> {
+    config::delete_config(name.to_string())
+}
+
+pub fn load_task_order() -> Result<Vec<String>, String> {
+    config::load_task_order()
+}
+
+// 进程
+
+pub fn start(instance_id: &str, request: LaunchRequest) -> Result<(), String> {
+    process::start_sra_process_command(instance_id, request)
+}
+
+pub fn stop(instance_id: &str) -> Result<(), String> {
+    process::stop_sra_process_command(instance_id)
+}
+
+pub fn stop_all() -> Result<(), String> {
+    process::stop_all_sra_processes()
+}
+
+pub fn instance_status(instance_id: &str) -> Result<String, String> {
+    process::get_instance_status(instance_id)
+}
+
+// 执行计划并等待结束；run_post_actions 为 true 且未被取消时，等待完成后操作的倒计时和执行
+pub fn run_plan(mut plan: RunPlan, run_post_actions: bool) -> Result<PlanReport, String> {
+    // 计划结束后进程可能马上退出，完成后操作不交给后台线程
+    plan.post_actions = false;
+    let configs: Vec<String> = plan.entries.iter().map(|e| e.config.clone()).collect();
+    let report = crate::run_plan::execute_plan(plan)?;
+    if run_post_actions && report.result != PlanResult::Cancelled {
+        post_actions::schedule_and_wait(post_actions::actions_for_configs(&configs))?;
+    }
+    Ok(report)
+}
+
+// 按任务顺序生成计划
+pub fn task_order_plan(instance_id: &str) -> Result<RunPlan, String> {
+    RunPlan::from_task_order(Some(instance_id.to_string()))
+}
+
+pub fn cancel_plan(instance_id: &str) -> Result<bool, String> {
+    crate::run_plan::cancel_plan(instance_id)
+}
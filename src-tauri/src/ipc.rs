@@ -0,0 +1,421 @@
+// 本地 IPC 接口
+// 供本机脚本控制正在运行的程序，不开放网络端口：Windows 上为命名管道 \\.\pipe\sra-ce，
+// 其他平台为 Unix 域套接字（$XDG_RUNTIME_DIR 或临时目录下按用户区分的子目录中的 sra-ce.sock）。默认关闭，
+// 管道和套接字都只允许当前用户连接
+//
+// 按行收发 JSON，每行一个请求 / 响应，命令和参数与前端 invoke 相同（参数名为 camelCase）:
+//   {"id": 1, "command": "task_run", "args": {"configName": "Default"}}
+//   {"id": 1, "ok": true, "data": null}
+//   {"id": 1, "ok": false, "error": "..."}
+// id 可以是任意 JSON 值，响应原样带回；同一连接上的请求并发执行，响应顺序不一定与请求一致
+
+use crate::config;
+use crate::control::{blocking, to_value};
+use crate::log_query::{self, LogQuery};
+use crate::logger::{self, log, LogLevel, LogSource};
+use crate::process;
+use crate::run_history::{self, RunQuery};
+use crate::run_plan::{self, RunPlan};
+use crate::settings;
+use crate::types::LaunchRequest;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::thread;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::sync::mpsc;
+
+#[cfg(target_os = "windows")]
+const PIPE_NAME: &str = r"\\.\pipe\sra-ce";
+
+// IPC 端点（命名管道名或套接字路径）
+pub fn endpoint() -> String {
+    #[cfg(target_os = "windows")]
+    {
+        PIPE_NAME.to_string()
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        // $XDG_RUNTIME_DIR 本身仅当前用户可访问；临时目录为所有用户共享，使用按用户区分的子目录
+        let dir = std::env::var_os("XDG_RUNTIME_DIR")
+            .map(std::path::PathBuf::from)
+            .unwrap_or_else(|| std::env::temp_dir().join(format!("sra-ce-{}", unsafe { libc::geteuid() })));
+        dir.join("sra-ce.sock").to_string_lossy().to_string()
+    }
+}
+
+// 程序启动时按设置启动 IPC 服务（设置修改后需重启程序生效）
+pub fn start_server() {
+    let enabled = settings::load_ce_settings_or_default().ipc_enabled;
+    if !enabled {
+        return;
+    }
+    let endpoint = endpoint();
+    match start_at(&endpoint) {
+        Ok(()) => {
+            let _ = log(LogSource::Backend, LogLevel::INFO, format!("本地 IPC 接口已启动: {}", endpoint));
+        }
+        Err(e) => {
+            let _ = log(LogSource::Backend, LogLevel::ERR, format!("启动本地 IPC 接口失败: {}", e));
+        }
+    }
+}
+
+// 在指定端点启动服务（监听失败时返回错误）
+pub fn start_at(endpoint: &str) -> Result<(), String> {
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(1)
+        .enable_all()
+        .build()
+        .map_err(|e| format!("Failed to create runtime: {}", e))?;
+    let listener = runtime.block_on(Listener::bind(endpoint))?;
+    thread::spawn(move || runtime.block_on(listener.serve()));
+    Ok(())
+}
+
+#[cfg(not(target_os = "windows"))]
+struct Listener(tokio::net::UnixListener);
+
+#[cfg(not(target_os = "windows"))]
+impl Listener {
+    async fn bind(path: &str) -> Result<Self, String> {
+        // 套接字创建时的权限受 umask 影响，放在仅当前用户可访问的目录中，其他用户无法连接或抢先占用
+        if let Some(dir) = std::path::Path::new(path).parent() {
+            ensure_private_dir(dir)?;
+        }
+        // 上次异常退出时留下的套接字文件
+        let _ = std::fs::remove_file(path);
+        let listener = tokio::net::UnixListener::bind(path).map_err(|e| format!("无法监听 {}: {}", path, e))?;
+        Ok(Self(listener))
+    }
+
+    async fn serve(self) {
+        while let Ok((stream, _)) = self.0.accept().await {
+            tokio::spawn(handle_client(stream));
+        }
+    }
+}
+
+// 创建仅当前用户可访问的目录；目录已存在时检查所有者和权限，防止其他用户预先创建同名目录
+#[cfg(not(target_os = "windows"))]
+fn ensure_private_dir(dir: &std::path::Path) -> Result<(), String> {
+    use std::os::unix::fs::{DirBuilderExt, MetadataExt};
+    match std::fs::DirBuilder::new().mode(0o700).create(dir) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {}
+        Err(e) => return Err(format!("无法创建目录 {}: {}", dir.display(), e)),
+    }
+    let metadata = std::fs::symlink_metadata(dir).map_err(|e| format!("无法读取目录 {}: {}", dir.display(), e))?;
+    if !metadata.is_dir() || metadata.uid() != unsafe { libc::geteuid() } || metadata.mode() & 0o077 != 0 {
+        return Err(format!("{} 不是仅当前用户可访问的目录", dir.display()));
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "windows")]
+struct Listener {
+    name: String,
+    security: PipeSecurity,
+    server: tokio::net::windows::named_pipe::NamedPipeServer,
+}
+
+#[cfg(target_os = "windows")]
+impl Listener {
+    async fn bind(name: &str) -> Result<Self, String> {
+        use tokio::net::windows::named_pipe::ServerOptions;
+        let security = PipeSecurity::current_user()?;
+        // first_pipe_instance 防止其他进程抢先创建同名管道；默认拒绝远程客户端
+        let server = security
+            .create(ServerOptions::new().first_pipe_instance(true), name)
+            .map_err(|e| format!("无法创建命名管道 {}: {}", name, e))?;
+        Ok(Self { name: name.to_string(), security, server })
+    }
+
+    async fn serve(self) {
+        use tokio::net::windows::named_pipe::ServerOptions;
+        let mut server = self.server;
+        loop {
+            if server.connect().await.is_err() {
+                return;
+            }
+            // 当前实例交给客户端，再创建新实例等待下一个连接
+            let client = server;
+            server = match self.security.create(&ServerOptions::new(), &self.name) {
+                Ok(server) => server,
+                Err(_) => return,
+            };
+            tokio::spawn(handle_client(client));
+        }
+    }
+}
+
+// 命名管道的安全描述符：默认 DACL 允许同一台机器上的其他用户连接，改为只允许当前用户
+#[cfg(target_os = "windows")]
+struct PipeSecurity(windows::Win32::Security::PSECURITY_DESCRIPTOR);
+
+// 安全描述符创建后只读，可以在线程间传递
+#[cfg(target_os = "windows")]
+unsafe impl Send for PipeSecurity {}
+
+#[cfg(target_os = "windows")]
+impl PipeSecurity {
+    fn current_user() -> Result<Self, String> {
+        use windows::core::{PCWSTR, PWSTR};
+        use windows::Win32::Foundation::{CloseHandle, LocalFree, HANDLE, HLOCAL};
+        use windows::Win32::Security::Authorization::{
+            ConvertSidToStringSidW, ConvertStringSecurityDescriptorToSecurityDescriptorW, SDDL_REVISION_1,
+        };
+        use windows::Win32::Security::{GetTokenInformation, TokenUser, PSECURITY_DESCRIPTOR, TOKEN_QUERY, TOKEN_USER};
+        use windows::Win32::System::Threading::{GetCurrentProcess, OpenProcessToken};
+
+        unsafe {
+            // 当前用户的 SID
+            let mut token = HANDLE::default();
+            OpenProcessToken(GetCurrentProcess(), TOKEN_QUERY, &mut token)
+                .map_err(|e| format!("Failed to open process token: {}", e))?;
+            let mut size = 0;
+            let _ = GetTokenInformation(token, TokenUser, None, 0, &mut size);
+            let mut buffer = vec![0u64; (size as usize).div_ceil(8)]; // TOKEN_USER 需要按指针对齐
+            let result = GetTokenInformation(token, TokenUser, Some(buffer.as_mut_ptr() as *mut _), size, &mut size);
+            let _ = CloseHandle(token);
+            result.map_err(|e| format!("Failed to query token user: {}", e))?;
+            let user = &*(buffer.as_ptr() as *const TOKEN_USER);
+
+            let mut sid = PWSTR::null();
+            ConvertSidToStringSidW(user.User.Sid, &mut sid).map_err(|e| format!("Failed to convert SID: {}", e))?;
+            let sid_string = sid.to_string();
+            let _ = LocalFree(HLOCAL(sid.0 as *mut _));
+            let sid_string = sid_string.map_err(|e| format!("Failed to convert SID: {}", e))?;
+
+            // 受保护的 DACL（不继承），只有当前用户拥有访问权限
+            let sddl: Vec<u16> = format!("D:P(A;;GA;;;{})", sid_string)
+                .encode_utf16()
+                .chain(std::iter::once(0))
+                .collect();
+            let mut descriptor = PSECURITY_DESCRIPTOR(std::ptr::null_mut());
+            ConvertStringSecurityDescriptorToSecurityDescriptorW(PCWSTR(sddl.as_ptr()), SDDL_REVISION_1, &mut descriptor, None)
+                .map_err(|e| format!("Failed to create security descriptor: {}", e))?;
+            Ok(Self(descriptor))
+        }
+    }
+
+    // 使用该安全描述符创建管道实例
+    fn create(
+        &self,
+        options: &tokio::net::windows::named_pipe::ServerOptions,
+        name: &str,
+    ) -> std::io::Result<tokio::net::windows::named_pipe::NamedPipeServer> {
+        use windows::Win32::Security::SECURITY_ATTRIBUTES;
+        let mut attributes = SECURITY_ATTRIBUTES {
+            nLength: std::mem::size_of::<SECURITY_ATTRIBUTES>() as u32,
+            lpSecurityDescriptor: self.0 .0,
+            bInheritHandle: false.into(),
+        };
+        unsafe { options.create_with_security_attributes_raw(name, &mut attributes as *mut _ as *mut std::ffi::c_void) }
+    }
+}
+
+#[cfg(target_os = "windows")]
+impl Drop for PipeSecurity {
+    fn drop(&mut self) {
+        use windows::Win32::Foundation::{LocalFree, HLOCAL};
+        unsafe {
+            let _ = LocalFree(HLOCAL(self.0 .0));
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct IpcRequest {
+    #[serde(default)]
+    id: Value,
+    command: String,
+    #[serde(default)]
+    args: Value,
+}
+
+// 各命令的参数（与前端 invoke 的参数名相同）
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct CommandArgs {
+    instance_id: Option<String>,
+    profile: Option<String>,
+    arguments: Option<String>,
+    config_name: Option<String>,
+    input: Option<String>,
+    timeout_ms: Option<u64>,
+    plan: Option<RunPlan>,
+    query: Option<Value>, // 查询条件，类型因命令而异
+    since: Option<u64>,
+    limit: Option<usize>,
+}
+
+// 将查询条件解析为命令需要的类型
+fn parse_query<T: DeserializeOwned>(query: Option<Value>) -> Result<Option<T>, String> {
+    query
+        .map(|query| serde_json::from_value(query).map_err(|e| format!("无效的查询条件: {}", e)))
+        .transpose()
+}
+
+// 处理一个连接：逐行读取请求，每个请求单独执行，响应经由通道按完成顺序写回
+async fn handle_client<S: AsyncRead + AsyncWrite + Send + 'static>(stream: S) {
+    let (reader, mut writer) = tokio::io::split(stream);
+    let (responses_tx, mut responses_rx) = mpsc::unbounded_channel::<String>();
+    let writer_task = tokio::spawn(async move {
+        while let Some(mut line) = responses_rx.recv().await {
+            line.push('\n');
+            if writer.write_all(line.as_bytes()).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut lines = BufReader::new(reader).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let responses_tx = responses_tx.clone();
+        tokio::spawn(async move {
+            let response = match serde_json::from_str::<IpcRequest>(&line) {
+                Ok(request) => match dispatch(&request.command, request.args).await {
+                    Ok(data) => json!({ "id": request.id, "ok": true, "data": data }),
+                    Err(e) => json!({ "id": request.id, "ok": false, "error": e }),
+                },
+                Err(e) => json!({ "id": null, "ok": false, "error": format!("无效的请求: {}", e) }),
+            };
+            let _ = responses_tx.send(response.to_string());
+        });
+    }
+    // 客户端关闭写端后，等待进行中的请求写回响应
+    drop(responses_tx);
+    let _ = writer_task.await;
+}
+
+// 执行命令（与 invoke_handler 中的同名命令调用相同的函数）
+async fn dispatch(command: &str, args: Value) -> Result<Value, String> {
+    let args: CommandArgs = if args.is_null() {
+        CommandArgs::default()
+    } else {
+        serde_json::from_value(args).map_err(|e| format!("无效的参数: {}", e))?
+    };
+    let CommandArgs { instance_id, profile, arguments, config_name, input, timeout_ms, plan, query, since, limit } = args;
+    let required = |value: Option<String>, name: &str| value.ok_or_else(|| format!("缺少参数 {}", name));
+
+    let instance = process::instance_key(instance_id.clone());
+
+    match command {
+        "get_sra_status" => to_value(process::get_sra_status()),
+        "get_sra_state_history" => to_value(process::get_sra_state_history(&instance)),
+        "get_all_logs" => to_value(logger::get_all_logs()),
+        "get_logs_since" => to_value(logger::get_logs_since(since.unwrap_or(0), limit)),
+        "get_config_list" => to_value(blocking(config::get_config_list).await),
+        "load_task_order" => to_value(config::load_task_order()),
+        "get_active_plans" => to_value(Ok(run_plan::get_active_plans())),
+        "query_run_history" => {
+            let query: Option<RunQuery> = parse_query(query)?;
+            to_value(blocking(move || run_history::query_runs(&query.unwrap_or_default())).await)
+        }
+        "query_logs" => {
+            let query: Option<LogQuery> = parse_query(query)?;
+            to_value(blocking(move || log_query::query_logs(&query.unwrap_or_default())).await)
+        }
+        "start_sra_process_command" => to_value(
+            blocking(move || process::start_sra_process_command(&instance, LaunchRequest { profile, arguments })).await,
+        ),
+        "stop_sra_process_command" => to_value(blocking(move || process::stop_sra_process_command(&instance)).await),
+        "restart_sra_process_command" => to_value(
+            blocking(move || process::restart_sra_process_command(&instance, LaunchRequest { profile, arguments })).await,
+        ),
+        "send_input_to_sra" => {
+            let input = required(input, "input")?;
+            to_value(process::send_input_to_sra(&instance, input))
+        }
+        "send_command_and_wait" => {
+            let input = required(input, "input")?;
+            to_value(blocking(move || process::send_command_and_wait(&instance, input, timeout_ms)).await)
+        }
+        "task_run" => to_value(process::task_run(&instance, config_name)),
+        "task_stop" => to_value(process::task_stop(&instance)),
+        "start_run_plan" => to_value(
+            blocking(move || {
+                let plan = match plan {
+                    Some(plan) => plan,
+                    None => RunPlan::from_task_order(instance_id)?,
+                };
+                run_plan::start_plan(plan)
+            })
+            .await,
+        ),
+        "cancel_run_plan" => to_value(blocking(move || run_plan::cancel_plan(&instance)).await),
+        _ => Err(format!("未知命令: {}", command)),
+    }
+}
+
+// Windows 上使用命名管道，这里只测试 Unix 域套接字
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::mock_backend::tests::{setup, BASIC_SESSION};
+    use crate::process;
+    use crate::types::{SraStatus, DEFAULT_INSTANCE};
+    use std::os::unix::fs::PermissionsExt;
+    use std::time::Duration;
+
+    #[test]
+    fn test_socket_dir_must_be_private() {
+        let dir = std::env::temp_dir().join(format!("sra-ce-test-{}-shared", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o777)).unwrap();
+        // 其他用户可写的目录不能放置套接字
+        assert!(start_at(dir.join("sra-ce.sock").to_str().unwrap()).is_err());
+        std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o700)).unwrap();
+        assert!(ensure_private_dir(&dir).is_ok());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_ipc_commands_with_correlation_ids() {
+        use std::io::{BufRead, BufReader, Write};
+        use std::os::unix::net::UnixStream;
+        let (_guard, _mock) = setup(BASIC_SESSION);
+        let dir = std::env::temp_dir().join(format!("sra-ce-test-{}-ipc", std::process::id()));
+        let path = dir.join("sra-ce.sock");
+        start_at(path.to_str().unwrap()).unwrap();
+        // 套接字所在目录仅当前用户可访问
+        assert_eq!(std::fs::metadata(&dir).unwrap().permissions().mode() & 0o777, 0o700);
+
+        let mut stream = UnixStream::connect(&path).unwrap();
+        stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut call = |request: &str| -> serde_json::Value {
+            writeln!(stream, "{}", request).unwrap();
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            serde_json::from_str(&line).unwrap()
+        };
+
+        let started = call(r#"{"id": "start", "command": "start_sra_process_command", "args": {"instanceId": null}}"#);
+        assert_eq!(started["id"], "start");
+        assert_eq!(started["ok"], true);
+        assert!(process::wait_until(Duration::from_secs(5), || {
+            process::get_instance_status(DEFAULT_INSTANCE).unwrap() == SraStatus::Idle.as_str()
+        }));
+
+        let run = call(r#"{"id": 1, "command": "task_run", "args": {"configName": "Default"}}"#);
+        assert_eq!(run["id"], 1);
+        assert_eq!(run["ok"], true, "{}", run);
+        let status = call(r#"{"id": 2, "command": "get_sra_status"}"#);
+        assert!(status["data"].as_array().unwrap().iter().any(|s| s["instance_id"] == DEFAULT_INSTANCE));
+
+        let missing = call(r#"{"id": 3, "command": "send_input_to_sra"}"#);
+        assert_eq!((missing["id"].clone(), missing["ok"].clone()), (serde_json::json!(3), serde_json::json!(false)));
+        let unknown = call(r#"{"id": 4, "command": "format_disk"}"#);
+        assert!(unknown["error"].as_str().unwrap().contains("format_disk"));
+        let invalid = call("not json");
+        assert!(invalid["id"].is_null() && invalid["ok"] == false);
+
+        process::stop_sra_process_command(DEFAULT_INSTANCE).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -1,21 +1,54 @@
 // Tauri 应用主入口
 // 模块化结构：将功能分离到不同模块中
+// 不启用 gui 特性时（SRA-CE-cli）只使用无界面模式用到的部分函数
+#![cfg_attr(not(feature = "gui"), allow(dead_code))]
 
 mod types;
 mod process;
 mod config;
+#[cfg(feature = "gui")]
 mod commands;
 mod logger;
+mod log_rotation;
+mod log_query;
+mod redact;
 mod sra_parser;
 mod wallpaper;
 mod announcement;
 mod settings;
 mod encryption;
 mod shortcut;
+mod resolver;
+mod watchdog;
+mod state;
+mod events;
+mod backend;
+mod mock_backend;
+mod args;
+mod line_codec;
+mod transcript;
+mod run_history;
+mod cron;
+mod scheduler;
+mod reset_clock;
+mod task_timeout;
+mod post_actions;
+mod run_plan;
+mod websocket;
+mod control;
+mod remote_api;
+mod ipc;
+mod paths;
 
+// 无界面模式（供 SRA-CE-cli 使用）
+pub mod headless;
+
+#[cfg(feature = "gui")]
 use std::thread;
+#[cfg(feature = "gui")]
 use std::time::Duration;
 
+#[cfg(feature = "gui")]
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
@@ -36,10 +69,48 @@ pub fn run() {
             commands::stop_sra_process_command,
             commands::restart_sra_process_command,
             commands::get_sra_status,
+            commands::get_sra_state_history,
             commands::send_input_to_sra,
+            commands::send_command_and_wait,
             commands::task_run,
             commands::task_stop,
+            commands::diagnose_sra_cli_path,
+            commands::save_sra_cli_path,
+            commands::save_watchdog_settings,
+            commands::save_stop_policy,
+            commands::get_launch_profiles,
+            commands::save_launch_profile,
+            commands::delete_launch_profile,
+            commands::set_default_launch_profile,
+            commands::save_transcript_settings,
+            commands::list_transcripts,
+            commands::replay_transcript,
+            commands::query_run_history,
+            commands::query_logs,
+            commands::get_schedules,
+            commands::save_schedule,
+            commands::delete_schedule,
+            commands::get_upcoming_runs,
+            commands::run_schedule_now,
+            commands::get_reset_clock,
+            commands::save_server_region,
+            commands::save_task_timeout_settings,
+            commands::save_post_action_settings,
+            commands::run_post_actions,
+            commands::cancel_post_actions,
+            commands::start_run_plan,
+            commands::cancel_run_plan,
+            commands::get_active_plans,
+            commands::save_remote_api_settings,
+            commands::save_ipc_enabled,
+            commands::generate_remote_api_token,
+            commands::get_remote_api_address,
             commands::get_all_logs,
+            commands::get_logs_since,
+            commands::save_log_retention_settings,
+            commands::save_log_level_settings,
+            commands::save_redaction_settings,
+            commands::save_log_buffer_capacity,
             commands::log_from_frontend,
             commands::set_wallpaper,
             commands::reset_wallpaper,
@@ -66,22 +137,20 @@ pub fn run() {
             commands::run_elevated_command
         ])
         .setup(|app| {
+            // 事件发送到前端
+            events::set_app_handle(app.handle().clone());
+            
             // 初始化日志系统
-            if let Err(e) = logger::init_logger(app.handle().clone()) {
+            if let Err(e) = logger::init_logger() {
                 eprintln!("Failed to initialize logger: {}", e);
             }
             
-            // 初始化 SRA 日志解析器
-            sra_parser::init_parser();
-            
             // 异步启动SRA进程，不阻塞UI加载
-            let app_handle = app.handle().clone();
             thread::spawn(move || {
                 // 短暂延迟，让UI先加载
                 thread::sleep(Duration::from_millis(500));
-                if let Err(e) = process::start_sra_process_command(app_handle.clone(), None) {
-                    use tauri::Emitter;
-                    let _ = app_handle.emit("sra-status-changed", "error");
+                // 启动失败时状态机已切换为 error 并通知前端
+                if let Err(e) = process::start_sra_process_command(types::DEFAULT_INSTANCE, types::LaunchRequest::default()) {
                     let _ = logger::log(
                         logger::LogSource::Backend,
                         logger::LogLevel::ERR,
@@ -90,6 +159,16 @@ pub fn run() {
                 }
             });
             
+            // 启动计划任务调度和任务超时检测
+            scheduler::start_scheduler();
+            task_timeout::start_monitor();
+
+            // 按设置启动远程控制接口（默认关闭）
+            remote_api::start_server();
+
+            // 本地 IPC 接口（供本机脚本使用）
+            ipc::start_server();
+            
             Ok(())
         })
         .run(tauri::generate_context!())
@@ -0,0 +1,214 @@
+// SRA-cli 输出的分行与解码
+// 先按字节分行再逐行解码，避免多字节字符被读取块截断；
+// 支持 UTF-8 / GBK（Python 环境未设置 PYTHONIOENCODING 时的默认编码）以及 \r 进度刷新
+
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+
+// 输出编码
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum OutputEncoding {
+    #[default]
+    Auto, // 默认 UTF-8，遇到非法 UTF-8 且能按 GBK 正确解码的行时切换为 GBK
+    Utf8,
+    Gbk,
+}
+
+// \r 的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum CarriageReturnMode {
+    #[default]
+    Overwrite, // 与终端一致，\r 之后的内容覆盖之前的内容，只保留每行最后一次刷新
+    Split,     // 每次 \r 刷新都作为单独的一行输出
+}
+
+// 缓冲区中已消费的部分超过该大小时才整理，避免每行都移动数据
+const COMPACT_THRESHOLD: usize = 8192;
+
+pub struct LineCodec {
+    encoding: OutputEncoding, // 配置的编码
+    gbk_detected: bool,       // Auto 模式下是否已切换为 GBK
+    cr_mode: CarriageReturnMode,
+    buffer: Vec<u8>,
+    start: usize, // 当前未完成行的起始位置
+    scanned: usize, // 已查找过分隔符的位置
+}
+
+impl LineCodec {
+    pub fn new(encoding: OutputEncoding, cr_mode: CarriageReturnMode) -> Self {
+        Self {
+            encoding,
+            gbk_detected: false,
+            cr_mode,
+            buffer: Vec::with_capacity(4096),
+            start: 0,
+            scanned: 0,
+        }
+    }
+
+    // 当前实际使用的编码
+    pub fn active_encoding(&self) -> OutputEncoding {
+        match self.encoding {
+            OutputEncoding::Auto if self.gbk_detected => OutputEncoding::Gbk,
+            OutputEncoding::Auto => OutputEncoding::Utf8,
+            encoding => encoding,
+        }
+    }
+
+    // 写入读取到的字节，完整的行追加到 lines
+    pub fn push(&mut self, chunk: &[u8], lines: &mut Vec<String>) {
+        if self.start == self.buffer.len() {
+            self.clear_partial();
+        } else if self.start > COMPACT_THRESHOLD {
+            self.buffer.drain(..self.start);
+            self.scanned -= self.start;
+            self.start = 0;
+        }
+        self.buffer.extend_from_slice(chunk);
+
+        while let Some(offset) = self.find_delimiter() {
+            let end = self.scanned + offset;
+            let line_start = self.start;
+            self.start = end + 1;
+            self.scanned = self.start;
+            self.emit_line(line_start, end, lines);
+        }
+        self.scanned = self.buffer.len();
+    }
+
+    // 查找下一个行分隔符（Split 模式下 \r 也作为分隔符）
+    fn find_delimiter(&self) -> Option<usize> {
+        let rest = &self.buffer[self.scanned..];
+        match self.cr_mode {
+            CarriageReturnMode::Overwrite => rest.iter().position(|&b| b == b'\n'),
+            CarriageReturnMode::Split => rest.iter().position(|&b| b == b'\n' || b == b'\r'),
+        }
+    }
+
+    // 尚未结束的行（用于检测不换行的 sra> 提示符）
+    pub fn partial(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(self.visible(self.start, self.buffer.len()))
+    }
+
+    // 丢弃尚未结束的行
+    pub fn clear_partial(&mut self) {
+        self.buffer.clear();
+        self.start = 0;
+        self.scanned = 0;
+    }
+
+    // 输入结束，输出剩余的内容
+    pub fn finish(&mut self, lines: &mut Vec<String>) {
+        if self.start < self.buffer.len() {
+            let (start, end) = (self.start, self.buffer.len());
+            self.emit_line(start, end, lines);
+        }
+        self.clear_partial();
+    }
+
+    // 去掉行尾的 \r，Overwrite 模式下只保留最后一次刷新的内容
+    fn visible(&self, start: usize, end: usize) -> &[u8] {
+        let mut line = &self.buffer[start..end];
+        while let Some((&b'\r', rest)) = line.split_last() {
+            line = rest;
+        }
+        if self.cr_mode == CarriageReturnMode::Overwrite {
+            if let Some(pos) = line.iter().rposition(|&b| b == b'\r') {
+                line = &line[pos + 1..];
+            }
+        }
+        line
+    }
+
+    fn emit_line(&mut self, start: usize, end: usize, lines: &mut Vec<String>) {
+        let (text, switch_to_gbk) = {
+            let bytes = self.visible(start, end);
+            if bytes.is_empty() {
+                return;
+            }
+            decode(bytes, self.encoding, self.gbk_detected)
+        };
+        if switch_to_gbk {
+            self.gbk_detected = true;
+        }
+        lines.push(text);
+    }
+}
+
+// 解码一行，返回文本以及 Auto 模式下是否应切换为 GBK
+fn decode(bytes: &[u8], encoding: OutputEncoding, gbk_detected: bool) -> (String, bool) {
+    match encoding {
+        OutputEncoding::Gbk => (decode_gbk(bytes), false),
+        OutputEncoding::Auto if gbk_detected => (decode_gbk(bytes), false),
+        OutputEncoding::Utf8 => (String::from_utf8_lossy(bytes).into_owned(), false),
+        OutputEncoding::Auto => match std::str::from_utf8(bytes) {
+            Ok(text) => (text.to_string(), false),
+            Err(_) => match encoding_rs::GBK.decode_without_bom_handling_and_without_replacement(bytes) {
+                Some(text) => (text.into_owned(), true),
+                None => (String::from_utf8_lossy(bytes).into_owned(), false),
+            },
+        },
+    }
+}
+
+fn decode_gbk(bytes: &[u8]) -> String {
+    encoding_rs::GBK.decode_without_bom_handling(bytes).0.into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feed(codec: &mut LineCodec, chunks: &[&[u8]]) -> Vec<String> {
+        let mut lines = Vec::new();
+        for chunk in chunks {
+            codec.push(chunk, &mut lines);
+        }
+        lines
+    }
+
+    #[test]
+    fn test_utf8_split_across_chunks() {
+        let text = "14:20:07[40401] | INFO | 当前配置: Default\n".as_bytes();
+        // 在「当」字中间截断
+        let cut = text.iter().position(|&b| b >= 0x80).unwrap() + 1;
+        let mut codec = LineCodec::new(OutputEncoding::Auto, CarriageReturnMode::Overwrite);
+        let lines = feed(&mut codec, &[&text[..cut], &text[cut..]]);
+        assert_eq!(lines, vec!["14:20:07[40401] | INFO | 当前配置: Default"]);
+        assert_eq!(codec.active_encoding(), OutputEncoding::Utf8);
+    }
+
+    #[test]
+    fn test_auto_detects_gbk() {
+        let (gbk, _, _) = encoding_rs::GBK.encode("任务完成\n第二行\n");
+        let mut codec = LineCodec::new(OutputEncoding::Auto, CarriageReturnMode::Overwrite);
+        let lines = feed(&mut codec, &[b"ascii line\n", &gbk]);
+        assert_eq!(lines, vec!["ascii line", "任务完成", "第二行"]);
+        assert_eq!(codec.active_encoding(), OutputEncoding::Gbk);
+    }
+
+    #[test]
+    fn test_carriage_return_modes() {
+        let input: &[&[u8]] = &[b"progress 10%\rprogress 50%\r", b"progress 100%\r\nnext\r\n"];
+
+        let mut overwrite = LineCodec::new(OutputEncoding::Utf8, CarriageReturnMode::Overwrite);
+        assert_eq!(feed(&mut overwrite, input), vec!["progress 100%", "next"]);
+
+        let mut split = LineCodec::new(OutputEncoding::Utf8, CarriageReturnMode::Split);
+        assert_eq!(feed(&mut split, input), vec!["progress 10%", "progress 50%", "progress 100%", "next"]);
+    }
+
+    #[test]
+    fn test_partial_prompt_and_finish() {
+        let mut codec = LineCodec::new(OutputEncoding::Auto, CarriageReturnMode::Overwrite);
+        let lines = feed(&mut codec, &[b"done\nsra> "]);
+        assert_eq!(lines, vec!["done"]);
+        assert_eq!(codec.partial().trim(), "sra>");
+        codec.clear_partial();
+        assert_eq!(codec.partial(), "");
+
+        let mut lines = feed(&mut codec, &[b"tail without newline"]);
+        codec.finish(&mut lines);
+        assert_eq!(lines, vec!["tail without newline"]);
+    }
+}
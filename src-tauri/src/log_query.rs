@@ -0,0 +1,378 @@
+// 历史日志查询
+// 在 SRA-CE-Logs 的所有日志文件（含压缩的 .gz）中按时间、级别、来源、文本或正则、实例和运行 ID 查找日志，分页返回（最新的在前）；
+// 优先读取结构化日志（.jsonl），没有结构化日志的旧会话按文本日志逐行解析（来源和运行 ID 未知）
+
+use crate::logger::{self, LogLevel, LogSource};
+use crate::run_history::parse_bound;
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone};
+use flate2::read::GzDecoder;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashSet, VecDeque};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+// 每页默认条数和上限
+const DEFAULT_LIMIT: usize = 200;
+const MAX_LIMIT: usize = 1000;
+
+// 日志查询条件（为空的条件不限制）
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct LogQuery {
+    pub from: Option<String>, // 包含
+    pub to: Option<String>,   // 不包含（日期格式时包含当天）
+    pub levels: Vec<LogLevel>,
+    pub sources: Vec<LogSource>,
+    pub text: Option<String>, // 不区分大小写的包含匹配
+    pub regex: bool,          // text 按正则表达式匹配
+    pub instance_id: Option<String>,
+    pub run_id: Option<String>,
+    pub offset: usize,
+    pub limit: Option<usize>,
+}
+
+// 查询到的一条日志（文本日志中没有的字段为 null）
+#[derive(Debug, Clone, Serialize)]
+pub struct LogEntry {
+    pub seq: Option<u64>,
+    pub source: Option<LogSource>,
+    pub level: LogLevel,
+    pub message: String,
+    pub time: String, // ISO 8601 格式
+    pub process_time: Option<String>,
+    pub instance: Option<String>,
+    pub run_id: Option<String>,
+    pub file: String, // 所在的日志文件名
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LogQueryResult {
+    pub total: usize, // 符合条件的总条数
+    pub entries: Vec<LogEntry>,
+}
+
+// 结构化日志的一行（见 logger::JsonLogRecord）
+#[derive(Deserialize)]
+struct JsonLogLine {
+    seq: u64,
+    source: LogSource,
+    level: LogLevel,
+    message: String,
+    host_time: String,
+    process_time: Option<String>,
+    instance: Option<String>,
+    run_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FileKind {
+    Json,
+    Text,
+}
+
+// 日志目录中的一个文件: log-<会话时间>[.<分段>].<log|jsonl>[.gz]
+#[derive(Debug, Clone, PartialEq)]
+struct LogFile {
+    path: PathBuf,
+    session: String, // log-2025-11-29_13-20-11
+    part: u32,
+    kind: FileKind,
+}
+
+fn classify(path: &Path) -> Option<LogFile> {
+    let name = path.file_name()?.to_str()?;
+    let name = name.strip_suffix(".gz").unwrap_or(name);
+    let (rest, kind) = if let Some(rest) = name.strip_suffix(".jsonl") {
+        (rest, FileKind::Json)
+    } else {
+        (name.strip_suffix(".log")?, FileKind::Text)
+    };
+    let (session, part) = match rest.split_once('.') {
+        Some((session, part)) => (session, part.parse().ok()?),
+        None => (rest, 1),
+    };
+    Some(LogFile { path: path.to_path_buf(), session: session.to_string(), part, kind })
+}
+
+// 要读取的文件，按会话和分段从旧到新排列（有结构化日志的会话跳过文本日志）
+fn files_to_search(dir: &Path) -> Result<Vec<LogFile>, String> {
+    let files: Vec<LogFile> = crate::log_rotation::list_log_files(dir)?
+        .iter()
+        .filter_map(|file| classify(&file.path))
+        .collect();
+    let structured: HashSet<String> = files
+        .iter()
+        .filter(|file| file.kind == FileKind::Json)
+        .map(|file| file.session.clone())
+        .collect();
+    let mut files: Vec<LogFile> = files
+        .into_iter()
+        .filter(|file| file.kind == FileKind::Json || !structured.contains(&file.session))
+        .collect();
+    files.sort_by(|a, b| (&a.session, a.part).cmp(&(&b.session, b.part)));
+    Ok(files)
+}
+
+// 会话开始时间（由文件名得出）
+fn session_start(session: &str) -> Option<DateTime<Local>> {
+    let time = NaiveDateTime::parse_from_str(session.strip_prefix("log-")?, "%Y-%m-%d_%H-%M-%S").ok()?;
+    Local.from_local_datetime(&time).earliest()
+}
+
+fn parse_level(value: &str) -> Option<LogLevel> {
+    Some(match value {
+        "INFO" => LogLevel::INFO,
+        "WARN" => LogLevel::WARN,
+        "ERR" => LogLevel::ERR,
+        "TRACE" => LogLevel::TRACE,
+        "DEBUG" => LogLevel::DEBUG,
+        "SUCCESS" => LogLevel::SUCCESS,
+        "MSG" => LogLevel::MSG,
+        _ => return None,
+    })
+}
+
+// 解析文本日志的一行: 2024-11-29 13:45:30 [INFO] [default] 消息内容（实例标记可选）
+// 文本日志无法区分实例标记和以 [xxx] 开头的消息，按实例处理
+fn parse_text_line(line: &str, file: &str) -> Option<LogEntry> {
+    let time = NaiveDateTime::parse_from_str(line.get(..19)?, "%Y-%m-%d %H:%M:%S").ok()?;
+    let time = Local.from_local_datetime(&time).earliest()?;
+    let rest = line.get(19..)?.strip_prefix(" [")?;
+    let (level, rest) = rest.split_once("] ")?;
+    let level = parse_level(level)?;
+    let (instance, message) = match rest.strip_prefix('[').and_then(|r| r.split_once("] ")) {
+        Some((instance, message)) if !instance.is_empty() && !instance.contains(char::is_whitespace) => {
+            (Some(instance.to_string()), message)
+        }
+        _ => (None, rest),
+    };
+    Some(LogEntry {
+        seq: None,
+        source: None,
+        level,
+        message: message.to_string(),
+        time: time.to_rfc3339(),
+        process_time: None,
+        instance,
+        run_id: None,
+        file: file.to_string(),
+    })
+}
+
+fn parse_json_line(line: &str, file: &str) -> Option<LogEntry> {
+    let record: JsonLogLine = serde_json::from_str(line).ok()?;
+    Some(LogEntry {
+        seq: Some(record.seq),
+        source: Some(record.source),
+        level: record.level,
+        message: record.message,
+        time: record.host_time,
+        process_time: record.process_time,
+        instance: record.instance,
+        run_id: record.run_id,
+        file: file.to_string(),
+    })
+}
+
+fn open_reader(path: &Path) -> Result<Box<dyn BufRead>, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    if path.extension().is_some_and(|ext| ext == "gz") {
+        Ok(Box::new(BufReader::new(GzDecoder::new(file))))
+    } else {
+        Ok(Box::new(BufReader::new(file)))
+    }
+}
+
+// 编译后的查询条件
+struct Filter {
+    from: Option<DateTime<Local>>,
+    to: Option<DateTime<Local>>,
+    levels: Vec<LogLevel>,
+    sources: Vec<LogSource>,
+    text: Option<String>,
+    regex: Option<Regex>,
+    instance_id: Option<String>,
+    run_id: Option<String>,
+}
+
+impl Filter {
+    fn new(query: &LogQuery) -> Result<Self, String> {
+        let text = query.text.clone().filter(|t| !t.is_empty());
+        let regex = match text {
+            Some(ref pattern) if query.regex => {
+                Some(Regex::new(pattern).map_err(|e| format!("无效的正则表达式: {}", e))?)
+            }
+            _ => None,
+        };
+        Ok(Self {
+            from: query.from.as_deref().map(|v| parse_bound(v, false)).transpose()?,
+            to: query.to.as_deref().map(|v| parse_bound(v, true)).transpose()?,
+            levels: query.levels.clone(),
+            sources: query.sources.clone(),
+            text: if query.regex { None } else { text.map(|t| t.to_lowercase()) },
+            regex,
+            instance_id: query.instance_id.clone(),
+            run_id: query.run_id.clone(),
+        })
+    }
+
+    fn matches(&self, entry: &LogEntry) -> bool {
+        if !self.levels.is_empty() && !self.levels.contains(&entry.level) {
+            return false;
+        }
+        if !self.sources.is_empty() && !entry.source.as_ref().is_some_and(|s| self.sources.contains(s)) {
+            return false;
+        }
+        if self.instance_id.is_some() && entry.instance != self.instance_id {
+            return false;
+        }
+        if self.run_id.is_some() && entry.run_id != self.run_id {
+            return false;
+        }
+        if self.from.is_some() || self.to.is_some() {
+            let time = match DateTime::parse_from_rfc3339(&entry.time) {
+                Ok(time) => time.with_timezone(&Local),
+                Err(_) => return false,
+            };
+            if self.from.is_some_and(|f| time < f) || self.to.is_some_and(|t| time >= t) {
+                return false;
+            }
+        }
+        if let Some(ref regex) = self.regex {
+            return regex.is_match(&entry.message);
+        }
+        if let Some(ref text) = self.text {
+            return entry.message.to_lowercase().contains(text);
+        }
+        true
+    }
+}
+
+// 在指定目录中查询
+pub fn query_dir(dir: &Path, query: &LogQuery) -> Result<LogQueryResult, String> {
+    let filter = Filter::new(query)?;
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT);
+    // 从旧到新读取，只保留最新的 offset + limit 条
+    let keep = query.offset.saturating_add(limit);
+    let mut newest: VecDeque<LogEntry> = VecDeque::new();
+    let mut total = 0;
+
+    for file in files_to_search(dir)? {
+        // 会话在查询范围结束之后开始时整个文件都不需要读取
+        if filter.to.is_some_and(|to| session_start(&file.session).is_some_and(|start| start >= to)) {
+            continue;
+        }
+        let name = file.path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        // 文件可能已被清理（如另一进程正在轮转），跳过
+        let reader = match open_reader(&file.path) {
+            Ok(reader) => reader,
+            Err(_) => continue,
+        };
+        for line in reader.split(b'\n') {
+            // 压缩文件损坏时停止读取该文件
+            let line = match line {
+                Ok(line) => line,
+                Err(_) => break,
+            };
+            let line = String::from_utf8_lossy(&line);
+            let line = line.trim_end_matches('\r');
+            let entry = match file.kind {
+                FileKind::Json => parse_json_line(line, &name),
+                FileKind::Text => parse_text_line(line, &name),
+            };
+            let entry = match entry {
+                Some(entry) if filter.matches(&entry) => entry,
+                _ => continue,
+            };
+            total += 1;
+            if keep > 0 {
+                if newest.len() == keep {
+                    newest.pop_front();
+                }
+                newest.push_back(entry);
+            }
+        }
+    }
+
+    let entries = newest.into_iter().rev().skip(query.offset).take(limit).collect();
+    Ok(LogQueryResult { total, entries })
+}
+
+// 查询 SRA-CE-Logs 中的日志
+pub fn query_logs(query: &LogQuery) -> Result<LogQueryResult, String> {
+    let dir = logger::log_dir()?;
+    if !dir.exists() {
+        return Ok(LogQueryResult { total: 0, entries: Vec::new() });
+    }
+    query_dir(&dir, query)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::io::Write;
+
+    #[test]
+    fn test_query_structured_and_text_logs() {
+        let dir = std::env::temp_dir().join(format!("sra-ce-log-query-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        // 旧会话只有文本日志（已压缩），新会话有结构化日志（文本日志应被跳过）
+        let old = "2025-11-28 04:00:01 [INFO] [default] [Start] 日常\n\
+                   2025-11-28 04:10:00 [ERR] [default] 找不到目标\n\
+                   2025-11-28 04:10:05 [INFO] 后端消息\n";
+        let mut encoder = flate2::write::GzEncoder::new(
+            File::create(dir.join("log-2025-11-28_03-59-00.log.gz")).unwrap(),
+            flate2::Compression::default(),
+        );
+        encoder.write_all(old.as_bytes()).unwrap();
+        encoder.finish().unwrap();
+        fs::write(dir.join("log-2025-11-29_03-59-00.log"), "2025-11-29 04:00:00 [ERR] 重复的文本日志\n").unwrap();
+        let json = |seq: u64, level: &str, message: &str, run_id: &str| {
+            format!(
+                "{{\"seq\":{},\"source\":\"进程端\",\"level\":\"{}\",\"message\":\"{}\",\"host_time\":\"2025-11-29T04:0{}:00+08:00\",\"process_time\":\"04:00:00\",\"instance\":\"default\",\"run_id\":\"{}\"}}\n",
+                seq, level, message, seq, run_id
+            )
+        };
+        fs::write(
+            dir.join("log-2025-11-29_03-59-00.jsonl"),
+            json(1, "INFO", "开始", "default-1") + &json(2, "ERR", "识别失败 code=42", "default-1"),
+        )
+        .unwrap();
+        fs::write(dir.join("log-2025-11-29_03-59-00.2.jsonl"), json(3, "ERR", "再次失败", "default-2") + "损坏的行\n").unwrap();
+
+        let errors = query_dir(&dir, &LogQuery { levels: vec![LogLevel::ERR], ..Default::default() }).unwrap();
+        assert_eq!(errors.total, 3);
+        let messages: Vec<&str> = errors.entries.iter().map(|e| e.message.as_str()).collect();
+        assert_eq!(messages, vec!["再次失败", "识别失败 code=42", "找不到目标"]);
+        assert_eq!(errors.entries[2].instance.as_deref(), Some("default"));
+        assert!(errors.entries[2].source.is_none());
+
+        let run = query_dir(&dir, &LogQuery { run_id: Some("default-1".to_string()), ..Default::default() }).unwrap();
+        assert_eq!(run.total, 2);
+        assert_eq!(run.entries[0].seq, Some(2));
+
+        let regex = LogQuery { text: Some(r"code=\d+".to_string()), regex: true, ..Default::default() };
+        assert_eq!(query_dir(&dir, &regex).unwrap().entries[0].message, "识别失败 code=42");
+
+        let page = LogQuery { offset: 1, limit: Some(2), ..Default::default() };
+        let page = query_dir(&dir, &page).unwrap();
+        assert_eq!(page.total, 6);
+        assert_eq!(page.entries.len(), 2);
+        assert_eq!(page.entries[0].message, "识别失败 code=42");
+
+        let text_only = LogQuery {
+            to: Some("2025-11-28".to_string()),
+            text: Some("后端".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(query_dir(&dir, &text_only).unwrap().total, 1);
+        assert!(query_dir(&dir, &LogQuery { sources: vec![LogSource::Backend], ..Default::default() }).unwrap().entries.is_empty());
+        assert!(query_dir(&dir, &LogQuery { text: Some("(".to_string()), regex: true, ..Default::default() }).is_err());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
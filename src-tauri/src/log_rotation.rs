@@ -0,0 +1,392 @@
+// 日志文件轮转与清理
+// 单个日志文件超过设置的大小时切换到新文件（log-<时间>.2.log、log-<时间>.3.log ...），切换下来的文件可选 gzip 压缩；
+// 启动时按数量、保留天数和总大小清理 SRA-CE-Logs 中的旧日志（以会话为单位）；
+// 写日志的进程持有 <会话>.lock 的独占锁，其他进程（GUI、CLI、守护进程）清理时跳过仍被锁定的会话
+
+use crate::settings::LogRetentionSettings;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+const MB: u64 = 1024 * 1024;
+
+// 日志文件名前缀（只清理此前缀的文件）
+const LOG_FILE_PREFIX: &str = "log-";
+
+// 按大小轮转的日志文件
+pub struct RotatingFile {
+    dir: PathBuf,
+    stem: String,      // 如 log-2025-11-29_13-20-11
+    extension: String, // 如 log
+    part: u32,
+    path: PathBuf,
+    file: File,
+    size: u64,
+    policy: LogRetentionSettings,
+}
+
+fn open_append(path: &Path) -> Result<File, String> {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("Failed to create log file: {}", e))
+}
+
+impl RotatingFile {
+    pub fn create(dir: &Path, stem: &str, extension: &str, policy: LogRetentionSettings) -> Result<Self, String> {
+        let path = dir.join(format!("{}.{}", stem, extension));
+        let file = open_append(&path)?;
+        let size = file.metadata().map(|m| m.len()).unwrap_or(0);
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            stem: stem.to_string(),
+            extension: extension.to_string(),
+            part: 1,
+            path,
+            file,
+            size,
+            policy,
+        })
+    }
+
+    // 当前写入的文件
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // 写入并刷新，写入后超过大小限制时先切换到新文件（单次写入不拆分）
+    pub fn write_all(&mut self, data: &[u8]) -> Result<(), String> {
+        let max_bytes = self.policy.max_file_size_mb * MB;
+        if max_bytes > 0 && self.size > 0 && self.size + data.len() as u64 > max_bytes {
+            self.rotate()?;
+        }
+        self.file
+            .write_all(data)
+            .map_err(|e| format!("Failed to write log: {}", e))?;
+        self.file
+            .flush()
+            .map_err(|e| format!("Failed to flush log: {}", e))?;
+        self.size += data.len() as u64;
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> Result<(), String> {
+        self.part += 1;
+        let path = self.dir.join(format!("{}.{}.{}", self.stem, self.part, self.extension));
+        self.file = open_append(&path)?;
+        self.size = 0;
+        let finished = std::mem::replace(&mut self.path, path);
+        if self.policy.compress_rotated {
+            thread::spawn(move || compress_file(&finished));
+        }
+        Ok(())
+    }
+}
+
+// 会话锁，在进程写日志期间一直持有
+pub struct SessionLock {
+    _file: File,
+}
+
+fn lock_path(dir: &Path, stem: &str) -> PathBuf {
+    dir.join(format!("{}.lock", stem))
+}
+
+impl SessionLock {
+    pub fn acquire(dir: &Path, stem: &str) -> Result<Self, String> {
+        let path = lock_path(dir, stem);
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+        file.try_lock()
+            .map_err(|e| format!("Failed to lock {}: {}", path.display(), e))?;
+        Ok(Self { _file: file })
+    }
+}
+
+// 会话是否仍有进程在写入（包括本进程）
+fn session_in_use(dir: &Path, stem: &str) -> bool {
+    let Ok(file) = OpenOptions::new().append(true).open(lock_path(dir, stem)) else {
+        return false;
+    };
+    matches!(file.try_lock(), Err(TryLockError::WouldBlock))
+}
+
+// gzip 压缩为 <文件名>.gz，成功后删除原文件
+pub fn compress_file(path: &Path) -> Result<PathBuf, String> {
+    let mut target = path.as_os_str().to_os_string();
+    target.push(".gz");
+    let target = PathBuf::from(target);
+
+    let mut input = File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let output = File::create(&target).map_err(|e| format!("Failed to create {}: {}", target.display(), e))?;
+    let mut encoder = GzEncoder::new(output, Compression::default());
+    io::copy(&mut input, &mut encoder)
+        .and_then(|_| encoder.finish())
+        .map_err(|e| format!("Failed to compress {}: {}", path.display(), e))?;
+    fs::remove_file(path).map_err(|e| format!("Failed to remove {}: {}", path.display(), e))?;
+    Ok(target)
+}
+
+#[derive(Debug, Clone)]
+pub struct LogFileInfo {
+    pub path: PathBuf,
+    pub size: u64,
+    pub modified: SystemTime,
+}
+
+// 目录中的日志文件，按修改时间从新到旧排列
+pub fn list_log_files(dir: &Path) -> Result<Vec<LogFileInfo>, String> {
+    let entries = fs::read_dir(dir).map_err(|e| format!("Failed to read log directory: {}", e))?;
+    let mut files: Vec<LogFileInfo> = entries
+        .flatten()
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with(LOG_FILE_PREFIX))
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok().filter(|m| m.is_file())?;
+            Some(LogFileInfo {
+                path: entry.path(),
+                size: metadata.len(),
+                modified: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+            })
+        })
+        .collect();
+    files.sort_by_key(|file| std::cmp::Reverse(file.modified));
+    Ok(files)
+}
+
+// 日志所属的会话：文件名中第一个 "." 之前的部分（如 log-2025-11-29_13-20-11），
+// 同一会话的文本日志、结构化日志以及它们的轮转和压缩文件共用这一部分
+pub fn session_stem(path: &Path) -> String {
+    let name = path.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+    name.split('.').next().unwrap_or_default().to_string()
+}
+
+// 一个会话的全部日志文件
+struct LogSession<'a> {
+    stem: String,
+    files: Vec<&'a LogFileInfo>,
+    size: u64,
+    modified: SystemTime, // 会话中最新的修改时间
+}
+
+// 按会话分组（files 按从新到旧排列，会话按其最新文件的顺序排列）
+fn group_sessions(files: &[LogFileInfo]) -> Vec<LogSession<'_>> {
+    let mut sessions: Vec<LogSession> = Vec::new();
+    for file in files {
+        let stem = session_stem(&file.path);
+        match sessions.iter_mut().find(|session| session.stem == stem) {
+            Some(session) => {
+                session.files.push(file);
+                session.size += file.size;
+                session.modified = session.modified.max(file.modified);
+            }
+            None => sessions.push(LogSession {
+                stem,
+                files: vec![file],
+                size: file.size,
+                modified: file.modified,
+            }),
+        }
+    }
+    sessions
+}
+
+// 按保留策略选出要删除的文件（files 按从新到旧排列）；数量、保留天数和总大小都按会话计算，
+// 较新的会话优先保留，一个会话的文件总是一起保留或一起删除
+pub fn files_to_delete(files: &[LogFileInfo], policy: &LogRetentionSettings, now: SystemTime) -> Vec<PathBuf> {
+    let max_age = Duration::from_secs(policy.max_age_days as u64 * 24 * 3600);
+    let max_total = policy.max_total_size_mb * MB;
+    let mut total = 0;
+    group_sessions(files)
+        .into_iter()
+        .enumerate()
+        .filter(|(index, session)| {
+            total += session.size;
+            let too_old = policy.max_age_days > 0 && now.duration_since(session.modified).unwrap_or_default() > max_age;
+            let too_many = policy.max_files > 0 && *index >= policy.max_files;
+            let too_large = policy.max_total_size_mb > 0 && total > max_total;
+            too_old || too_many || too_large
+        })
+        .flat_map(|(_, session)| session.files.into_iter().map(|file| file.path.clone()))
+        .collect()
+}
+
+// 启动时清理旧日志，返回删除的文件数；current 为本次正在写入的文本日志和结构化日志，
+// 它们所在的会话和其他进程仍在写入的会话不会被删除或压缩。
+// 开启压缩时，比最新会话更早的、之前未压缩的日志在后台压缩
+pub fn apply_retention(dir: &Path, current: &[&Path], policy: &LogRetentionSettings) -> Result<usize, String> {
+    let files: Vec<LogFileInfo> = list_log_files(dir)?;
+    let current_stems: Vec<String> = current.iter().map(|path| session_stem(path)).collect();
+    let sessions = group_sessions(&files);
+    let protected: Vec<&str> = sessions
+        .iter()
+        .map(|session| session.stem.as_str())
+        .filter(|stem| current_stems.iter().any(|c| c == stem) || session_in_use(dir, stem))
+        .collect();
+    let is_protected = |path: &Path| protected.contains(&session_stem(path).as_str());
+
+    let doomed: Vec<PathBuf> = files_to_delete(&files, policy, SystemTime::now())
+        .into_iter()
+        .filter(|path| !is_protected(path))
+        .collect();
+    let mut deleted = 0;
+    for path in &doomed {
+        if fs::remove_file(path).is_ok() {
+            deleted += 1;
+        }
+    }
+
+    if policy.compress_rotated {
+        let pending: Vec<PathBuf> = sessions
+            .iter()
+            .skip(1)
+            .flat_map(|session| session.files.iter().map(|file| file.path.clone()))
+            .filter(|path| !is_protected(path) && !doomed.contains(path))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "log" || ext == "jsonl"))
+            .collect();
+        if !pending.is_empty() {
+            thread::spawn(move || {
+                for path in pending {
+                    let _ = compress_file(&path);
+                }
+            });
+        }
+    }
+    Ok(deleted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("sra-ce-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_retention_by_count_age_and_size() {
+        let now = SystemTime::now();
+        let file = |name: &str, size_mb: u64, days_ago: u64| LogFileInfo {
+            path: PathBuf::from(name),
+            size: size_mb * MB,
+            modified: now - Duration::from_secs(days_ago * 24 * 3600),
+        };
+        let files = vec![file("a", 1, 0), file("b", 1, 1), file("c", 1, 2), file("d", 1, 40)];
+        let policy = |max_files, max_age_days, max_total_size_mb| LogRetentionSettings {
+            max_files,
+            max_age_days,
+            max_total_size_mb,
+            ..Default::default()
+        };
+
+        assert_eq!(files_to_delete(&files, &policy(2, 0, 0), now), vec![PathBuf::from("c"), PathBuf::from("d")]);
+        assert_eq!(files_to_delete(&files, &policy(0, 30, 0), now), vec![PathBuf::from("d")]);
+        assert_eq!(files_to_delete(&files, &policy(0, 0, 2), now), vec![PathBuf::from("c"), PathBuf::from("d")]);
+        assert!(files_to_delete(&files, &policy(0, 0, 0), now).is_empty());
+    }
+
+    #[test]
+    fn test_retention_counts_sessions_not_files() {
+        let now = SystemTime::now();
+        let file = |name: &str, size_mb: u64, days_ago: u64| LogFileInfo {
+            path: PathBuf::from(name),
+            size: size_mb * MB,
+            modified: now - Duration::from_secs(days_ago * 24 * 3600),
+        };
+        // 三个会话，每个都有文本日志和结构化日志，最旧的会话还有轮转和压缩文件
+        let files = vec![
+            file("log-3.jsonl", 1, 0),
+            file("log-3.log", 1, 0),
+            file("log-2.log", 1, 1),
+            file("log-2.jsonl", 1, 1),
+            file("log-1.2.log", 1, 31),
+            file("log-1.jsonl.gz", 1, 35),
+            file("log-1.log.gz", 1, 35),
+        ];
+        let policy = |max_files, max_age_days, max_total_size_mb| LogRetentionSettings {
+            max_files,
+            max_age_days,
+            max_total_size_mb,
+            ..Default::default()
+        };
+        let paths = |names: &[&str]| names.iter().map(PathBuf::from).collect::<Vec<_>>();
+        let oldest = paths(&["log-1.2.log", "log-1.jsonl.gz", "log-1.log.gz"]);
+
+        assert_eq!(session_stem(Path::new("log-1.2.log.gz")), "log-1");
+        assert_eq!(files_to_delete(&files, &policy(2, 0, 0), now), oldest);
+        assert_eq!(files_to_delete(&files, &policy(0, 30, 0), now), oldest);
+        assert_eq!(files_to_delete(&files, &policy(0, 0, 4), now), oldest);
+        let mut expected = paths(&["log-2.log", "log-2.jsonl"]);
+        expected.extend(oldest);
+        assert_eq!(files_to_delete(&files, &policy(1, 0, 0), now), expected);
+        assert!(files_to_delete(&files, &policy(3, 0, 0), now).is_empty());
+    }
+
+    #[test]
+    fn test_rotation_and_compression() {
+        let dir = temp_dir("log-rotation");
+        let policy = LogRetentionSettings {
+            max_file_size_mb: 1,
+            compress_rotated: false,
+            ..Default::default()
+        };
+        let mut file = RotatingFile::create(&dir, "log-test", "log", policy).unwrap();
+        let line = vec![b'x'; 1024];
+        for _ in 0..1500 {
+            file.write_all(&line).unwrap();
+        }
+        assert_eq!(file.path(), dir.join("log-test.2.log"));
+        assert_eq!(fs::metadata(dir.join("log-test.log")).unwrap().len(), MB);
+
+        let compressed = compress_file(&dir.join("log-test.log")).unwrap();
+        assert_eq!(compressed, dir.join("log-test.log.gz"));
+        assert!(!dir.join("log-test.log").exists());
+        assert!(fs::metadata(&compressed).unwrap().len() < 100 * 1024);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_retention_skips_sessions_in_use() {
+        let dir = temp_dir("log-retention-lock");
+        let two_days_ago = SystemTime::now() - Duration::from_secs(2 * 24 * 3600);
+        let age = |name: &str| {
+            let file = OpenOptions::new().create(true).append(true).open(dir.join(name)).unwrap();
+            file.set_modified(two_days_ago).unwrap();
+        };
+        // 另一个进程正在写入的会话（持有会话锁）和已经结束的会话（锁已释放）
+        let live = SessionLock::acquire(&dir, "log-1").unwrap();
+        drop(SessionLock::acquire(&dir, "log-2").unwrap());
+        for name in ["log-1.log", "log-1.jsonl", "log-1.lock", "log-2.log", "log-2.jsonl", "log-2.lock"] {
+            age(name);
+        }
+        assert!(SessionLock::acquire(&dir, "log-1").is_err());
+        let current = dir.join("log-3.log");
+        fs::write(&current, b"line\n").unwrap();
+
+        let policy = LogRetentionSettings {
+            max_age_days: 1,
+            ..Default::default()
+        };
+        assert_eq!(apply_retention(&dir, &[&current], &policy).unwrap(), 3);
+        assert!(dir.join("log-1.log").exists() && dir.join("log-1.jsonl").exists());
+        assert!(!dir.join("log-2.log").exists() && !dir.join("log-2.lock").exists());
+        assert!(current.exists());
+
+        // 进程退出、锁释放后，会话可以被清理
+        drop(live);
+        assert_eq!(apply_retention(&dir, &[&current], &policy).unwrap(), 3);
+        assert!(!dir.join("log-1.log").exists());
+        assert!(current.exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -1,12 +1,12 @@
 // 日志系统模块
 // 负责日志的收集、存储和分发
 
+use crate::log_rotation::{self, RotatingFile, SessionLock};
+use crate::settings::LogLevelSettings;
 use serde::{Deserialize, Serialize};
-use std::fs::{File, OpenOptions};
-use std::io::Write;
+use std::collections::VecDeque;
 use std::path::PathBuf;
 use std::sync::Mutex;
-use tauri::Emitter;
 
 // 日志级别
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -32,6 +32,22 @@ impl LogLevel {
             LogLevel::MSG => "MSG",
         }
     }
+
+    // 严重程度，用于按最低级别过滤
+    fn severity(&self) -> u8 {
+        match self {
+            LogLevel::TRACE => 0,
+            LogLevel::DEBUG => 1,
+            LogLevel::INFO | LogLevel::SUCCESS | LogLevel::MSG => 2,
+            LogLevel::WARN => 3,
+            LogLevel::ERR => 4,
+        }
+    }
+
+    // 是否不低于 min
+    pub fn at_least(&self, min: &LogLevel) -> bool {
+        self.severity() >= min.severity()
+    }
 }
 
 // 日志来源
@@ -63,33 +79,179 @@ pub struct LogMessage {
     pub level: LogLevel,
     pub message: String,
     pub time: String, // ISO 8601 格式
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instance: Option<String>, // SRA-cli 实例 ID（仅实例相关日志）
+    #[serde(default)]
+    pub seq: u64, // 本次启动内递增的序号
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub process_time: Option<String>, // SRA-cli 输出中的时间（HH:MM:SS，仅进程端日志）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run_id: Option<String>, // 所属的任务运行（见 run_history）
+}
+
+// JSONL 日志中的一行（字段固定，缺失时为 null，便于外部工具读取）
+#[derive(Serialize)]
+struct JsonLogRecord<'a> {
+    seq: u64,
+    source: &'a LogSource,
+    level: &'a LogLevel,
+    message: &'a str,
+    host_time: &'a str,
+    process_time: Option<&'a str>,
+    instance: Option<&'a str>,
+    run_id: Option<&'a str>,
+}
+
+impl<'a> From<&'a LogMessage> for JsonLogRecord<'a> {
+    fn from(msg: &'a LogMessage) -> Self {
+        Self {
+            seq: msg.seq,
+            source: &msg.source,
+            level: &msg.level,
+            message: &msg.message,
+            host_time: &msg.time,
+            process_time: msg.process_time.as_deref(),
+            instance: msg.instance.as_deref(),
+            run_id: msg.run_id.as_deref(),
+        }
+    }
+}
+
+// 增量获取日志的结果
+#[derive(Debug, Clone, Serialize)]
+pub struct LogBatch {
+    pub logs: Vec<LogMessage>, // 按序号从旧到新
+    pub gap: bool,             // 请求的序号之后有日志已被移出缓冲区（可用 query_logs 补齐）
+    pub has_more: bool,        // 受 limit 限制未返回全部，需以最后一条的序号继续获取
+    pub latest_seq: u64,       // 目前已分配的最大序号
+}
+
+// 最近日志的环形缓冲区，按序号递增排列
+struct LogBuffer {
+    entries: VecDeque<LogMessage>,
+    capacity: usize,
+    next_seq: u64,
+    evicted_seq: u64, // 最后一条被移出的日志的序号
 }
 
+impl LogBuffer {
+    fn new(capacity: usize) -> Self {
+        Self {
+            entries: VecDeque::with_capacity(capacity),
+            capacity,
+            next_seq: 1,
+            evicted_seq: 0,
+        }
+    }
+
+    // 分配下一个序号
+    fn assign_seq(&mut self) -> u64 {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        seq
+    }
+
+    fn push(&mut self, msg: LogMessage) {
+        self.entries.push_back(msg);
+        self.trim();
+    }
+
+    fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity.max(1);
+        self.trim();
+    }
+
+    fn trim(&mut self) {
+        while self.entries.len() > self.capacity {
+            if let Some(evicted) = self.entries.pop_front() {
+                self.evicted_seq = evicted.seq;
+            }
+        }
+    }
+
+    // 序号大于 since 的日志，最多 limit 条
+    fn since(&self, since: u64, limit: usize) -> LogBatch {
+        let start = self.entries.partition_point(|msg| msg.seq <= since);
+        let logs: Vec<LogMessage> = self.entries.range(start..).take(limit).cloned().collect();
+        LogBatch {
+            has_more: self.entries.len() - start > logs.len(),
+            logs,
+            gap: since < self.evicted_seq,
+            latest_seq: self.next_seq - 1,
+        }
+    }
+}
+
+// 增量获取时默认的最多条数
+const DEFAULT_BATCH_LIMIT: usize = 500;
+
 // 日志管理器
 pub struct LogManager {
-    log_file: Mutex<Option<File>>,
-    log_buffer: Mutex<Vec<LogMessage>>,
-    app_handle: Mutex<Option<tauri::AppHandle>>,
+    log_file: Mutex<Option<RotatingFile>>,
+    jsonl_file: Mutex<Option<RotatingFile>>, // 结构化日志（每行一个 JSON 对象）
+    session_lock: Mutex<Option<SessionLock>>, // 持有期间其他进程不会清理本次的日志
+    log_buffer: Mutex<LogBuffer>,
+    levels: Mutex<LogLevelSettings>,
+}
+
+// 一条日志应写入的输出目标
+struct Sinks {
+    file: bool,
+    jsonl: bool,
+    event: bool,
+    buffer: bool,
+}
+
+impl Sinks {
+    fn select(levels: &LogLevelSettings, source: &LogSource, level: &LogLevel) -> Self {
+        let source_min = match source {
+            LogSource::Backend => &levels.backend,
+            LogSource::Process => &levels.process,
+            LogSource::Frontend => &levels.frontend,
+        };
+        let passes = |sink_min: &LogLevel| level.at_least(source_min) && level.at_least(sink_min);
+        Self {
+            file: passes(&levels.file),
+            jsonl: passes(&levels.jsonl),
+            event: passes(&levels.event),
+            buffer: passes(&levels.buffer),
+        }
+    }
+
+    fn any(&self) -> bool {
+        self.file || self.jsonl || self.event || self.buffer
+    }
 }
 
 impl LogManager {
     pub fn new() -> Self {
         Self {
             log_file: Mutex::new(None),
-            log_buffer: Mutex::new(Vec::new()),
-            app_handle: Mutex::new(None),
+            jsonl_file: Mutex::new(None),
+            session_lock: Mutex::new(None),
+            log_buffer: Mutex::new(LogBuffer::new(crate::settings::DEFAULT_LOG_BUFFER_CAPACITY)),
+            levels: Mutex::new(LogLevelSettings::default()),
         }
     }
 
-    // 初始化日志系统
-    pub fn initialize(&self, app_handle: tauri::AppHandle) -> Result<(), String> {
-        // 保存 app_handle
-        let mut handle_guard = self.app_handle.lock().map_err(|e| format!("Lock error: {}", e))?;
-        *handle_guard = Some(app_handle);
-        drop(handle_guard);
+    // 更新日志级别设置（立即生效）
+    pub fn set_levels(&self, levels: LogLevelSettings) -> Result<(), String> {
+        let mut guard = self.levels.lock().map_err(|e| format!("Lock error: {}", e))?;
+        *guard = levels;
+        Ok(())
+    }
 
-        // 创建日志文件
-        let log_path = self.create_log_file()?;
+    // 更新缓冲区容量（立即生效，缩小时移出最旧的日志）
+    pub fn set_buffer_capacity(&self, capacity: usize) -> Result<(), String> {
+        let mut buffer_guard = self.log_buffer.lock().map_err(|e| format!("Lock error: {}", e))?;
+        buffer_guard.set_capacity(capacity);
+        Ok(())
+    }
+
+    // 初始化日志系统
+    pub fn initialize(&self) -> Result<(), String> {
+        // 创建日志文件（同时读取日志级别设置）
+        let (log_path, cleaned) = self.create_log_file()?;
         
         // 记录启动日志
         self.log(
@@ -97,82 +259,141 @@ impl LogManager {
             LogLevel::INFO,
             format!("日志系统已启动，日志文件: {}", log_path.display()),
         )?;
+        match cleaned {
+            Ok(0) => {}
+            Ok(count) => self.log(LogSource::Backend, LogLevel::INFO, format!("已清理 {} 个旧日志文件", count))?,
+            Err(e) => self.log(LogSource::Backend, LogLevel::WARN, format!("清理旧日志失败: {}", e))?,
+        }
 
         Ok(())
     }
 
-    // 创建日志文件
-    fn create_log_file(&self) -> Result<PathBuf, String> {
-        // 获取 APPDATA 路径
-        let appdata = std::env::var("APPDATA").map_err(|_| "Failed to get APPDATA path")?;
-        let log_dir = PathBuf::from(appdata).join("SRA").join("SRA-CE-Logs");
+    // 创建日志文件并按保留策略清理旧日志，返回日志文件路径和清理结果
+    fn create_log_file(&self) -> Result<(PathBuf, Result<usize, String>), String> {
+        let log_dir = log_dir()?;
 
         // 确保目录存在
         std::fs::create_dir_all(&log_dir)
             .map_err(|e| format!("Failed to create log directory: {}", e))?;
 
-        // 生成日志文件名: log-2025-11-29_13-20-11.log
+        // 生成日志文件名: log-2025-11-29_13-20-11.log（结构化日志为同名的 .jsonl）
         let now = chrono::Local::now();
-        let filename = format!("log-{}.log", now.format("%Y-%m-%d_%H-%M-%S"));
-        let log_path = log_dir.join(&filename);
+        // 超过大小上限后切换为 log-2025-11-29_13-20-11.2.log 等
+        let stem = format!("log-{}", now.format("%Y-%m-%d_%H-%M-%S"));
+        // 日志系统尚未可用（或持有日志锁），设置文件无法解析时不记录错误，直接使用默认值
+        let ce_settings = crate::settings::load_ce_settings().unwrap_or_default();
+        self.set_levels(ce_settings.log_levels)?;
+        self.set_buffer_capacity(ce_settings.log_buffer_capacity)?;
+        let policy = ce_settings.log_retention;
 
         // 创建日志文件
-        let file = OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(&log_path)
-            .map_err(|e| format!("Failed to create log file: {}", e))?;
+        let lock = SessionLock::acquire(&log_dir, &stem)?;
+        let file = RotatingFile::create(&log_dir, &stem, "log", policy.clone())?;
+        let jsonl_file = RotatingFile::create(&log_dir, &stem, "jsonl", policy.clone())?;
+        let log_path = file.path().to_path_buf();
+        let jsonl_path = jsonl_file.path().to_path_buf();
+        let cleaned = log_rotation::apply_retention(&log_dir, &[&log_path, &jsonl_path], &policy);
 
         let mut file_guard = self.log_file.lock().map_err(|e| format!("Lock error: {}", e))?;
         *file_guard = Some(file);
+        drop(file_guard);
+        let mut jsonl_guard = self.jsonl_file.lock().map_err(|e| format!("Lock error: {}", e))?;
+        *jsonl_guard = Some(jsonl_file);
+        drop(jsonl_guard);
+        let mut lock_guard = self.session_lock.lock().map_err(|e| format!("Lock error: {}", e))?;
+        *lock_guard = Some(lock);
 
-        Ok(log_path)
+        Ok((log_path, cleaned))
     }
 
     // 记录日志
     pub fn log(&self, source: LogSource, level: LogLevel, message: String) -> Result<(), String> {
+        self.log_with_instance(source, level, message, None)
+    }
+
+    // 记录日志并标记所属的 SRA-cli 实例
+    pub fn log_with_instance(&self, source: LogSource, level: LogLevel, message: String, instance: Option<String>) -> Result<(), String> {
+        self.record(source, level, message, instance, None, None)
+    }
+
+    // 记录日志，附带 SRA-cli 输出中的时间和所属的任务运行
+    fn record(
+        &self,
+        source: LogSource,
+        level: LogLevel,
+        message: String,
+        instance: Option<String>,
+        process_time: Option<String>,
+        run_id: Option<String>,
+    ) -> Result<(), String> {
+        let sinks = {
+            let levels = self.levels.lock().map_err(|e| format!("Lock error: {}", e))?;
+            Sinks::select(&levels, &source, &level)
+        };
+        if !sinks.any() {
+            return Ok(());
+        }
+
+        // 脱敏后再写入任何输出目标
+        let message = crate::redact::redact(&message);
+
         let now = chrono::Local::now();
         let time = now.to_rfc3339(); // ISO 8601 格式
 
-        let log_msg = LogMessage {
-            source: source.clone(),
+        let mut log_msg = LogMessage {
+            source,
             level: level.clone(),
             message: message.clone(),
-            time: time.clone(),
+            time,
+            instance: instance.clone(),
+            seq: 0,
+            process_time,
+            run_id,
         };
 
-        // 添加到缓冲区
+        // 添加到缓冲区（在缓冲区锁内分配序号，保证缓冲区按序号排列）
         let mut buffer_guard = self.log_buffer.lock().map_err(|e| format!("Lock error: {}", e))?;
-        buffer_guard.push(log_msg.clone());
-        
-        // 限制缓冲区大小（保留最近 1000 条）
-        if buffer_guard.len() > 1000 {
-            buffer_guard.remove(0);
+        log_msg.seq = buffer_guard.assign_seq();
+        if sinks.buffer {
+            buffer_guard.push(log_msg.clone());
         }
         drop(buffer_guard);
 
-        // 写入文件: 2024-11-29 13:45:30 [INFO] 消息内容
+        // 写入文件: 2024-11-29 13:45:30 [INFO] 消息内容（实例日志: 2024-11-29 13:45:30 [INFO] [default] 消息内容）
         let formatted_time = now.format("%Y-%m-%d %H:%M:%S");
-        let log_line = format!(
-            "{} [{}] {}\n",
-            formatted_time,
-            level.as_str(),
-            message
-        );
+        let log_line = match instance {
+            Some(ref instance) => format!(
+                "{} [{}] [{}] {}\n",
+                formatted_time,
+                level.as_str(),
+                instance,
+                message
+            ),
+            None => format!(
+                "{} [{}] {}\n",
+                formatted_time,
+                level.as_str(),
+                message
+            ),
+        };
 
-        let mut file_guard = self.log_file.lock().map_err(|e| format!("Lock error: {}", e))?;
-        if let Some(ref mut file) = *file_guard {
-            file.write_all(log_line.as_bytes())
-                .map_err(|e| format!("Failed to write log: {}", e))?;
-            file.flush()
-                .map_err(|e| format!("Failed to flush log: {}", e))?;
+        if sinks.file {
+            let mut file_guard = self.log_file.lock().map_err(|e| format!("Lock error: {}", e))?;
+            if let Some(ref mut file) = *file_guard {
+                file.write_all(log_line.as_bytes())?;
+            }
+        }
+
+        if sinks.jsonl {
+            let mut jsonl_guard = self.jsonl_file.lock().map_err(|e| format!("Lock error: {}", e))?;
+            if let Some(ref mut file) = *jsonl_guard {
+                file.write_all(json_line(&log_msg)?.as_bytes())?;
+            }
         }
-        drop(file_guard);
 
         // 发送到前端（如果前端正在监听）
-        let handle_guard = self.app_handle.lock().map_err(|e| format!("Lock error: {}", e))?;
-        if let Some(ref app_handle) = *handle_guard {
-            let _ = app_handle.emit("log-message", log_msg);
+        if sinks.event {
+            crate::events::emit("log-message", log_msg);
         }
 
         Ok(())
@@ -181,17 +402,38 @@ impl LogManager {
     // 获取所有日志
     pub fn get_all_logs(&self) -> Result<Vec<LogMessage>, String> {
         let buffer_guard = self.log_buffer.lock().map_err(|e| format!("Lock error: {}", e))?;
-        Ok(buffer_guard.clone())
+        Ok(buffer_guard.entries.iter().cloned().collect())
+    }
+
+    // 获取序号大于 since 的日志（limit 默认 500）
+    pub fn get_logs_since(&self, since: u64, limit: Option<usize>) -> Result<LogBatch, String> {
+        let buffer_guard = self.log_buffer.lock().map_err(|e| format!("Lock error: {}", e))?;
+        Ok(buffer_guard.since(since, limit.unwrap_or(DEFAULT_BATCH_LIMIT)))
     }
 }
 
+// 日志目录（%APPDATA%/SRA/SRA-CE-Logs）
+pub fn log_dir() -> Result<PathBuf, String> {
+    Ok(crate::paths::data_dir()?.join("SRA-CE-Logs"))
+}
+
+// 结构化日志的一行（含换行符）
+fn json_line(msg: &LogMessage) -> Result<String, String> {
+    let mut line = serde_json::to_string(&JsonLogRecord::from(msg))
+        .map_err(|e| format!("Failed to serialize log: {}", e))?;
+    line.push('\n');
+    Ok(line)
+}
+
 // 全局日志管理器
 static LOG_MANAGER: Mutex<Option<LogManager>> = Mutex::new(None);
 
 // 初始化全局日志管理器
-pub fn init_logger(app_handle: tauri::AppHandle) -> Result<(), String> {
+pub fn init_logger() -> Result<(), String> {
+    // 先收集已知的敏感值，启动日志中也不会出现
+    crate::redact::load_known_secrets();
     let manager = LogManager::new();
-    manager.initialize(app_handle)?;
+    manager.initialize()?;
     
     let mut guard = LOG_MANAGER.lock().map_err(|e| format!("Lock error: {}", e))?;
     *guard = Some(manager);
@@ -209,6 +451,63 @@ pub fn log(source: LogSource, level: LogLevel, message: String) -> Result<(), St
     }
 }
 
+// 记录实例日志（全局函数）
+pub fn log_instance(instance_id: &str, source: LogSource, level: LogLevel, message: String) -> Result<(), String> {
+    log_instance_entry(instance_id, source, level, message, None)
+}
+
+// 记录 SRA-cli 输出的日志，保留输出中的时间（全局函数）
+pub fn log_process(instance_id: &str, level: LogLevel, message: String, process_time: String) -> Result<(), String> {
+    log_instance_entry(instance_id, LogSource::Process, level, message, Some(process_time))
+}
+
+fn log_instance_entry(
+    instance_id: &str,
+    source: LogSource,
+    level: LogLevel,
+    message: String,
+    process_time: Option<String>,
+) -> Result<(), String> {
+    // 先于日志锁查询运行记录（持有运行记录锁时不会写日志）
+    let run_id = crate::run_history::current_run_id(instance_id);
+    let guard = LOG_MANAGER.lock().map_err(|e| format!("Lock error: {}", e))?;
+    if let Some(ref manager) = *guard {
+        manager.record(source, level, message, Some(instance_id.to_string()), process_time, run_id)
+    } else {
+        Err("Logger not initialized".to_string())
+    }
+}
+
+// 更新日志级别设置（全局函数）
+pub fn set_log_levels(levels: LogLevelSettings) -> Result<(), String> {
+    let guard = LOG_MANAGER.lock().map_err(|e| format!("Lock error: {}", e))?;
+    if let Some(ref manager) = *guard {
+        manager.set_levels(levels)
+    } else {
+        Err("Logger not initialized".to_string())
+    }
+}
+
+// 更新缓冲区容量（全局函数）
+pub fn set_log_buffer_capacity(capacity: usize) -> Result<(), String> {
+    let guard = LOG_MANAGER.lock().map_err(|e| format!("Lock error: {}", e))?;
+    if let Some(ref manager) = *guard {
+        manager.set_buffer_capacity(capacity)
+    } else {
+        Err("Logger not initialized".to_string())
+    }
+}
+
+// 获取序号大于 since 的日志（全局函数）
+pub fn get_logs_since(since: u64, limit: Option<usize>) -> Result<LogBatch, String> {
+    let guard = LOG_MANAGER.lock().map_err(|e| format!("Lock error: {}", e))?;
+    if let Some(ref manager) = *guard {
+        manager.get_logs_since(since, limit)
+    } else {
+        Err("Logger not initialized".to_string())
+    }
+}
+
 // 获取所有日志（全局函数）
 pub fn get_all_logs() -> Result<Vec<LogMessage>, String> {
     let guard = LOG_MANAGER.lock().map_err(|e| format!("Lock error: {}", e))?;
@@ -218,3 +517,116 @@ pub fn get_all_logs() -> Result<Vec<LogMessage>, String> {
         Err("Logger not initialized".to_string())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_jsonl_sink() {
+        let dir = std::env::temp_dir().join(format!("sra-ce-jsonl-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let manager = LogManager::new();
+        let file = RotatingFile::create(&dir, "log-test", "jsonl", Default::default()).unwrap();
+        let path = file.path().to_path_buf();
+        *manager.jsonl_file.lock().unwrap() = Some(file);
+
+        manager.log(LogSource::Backend, LogLevel::INFO, "启动".to_string()).unwrap();
+        manager
+            .record(
+                LogSource::Process,
+                LogLevel::ERR,
+                "出错了".to_string(),
+                Some("default".to_string()),
+                Some("13:45:30".to_string()),
+                Some("default-20251129134530000".to_string()),
+            )
+            .unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<serde_json::Value> = content.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["seq"], 1);
+        assert_eq!(lines[0]["source"], "后端");
+        assert!(lines[0]["run_id"].is_null());
+        assert_eq!(lines[1]["seq"], 2);
+        assert_eq!(lines[1]["level"], "ERR");
+        assert_eq!(lines[1]["process_time"], "13:45:30");
+        assert_eq!(lines[1]["instance"], "default");
+        assert_eq!(lines[1]["run_id"], "default-20251129134530000");
+        assert_eq!(manager.get_all_logs().unwrap()[1].seq, 2);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_level_thresholds() {
+        let manager = LogManager::new();
+        // 默认不过滤
+        manager.log(LogSource::Backend, LogLevel::TRACE, "[RAW] 输出".to_string()).unwrap();
+        assert_eq!(manager.get_all_logs().unwrap().len(), 1);
+
+        manager
+            .set_levels(LogLevelSettings {
+                buffer: LogLevel::INFO,
+                ..Default::default()
+            })
+            .unwrap();
+        manager.log(LogSource::Backend, LogLevel::TRACE, "[RAW] 输出".to_string()).unwrap();
+        manager.log(LogSource::Backend, LogLevel::MSG, "消息".to_string()).unwrap();
+        let logs = manager.get_all_logs().unwrap();
+        assert_eq!(logs.len(), 2);
+        assert_eq!(logs[1].message, "消息");
+
+        // 来源的最低级别对所有输出目标生效
+        manager
+            .set_levels(LogLevelSettings {
+                backend: LogLevel::WARN,
+                ..Default::default()
+            })
+            .unwrap();
+        manager.log(LogSource::Backend, LogLevel::INFO, "后端".to_string()).unwrap();
+        manager.log(LogSource::Process, LogLevel::TRACE, "进程端".to_string()).unwrap();
+        manager.log(LogSource::Backend, LogLevel::ERR, "错误".to_string()).unwrap();
+        let messages: Vec<String> = manager.get_all_logs().unwrap().into_iter().map(|l| l.message).collect();
+        assert_eq!(messages, vec!["[RAW] 输出", "消息", "进程端", "错误"]);
+        assert!(LogLevel::SUCCESS.at_least(&LogLevel::INFO) && !LogLevel::DEBUG.at_least(&LogLevel::INFO));
+    }
+
+    #[test]
+    fn test_ring_buffer_and_incremental_fetch() {
+        let manager = LogManager::new();
+        manager
+            .set_levels(LogLevelSettings {
+                buffer: LogLevel::INFO,
+                ..Default::default()
+            })
+            .unwrap();
+        manager.set_buffer_capacity(3).unwrap();
+        for i in 1..=5 {
+            manager.log(LogSource::Backend, LogLevel::INFO, format!("第 {} 条", i)).unwrap();
+        }
+        let seqs = |batch: &LogBatch| batch.logs.iter().map(|l| l.seq).collect::<Vec<_>>();
+
+        let all = manager.get_logs_since(0, None).unwrap();
+        assert_eq!(seqs(&all), vec![3, 4, 5]);
+        assert!(all.gap && !all.has_more);
+        assert_eq!(all.latest_seq, 5);
+
+        let batch = manager.get_logs_since(3, Some(1)).unwrap();
+        assert_eq!(seqs(&batch), vec![4]);
+        assert!(!batch.gap && batch.has_more);
+        assert!(manager.get_logs_since(5, None).unwrap().logs.is_empty());
+
+        // 只写入文件、未进入缓冲区的日志同样占用序号
+        manager.log(LogSource::Backend, LogLevel::DEBUG, "调试".to_string()).unwrap();
+        manager.log(LogSource::Backend, LogLevel::WARN, "第 7 条".to_string()).unwrap();
+        let batch = manager.get_logs_since(5, None).unwrap();
+        assert_eq!(seqs(&batch), vec![7]);
+        assert!(!batch.gap);
+
+        manager.set_buffer_capacity(1).unwrap();
+        assert_eq!(manager.get_all_logs().unwrap().len(), 1);
+        assert!(manager.get_logs_since(4, None).unwrap().gap);
+    }
+}
@@ -0,0 +1,589 @@
+// 模拟 SRA-cli 后端
+// 按脚本回放 SRA-cli 的交互过程（prompt、[Start]/[Done]、loguru 日志、Traceback 等），
+// 用于在没有 SRA-cli 和游戏的环境中测试进程管理、stdin 命令和日志解析
+//
+// 脚本格式（每行一条指令，# 开头为注释，单独一行 --- 分隔多次启动使用的脚本）:
+//   out: <文本>     向 stdout 输出一行
+//   err: <文本>     向 stderr 输出一行
+//   prompt          输出 sra> 提示符（不换行）
+//   sleep: <毫秒>   等待
+//   expect: <命令>  等待 stdin 收到指定命令（期间收到 exit 则正常退出）
+//   exit: <退出码>  进程退出
+//   hang            不再响应任何输入（包括 exit），直到被终止
+// 脚本执行完后进入空闲状态，直到收到 exit 或被终止
+
+use crate::backend::{ExitInfo, LaunchSpec, ProcessBackend, ProcessHandle};
+use std::io::{self, Read};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+// 脚本指令
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScriptStep {
+    Stdout(String),
+    Stderr(String),
+    Prompt,
+    Sleep(u64),
+    Expect(String),
+    Exit(i32),
+    Hang,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MockScript {
+    pub steps: Vec<ScriptStep>,
+}
+
+impl MockScript {
+    // 解析单个脚本
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut steps = Vec::new();
+        for (index, raw) in text.lines().enumerate() {
+            let line = raw.trim_end();
+            if line.trim().is_empty() || line.trim_start().starts_with('#') {
+                continue;
+            }
+            let (command, argument) = match line.split_once(':') {
+                Some((command, argument)) => (command.trim(), argument.strip_prefix(' ').unwrap_or(argument)),
+                None => (line.trim(), ""),
+            };
+            let step = match command {
+                "out" => ScriptStep::Stdout(argument.to_string()),
+                "err" => ScriptStep::Stderr(argument.to_string()),
+                "prompt" => ScriptStep::Prompt,
+                "hang" => ScriptStep::Hang,
+                "sleep" => ScriptStep::Sleep(
+                    argument.trim().parse().map_err(|_| format!("第 {} 行: 无效的等待时间", index + 1))?,
+                ),
+                "expect" => ScriptStep::Expect(argument.trim().to_string()),
+                "exit" => ScriptStep::Exit(
+                    argument.trim().parse().map_err(|_| format!("第 {} 行: 无效的退出码", index + 1))?,
+                ),
+                _ => return Err(format!("第 {} 行: 未知指令 {}", index + 1, command)),
+            };
+            steps.push(step);
+        }
+        Ok(Self { steps })
+    }
+
+    // 解析以 --- 分隔的多个脚本（第 N 次启动使用第 N 个脚本，超出后重复最后一个）
+    pub fn parse_sessions(text: &str) -> Result<Vec<Self>, String> {
+        let mut scripts = Vec::new();
+        let mut current = String::new();
+        for line in text.lines() {
+            if line.trim() == "---" {
+                scripts.push(Self::parse(&current)?);
+                current.clear();
+            } else {
+                current.push_str(line);
+                current.push('\n');
+            }
+        }
+        scripts.push(Self::parse(&current)?);
+        Ok(scripts)
+    }
+}
+
+// 模拟后端
+pub struct MockBackend {
+    scripts: Vec<MockScript>,
+    spawns: AtomicUsize,
+    received: Arc<Mutex<Vec<String>>>, // 所有会话收到的 stdin 命令
+    launches: Mutex<Vec<LaunchSpec>>,  // 每次启动的参数
+}
+
+impl MockBackend {
+    pub fn new(scripts: Vec<MockScript>) -> Self {
+        Self {
+            scripts,
+            spawns: AtomicUsize::new(0),
+            received: Arc::new(Mutex::new(Vec::new())),
+            launches: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn from_file(path: &str) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read mock script: {}", e))?;
+        Ok(Self::new(MockScript::parse_sessions(&content)?))
+    }
+
+    pub fn spawn_count(&self) -> usize {
+        self.spawns.load(Ordering::SeqCst)
+    }
+
+    pub fn received_commands(&self) -> Vec<String> {
+        self.received.lock().map(|r| r.clone()).unwrap_or_default()
+    }
+
+    pub fn launch_specs(&self) -> Vec<LaunchSpec> {
+        self.launches.lock().map(|l| l.clone()).unwrap_or_default()
+    }
+}
+
+impl ProcessBackend for MockBackend {
+    fn name(&self) -> &str {
+        "mock"
+    }
+
+    fn resolve_program(&self) -> Result<(PathBuf, PathBuf), String> {
+        let working_dir = std::env::temp_dir();
+        Ok((working_dir.join("SRA-cli.mock"), working_dir))
+    }
+
+    fn spawn(&self, spec: &LaunchSpec) -> Result<Box<dyn ProcessHandle>, String> {
+        if let Ok(mut launches) = self.launches.lock() {
+            launches.push(spec.clone());
+        }
+        let index = self.spawns.fetch_add(1, Ordering::SeqCst);
+        let script = self
+            .scripts
+            .get(index.min(self.scripts.len().saturating_sub(1)))
+            .cloned()
+            .unwrap_or_default();
+        Ok(Box::new(MockProcess::start(index as u32 + 1, script, self.received.clone())))
+    }
+}
+
+// 基于通道的读取端，发送端全部关闭后返回 EOF
+struct ChannelReader {
+    rx: Receiver<Vec<u8>>,
+    pending: Vec<u8>,
+    pos: usize,
+}
+
+impl Read for ChannelReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pos >= self.pending.len() {
+            match self.rx.recv() {
+                Ok(chunk) => {
+                    self.pending = chunk;
+                    self.pos = 0;
+                }
+                Err(_) => return Ok(0),
+            }
+        }
+        let n = buf.len().min(self.pending.len() - self.pos);
+        buf[..n].copy_from_slice(&self.pending[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+struct MockProcess {
+    id: u32,
+    stdout: Option<ChannelReader>,
+    stderr: Option<ChannelReader>,
+    stdin: Sender<String>,
+    exit: Arc<Mutex<Option<ExitInfo>>>,
+    killed: Arc<AtomicBool>,
+}
+
+// 脚本执行线程持有的输出端
+struct ScriptRunner {
+    stdout: Sender<Vec<u8>>,
+    stderr: Sender<Vec<u8>>,
+    stdin: Receiver<String>,
+    received: Arc<Mutex<Vec<String>>>,
+    killed: Arc<AtomicBool>,
+}
+
+impl ScriptRunner {
+    // 等待下一条 stdin 命令；返回 None 表示被终止或 stdin 已关闭
+    fn next_command(&self) -> Option<String> {
+        loop {
+            if self.killed.load(Ordering::SeqCst) {
+                return None;
+            }
+            match self.stdin.recv_timeout(Duration::from_millis(20)) {
+                Ok(command) => {
+                    if let Ok(mut received) = self.received.lock() {
+                        received.push(command.clone());
+                    }
+                    return Some(command);
+                }
+                Err(RecvTimeoutError::Timeout) => continue,
+                Err(RecvTimeoutError::Disconnected) => return None,
+            }
+        }
+    }
+
+    // 执行脚本，返回退出码（None 表示被终止）
+    fn run(&self, script: MockScript) -> Option<i32> {
+        for step in script.steps {
+            if self.killed.load(Ordering::SeqCst) {
+                return None;
+            }
+            match step {
+                ScriptStep::Stdout(text) => {
+                    let _ = self.stdout.send(format!("{}\n", text).into_bytes());
+                }
+                ScriptStep::Stderr(text) => {
+                    let _ = self.stderr.send(format!("{}\n", text).into_bytes());
+                }
+                ScriptStep::Prompt => {
+                    let _ = self.stdout.send(b"sra> ".to_vec());
+                }
+                ScriptStep::Sleep(ms) => thread::sleep(Duration::from_millis(ms)),
+                ScriptStep::Expect(expected) => loop {
+                    let command = self.next_command()?;
+                    if command == expected {
+                        break;
+                    }
+                    if command == "exit" {
+                        return Some(0);
+                    }
+                },
+                ScriptStep::Exit(code) => return Some(code),
+                ScriptStep::Hang => {
+                    while self.next_command().is_some() {}
+                    return None;
+                }
+            }
+        }
+
+        // 脚本结束后保持空闲，直到收到 exit
+        loop {
+            if self.next_command()? == "exit" {
+                return Some(0);
+            }
+        }
+    }
+}
+
+impl MockProcess {
+    fn start(id: u32, script: MockScript, received: Arc<Mutex<Vec<String>>>) -> Self {
+        let (stdout_tx, stdout_rx) = mpsc::channel();
+        let (stderr_tx, stderr_rx) = mpsc::channel();
+        let (stdin_tx, stdin_rx) = mpsc::channel();
+        let exit = Arc::new(Mutex::new(None));
+        let killed = Arc::new(AtomicBool::new(false));
+
+        let runner = ScriptRunner {
+            stdout: stdout_tx,
+            stderr: stderr_tx,
+            stdin: stdin_rx,
+            received,
+            killed: killed.clone(),
+        };
+        let thread_exit = exit.clone();
+        thread::spawn(move || {
+            let code = runner.run(script);
+            if let Ok(mut exit) = thread_exit.lock() {
+                if exit.is_none() {
+                    *exit = Some(ExitInfo { code });
+                }
+            }
+            // runner 在此处被丢弃，stdout/stderr 随之关闭
+        });
+
+        Self {
+            id,
+            stdout: Some(ChannelReader { rx: stdout_rx, pending: Vec::new(), pos: 0 }),
+            stderr: Some(ChannelReader { rx: stderr_rx, pending: Vec::new(), pos: 0 }),
+            stdin: stdin_tx,
+            exit,
+            killed,
+        }
+    }
+}
+
+impl ProcessHandle for MockProcess {
+    fn id(&self) -> u32 {
+        self.id
+    }
+
+    fn take_stdout(&mut self) -> Option<Box<dyn Read + Send>> {
+        self.stdout.take().map(|r| Box::new(r) as Box<dyn Read + Send>)
+    }
+
+    fn take_stderr(&mut self) -> Option<Box<dyn Read + Send>> {
+        self.stderr.take().map(|r| Box::new(r) as Box<dyn Read + Send>)
+    }
+
+    fn write_stdin(&mut self, data: &[u8]) -> io::Result<()> {
+        if self.exit.lock().map(|e| e.is_some()).unwrap_or(true) {
+            return Err(io::Error::new(io::ErrorKind::BrokenPipe, "进程已退出"));
+        }
+        for line in String::from_utf8_lossy(data).lines() {
+            self.stdin
+                .send(line.trim().to_string())
+                .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "进程已退出"))?;
+        }
+        Ok(())
+    }
+
+    fn try_wait(&mut self) -> io::Result<Option<ExitInfo>> {
+        Ok(*self.exit.lock().unwrap())
+    }
+
+    fn kill(&mut self) -> io::Result<()> {
+        self.killed.store(true, Ordering::SeqCst);
+        if let Ok(mut exit) = self.exit.lock() {
+            if exit.is_none() {
+                *exit = Some(ExitInfo { code: None });
+            }
+        }
+        Ok(())
+    }
+
+    fn wait(&mut self) -> io::Result<ExitInfo> {
+        loop {
+            if let Some(exit) = self.try_wait()? {
+                return Ok(exit);
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::types::{LaunchRequest, SraStatus, DEFAULT_INSTANCE};
+    use crate::{backend, events, post_actions, process, run_history, settings};
+    use std::sync::MutexGuard;
+
+    // 进程管理使用全局状态，集成测试需要串行执行
+    static TEST_LOCK: Mutex<()> = Mutex::new(());
+
+    // 启动时不输出提示符：测试无法确定启动提示符是否已被读取，send_command_and_wait 可能把它当作命令的响应
+    pub(crate) const BASIC_SESSION: &str = "\
+out: 14:20:07[40401] | INFO | SRA-cli 已启动
+expect: task run Default
+out: [Start] Default
+out: 14:20:08[40401] | INFO | 当前配置: Default
+out: 14:20:09[40401] | SUCCESS | 任务完成
+out: [Done] Default
+prompt
+";
+
+    // 重置测试数据目录中的记录并使用模拟后端，返回测试锁（各模块的集成测试共用）
+    pub(crate) fn setup(script: &str) -> (MutexGuard<'static, ()>, Arc<MockBackend>) {
+        let guard = TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let data_dir = crate::paths::data_dir().unwrap();
+        std::fs::create_dir_all(&data_dir).unwrap();
+        // 上一个测试结束的运行在后台保存，先写入再清空记录
+        run_history::save_finished_runs();
+        let _ = std::fs::remove_dir_all(data_dir.join("SRA-CE-History"));
+        let _ = std::fs::remove_file(data_dir.join("SRA-CE-Schedules.json"));
+
+        // 让看门狗立即重启，避免测试等待
+        settings::save_watchdog_settings(settings::WatchdogSettings {
+            base_delay_secs: 0,
+            ..Default::default()
+        })
+        .unwrap();
+        settings::save_stop_policy(settings::StopPolicy::default()).unwrap();
+        settings::save_transcript_settings(settings::TranscriptSettings::default()).unwrap();
+        settings::save_task_timeout_settings(settings::TaskTimeoutSettings::default()).unwrap();
+        settings::save_post_action_settings(settings::PostActionSettings::default()).unwrap();
+        post_actions::set_headless(false);
+
+        let _ = process::stop_all_sra_processes();
+        let mock = Arc::new(MockBackend::new(MockScript::parse_sessions(script).unwrap()));
+        backend::set_backend(mock.clone());
+        (guard, mock)
+    }
+
+    // 收集 sra-stop-progress 事件中的步骤
+    fn collect_stop_steps() -> (u64, Arc<Mutex<Vec<String>>>) {
+        let steps = Arc::new(Mutex::new(Vec::new()));
+        let sink = steps.clone();
+        let id = events::subscribe(move |event, payload| {
+            if event == "sra-stop-progress" {
+                if let Some(step) = payload.get("step").and_then(|s| s.as_str()) {
+                    sink.lock().unwrap().push(step.to_string());
+                }
+            }
+        });
+        (id, steps)
+    }
+
+    // 收集指定实例 sra-status-changed 事件中的状态
+    pub(crate) fn collect_statuses(instance_id: &str) -> (u64, Arc<Mutex<Vec<String>>>) {
+        let statuses = Arc::new(Mutex::new(Vec::new()));
+        let sink = statuses.clone();
+        let instance_id = instance_id.to_string();
+        let id = events::subscribe(move |event, payload| {
+            if event == "sra-status-changed" && payload.get("instance_id").and_then(|s| s.as_str()) == Some(instance_id.as_str()) {
+                if let Some(status) = payload.get("status").and_then(|s| s.as_str()) {
+                    sink.lock().unwrap().push(status.to_string());
+                }
+            }
+        });
+        (id, statuses)
+    }
+
+    #[test]
+    fn test_parse_script() {
+        let scripts = MockScript::parse_sessions("out: a\nprompt\n# 注释\nexpect: task run X\n---\nsleep: 5\nexit: 1\n").unwrap();
+        assert_eq!(scripts.len(), 2);
+        assert_eq!(
+            scripts[0].steps,
+            vec![
+                ScriptStep::Stdout("a".to_string()),
+                ScriptStep::Prompt,
+                ScriptStep::Expect("task run X".to_string()),
+            ]
+        );
+        assert_eq!(scripts[1].steps, vec![ScriptStep::Sleep(5), ScriptStep::Exit(1)]);
+        assert!(MockScript::parse("boom: 1").is_err());
+    }
+
+    #[test]
+    fn test_start_run_task_and_stop() {
+        let (_guard, mock) = setup(BASIC_SESSION);
+        let (sub, statuses) = collect_statuses(DEFAULT_INSTANCE);
+
+        process::start_sra_process_command(DEFAULT_INSTANCE, LaunchRequest::default()).unwrap();
+        assert_eq!(process::get_instance_status(DEFAULT_INSTANCE).unwrap(), "running");
+
+        let response = process::send_command_and_wait(DEFAULT_INSTANCE, "task run Default".to_string(), Some(3000)).unwrap();
+        assert!(response.lines.iter().any(|l| l.message == "当前配置: Default"));
+        assert!(process::wait_until(Duration::from_millis(1000), || process::get_instance_status(DEFAULT_INSTANCE).unwrap() == "running"));
+
+        process::stop_sra_process_command(DEFAULT_INSTANCE).unwrap();
+        assert_eq!(process::get_instance_status(DEFAULT_INSTANCE).unwrap(), "not-running");
+        events::unsubscribe(sub);
+
+        assert_eq!(mock.received_commands(), vec!["task run Default", "exit"]);
+        let statuses = statuses.lock().unwrap().clone();
+        assert!(statuses.contains(&"task-running".to_string()));
+        let history = process::get_sra_state_history(DEFAULT_INSTANCE).unwrap();
+        assert_eq!(history.last().unwrap().to.as_str(), "not-running");
+
+        let runs = run_history::query_runs(&run_history::RunQuery::default()).unwrap();
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].config.as_deref(), Some("Default"));
+        assert_eq!(runs[0].outcome, run_history::RunOutcome::Done);
+        assert!(runs[0].log_lines >= 2);
+        assert!(runs[0].errors.is_empty());
+        let game_day = crate::reset_clock::ServerRegion::Cn.game_day(&chrono::Local::now()).format("%Y-%m-%d").to_string();
+        assert_eq!(runs[0].game_day.as_deref(), Some(game_day.as_str()));
+        assert!(run_history::done_this_game_day("Default").unwrap());
+    }
+
+    #[test]
+    fn test_task_run_sends_command() {
+        let (_guard, mock) = setup(BASIC_SESSION);
+
+        process::start_sra_process_command(DEFAULT_INSTANCE, LaunchRequest::default()).unwrap();
+        let before = process::get_sra_state_history(DEFAULT_INSTANCE).unwrap().len();
+        process::task_run(DEFAULT_INSTANCE, Some("Default".to_string())).unwrap();
+        assert!(process::wait_until(Duration::from_millis(3000), || {
+            process::get_sra_state_history(DEFAULT_INSTANCE)
+                .unwrap()
+                .iter()
+                .skip(before)
+                .any(|t| t.from == SraStatus::TaskRunning && t.to == SraStatus::Idle)
+        }));
+        assert_eq!(mock.received_commands(), vec!["task run Default"]);
+        process::stop_sra_process_command(DEFAULT_INSTANCE).unwrap();
+    }
+
+    #[test]
+    fn test_crash_is_restarted_by_watchdog() {
+        let (_guard, mock) = setup("out: 14:20:07[40401] | ERROR | 崩溃\nerr: Traceback (most recent call last):\nerr: RuntimeError: boom\nexit: 1\n---\nprompt\n");
+        let (sub, statuses) = collect_statuses(DEFAULT_INSTANCE);
+
+        process::start_sra_process_command(DEFAULT_INSTANCE, LaunchRequest::default()).unwrap();
+        assert!(process::wait_until(Duration::from_millis(3000), || mock.spawn_count() == 2 && process::get_instance_status(DEFAULT_INSTANCE).unwrap() == "running"));
+        events::unsubscribe(sub);
+
+        let statuses = statuses.lock().unwrap().clone();
+        assert!(statuses.contains(&"crashed".to_string()));
+        assert!(statuses.contains(&"restarting".to_string()));
+        process::stop_sra_process_command(DEFAULT_INSTANCE).unwrap();
+    }
+
+    #[test]
+    fn test_restart_spawns_new_session() {
+        let (_guard, mock) = setup("prompt\n");
+
+        process::start_sra_process_command(DEFAULT_INSTANCE, LaunchRequest::default()).unwrap();
+        process::restart_sra_process_command(DEFAULT_INSTANCE, LaunchRequest::default()).unwrap();
+        assert_eq!(mock.spawn_count(), 2);
+        assert_eq!(process::get_instance_status(DEFAULT_INSTANCE).unwrap(), "running");
+
+        // 旧会话的读取线程退出时不能影响新进程
+        thread::sleep(Duration::from_millis(200));
+        assert_eq!(process::get_instance_status(DEFAULT_INSTANCE).unwrap(), "running");
+        process::stop_sra_process_command(DEFAULT_INSTANCE).unwrap();
+    }
+
+    #[test]
+    fn test_stop_interrupts_running_task_first() {
+        let (_guard, mock) = setup("prompt\nexpect: task run Default\nout: [Start] Default\nexpect: task stop\nout: 14:20:09[40401] | WARNING | 任务已中止\nout: [Done] Default\nprompt\n");
+        let (sub, steps) = collect_stop_steps();
+
+        process::start_sra_process_command(DEFAULT_INSTANCE, LaunchRequest::default()).unwrap();
+        process::task_run(DEFAULT_INSTANCE, Some("Default".to_string())).unwrap();
+        assert!(process::wait_until(Duration::from_millis(2000), || process::get_instance_status(DEFAULT_INSTANCE).unwrap() == "task-running"));
+
+        process::stop_sra_process_command(DEFAULT_INSTANCE).unwrap();
+        events::unsubscribe(sub);
+
+        assert_eq!(mock.received_commands(), vec!["task run Default", "task stop", "exit"]);
+        assert_eq!(*steps.lock().unwrap(), vec!["task-stop", "exit", "done"]);
+        assert_eq!(process::get_instance_status(DEFAULT_INSTANCE).unwrap(), "not-running");
+
+        // 被 task stop 中止的运行记为停止，即使 SRA-cli 输出了 [Done]
+        let query = run_history::RunQuery { config: Some("Default".to_string()), ..Default::default() };
+        let runs = run_history::query_runs(&query).unwrap();
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].outcome, run_history::RunOutcome::Stopped);
+    }
+
+    #[test]
+    fn test_concurrent_stops_run_the_pipeline_once() {
+        let (_guard, mock) = setup("prompt\nexpect: task run Default\nout: [Start] Default\nexpect: task stop\nsleep: 200\nout: [Done] Default\nprompt\n");
+        let (sub, steps) = collect_stop_steps();
+
+        process::start_sra_process_command(DEFAULT_INSTANCE, LaunchRequest::default()).unwrap();
+        process::task_run(DEFAULT_INSTANCE, Some("Default".to_string())).unwrap();
+        assert!(process::wait_until(Duration::from_millis(2000), || process::get_instance_status(DEFAULT_INSTANCE).unwrap() == "task-running"));
+
+        // 第一个停止请求正在等待任务停止时，第二个请求等待其完成
+        let first = thread::spawn(|| process::stop_sra_process_command(DEFAULT_INSTANCE));
+        assert!(process::wait_until(Duration::from_millis(2000), || mock.received_commands().len() == 2));
+        process::stop_sra_process_command(DEFAULT_INSTANCE).unwrap();
+        assert_eq!(process::get_instance_status(DEFAULT_INSTANCE).unwrap(), "not-running");
+        first.join().unwrap().unwrap();
+        events::unsubscribe(sub);
+
+        assert_eq!(mock.received_commands(), vec!["task run Default", "task stop", "exit"]);
+        assert_eq!(*steps.lock().unwrap(), vec!["task-stop", "exit", "done"]);
+    }
+
+    #[test]
+    fn test_unresponsive_process_is_killed_without_blocking_other_commands() {
+        let (_guard, _mock) = setup("prompt\nhang\n");
+        settings::save_stop_policy(settings::StopPolicy {
+            task_stop_timeout_ms: 0,
+            exit_timeout_ms: 300,
+            kill_timeout_ms: 1000,
+        })
+        .unwrap();
+        let (sub, steps) = collect_stop_steps();
+
+        process::start_sra_process_command(DEFAULT_INSTANCE, LaunchRequest::default()).unwrap();
+        let stopper = thread::spawn(|| process::stop_sra_process_command(DEFAULT_INSTANCE));
+
+        // 停止流程等待期间，其他命令不会被进程锁阻塞，能读到中间状态
+        assert!(process::wait_until(Duration::from_millis(1000), || process::get_instance_status(DEFAULT_INSTANCE).unwrap() == "stopping"));
+
+        stopper.join().unwrap().unwrap();
+        events::unsubscribe(sub);
+
+        assert_eq!(*steps.lock().unwrap(), vec!["exit", "kill", "done"]);
+        let history = process::get_sra_state_history(DEFAULT_INSTANCE).unwrap();
+        let last = history.last().unwrap();
+        assert_eq!(last.to, SraStatus::NotRunning);
+        assert_eq!(last.reason, "进程已强制终止");
+    }
+}
@@ -0,0 +1,14 @@
+// 数据目录
+// 设置、配置、日志和运行记录都保存在 %APPDATA%/SRA 下。测试使用每个测试进程独立的临时目录，
+// 不读取也不修改 APPDATA 环境变量
+
+use std::path::PathBuf;
+
+// 数据目录（%APPDATA%/SRA）
+pub fn data_dir() -> Result<PathBuf, String> {
+    if cfg!(test) {
+        return Ok(std::env::temp_dir().join(format!("sra-ce-test-{}", std::process::id())).join("SRA"));
+    }
+    let appdata = std::env::var("APPDATA").map_err(|_| "Failed to get APPDATA path")?;
+    Ok(PathBuf::from(appdata).join("SRA"))
+}
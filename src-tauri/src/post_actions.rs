@@ -0,0 +1,466 @@
+// 任务完成后的操作
+// 整个任务顺序执行完成后，根据配置中的 AfterExitGame / AfterLogout / AfterSleep / AfterShutdown / AfterExitApp
+// 倒计时执行对应的系统操作，倒计时期间可以取消；系统操作放在 trait 之后，测试时可替换，演练模式只记录不执行
+
+use crate::config;
+use crate::events;
+use crate::logger::{log, LogLevel, LogSource};
+use crate::process;
+use crate::settings::{self, PostActionSettings};
+use serde::{Deserialize, Serialize};
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// 游戏进程名
+const GAME_PROCESS_NAME: &str = "StarRail.exe";
+
+// 完成后要执行的操作（多个配置时取并集）
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct PostActions {
+    pub exit_game: bool,
+    pub logout: bool,
+    pub sleep: bool,
+    pub shutdown: bool,
+    pub exit_app: bool,
+}
+
+impl PostActions {
+    // 从配置内容读取 After* 开关
+    pub fn from_config(config: &serde_json::Value) -> Self {
+        let flag = |key: &str| config.get(key).and_then(|v| v.as_bool()).unwrap_or(false);
+        Self {
+            exit_game: flag("AfterExitGame"),
+            logout: flag("AfterLogout"),
+            sleep: flag("AfterSleep"),
+            shutdown: flag("AfterShutdown"),
+            exit_app: flag("AfterExitApp"),
+        }
+    }
+
+    pub fn union(self, other: Self) -> Self {
+        Self {
+            exit_game: self.exit_game || other.exit_game,
+            logout: self.logout || other.logout,
+            sleep: self.sleep || other.sleep,
+            shutdown: self.shutdown || other.shutdown,
+            exit_app: self.exit_app || other.exit_app,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    // 实际执行的步骤：先退出游戏，关机 / 注销 / 睡眠只执行优先级最高的一项，最后退出程序
+    pub fn steps(&self) -> Vec<PostActionStep> {
+        let mut steps = Vec::new();
+        if self.exit_game {
+            steps.push(PostActionStep::ExitGame);
+        }
+        if self.shutdown {
+            steps.push(PostActionStep::Shutdown);
+        } else if self.logout {
+            steps.push(PostActionStep::Logout);
+        } else if self.sleep {
+            steps.push(PostActionStep::Sleep);
+        }
+        if self.exit_app {
+            steps.push(PostActionStep::ExitApp);
+        }
+        steps
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PostActionStep {
+    ExitGame,
+    Logout,
+    Sleep,
+    Shutdown,
+    ExitApp,
+}
+
+impl PostActionStep {
+    fn description(&self) -> &str {
+        match self {
+            PostActionStep::ExitGame => "退出游戏",
+            PostActionStep::Logout => "注销",
+            PostActionStep::Sleep => "睡眠",
+            PostActionStep::Shutdown => "关机",
+            PostActionStep::ExitApp => "退出 SRA",
+        }
+    }
+}
+
+// 系统操作
+pub trait SystemActions: Send + Sync {
+    fn exit_game(&self) -> Result<(), String>;
+    fn logout(&self) -> Result<(), String>;
+    fn sleep(&self) -> Result<(), String>;
+    fn shutdown(&self) -> Result<(), String>;
+    fn exit_app(&self) -> Result<(), String>;
+}
+
+// 通过系统命令执行
+pub struct WindowsSystemActions;
+
+fn run_system_command(program: &str, args: &[&str]) -> Result<(), String> {
+    let mut command = Command::new(program);
+    command.args(args);
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        command.creation_flags(CREATE_NO_WINDOW);
+    }
+    let status = command.status().map_err(|e| format!("执行 {} 失败: {}", program, e))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("{} 执行失败（{}）", program, status))
+    }
+}
+
+impl SystemActions for WindowsSystemActions {
+    fn exit_game(&self) -> Result<(), String> {
+        run_system_command("taskkill", &["/IM", GAME_PROCESS_NAME, "/F"])
+    }
+
+    fn logout(&self) -> Result<(), String> {
+        run_system_command("shutdown", &["/l"])
+    }
+
+    fn sleep(&self) -> Result<(), String> {
+        run_system_command("rundll32.exe", &["powrprof.dll,SetSuspendState", "0,1,0"])
+    }
+
+    fn shutdown(&self) -> Result<(), String> {
+        run_system_command("shutdown", &["/s", "/t", "0"])
+    }
+
+    fn exit_app(&self) -> Result<(), String> {
+        // 退出前先停止所有 SRA-cli 实例，避免留下无人管理的子进程
+        if let Err(e) = process::stop_all_sra_processes() {
+            let _ = log(LogSource::Backend, LogLevel::WARN, format!("退出前停止 SRA 进程失败: {}", e));
+        }
+        events::exit_app(0);
+        Ok(())
+    }
+}
+
+static SYSTEM_ACTIONS: Mutex<Option<Arc<dyn SystemActions>>> = Mutex::new(None);
+
+// 替换系统操作（测试使用）
+pub fn set_system_actions(actions: Arc<dyn SystemActions>) {
+    if let Ok(mut guard) = SYSTEM_ACTIONS.lock() {
+        *guard = Some(actions);
+    }
+}
+
+fn current_system_actions() -> Arc<dyn SystemActions> {
+    SYSTEM_ACTIONS
+        .lock()
+        .ok()
+        .and_then(|guard| guard.clone())
+        .unwrap_or_else(|| Arc::new(WindowsSystemActions))
+}
+
+// 无界面模式（SRA-CE-cli 的 run 和 daemon）不执行退出程序：
+// 命令行自身负责输出结果和退出，常驻进程不能被计划任务或远程调用触发的完成后操作结束
+static HEADLESS: AtomicBool = AtomicBool::new(false);
+
+pub fn set_headless(headless: bool) {
+    HEADLESS.store(headless, Ordering::SeqCst);
+}
+
+// 倒计时编号
+static NEXT_COUNTDOWN_ID: AtomicU64 = AtomicU64::new(1);
+
+// 进行中的倒计时（开始新的倒计时或取消时替换，使原倒计时失效）
+static ACTIVE_COUNTDOWN: Mutex<Option<u64>> = Mutex::new(None);
+
+// 倒计时事件
+#[derive(Debug, Clone, Serialize)]
+pub struct CountdownEvent {
+    pub remaining_secs: u64,
+    pub steps: Vec<PostActionStep>,
+    pub dry_run: bool,
+}
+
+// 操作执行事件
+#[derive(Debug, Clone, Serialize)]
+pub struct ExecutedEvent {
+    pub step: PostActionStep,
+    pub dry_run: bool,
+    pub error: Option<String>,
+}
+
+fn is_active(id: u64) -> bool {
+    ACTIVE_COUNTDOWN.lock().is_ok_and(|active| *active == Some(id))
+}
+
+// 汇总任务顺序中各配置的完成后操作
+pub fn actions_for_configs(configs: &[String]) -> PostActions {
+    configs
+        .iter()
+        .filter_map(|name| config::load_config(name.clone()).ok())
+        .map(|value| PostActions::from_config(&value))
+        .fold(PostActions::default(), PostActions::union)
+}
+
+// 开始倒计时，结束后执行操作（已有倒计时时替换）
+pub fn schedule(actions: PostActions) -> Result<(), String> {
+    if let Some(countdown) = prepare(actions)? {
+        thread::spawn(move || countdown.run());
+    }
+    Ok(())
+}
+
+// 开始倒计时并等待操作执行完成（无界面模式使用，进程退出前需要等待）
+pub fn schedule_and_wait(actions: PostActions) -> Result<(), String> {
+    if let Some(countdown) = prepare(actions)? {
+        countdown.run();
+    }
+    Ok(())
+}
+
+struct Countdown {
+    id: u64,
+    steps: Vec<PostActionStep>,
+    policy: PostActionSettings,
+}
+
+// 登记倒计时，没有要执行的操作时返回 None
+fn prepare(mut actions: PostActions) -> Result<Option<Countdown>, String> {
+    if HEADLESS.load(Ordering::SeqCst) {
+        actions.exit_app = false;
+    }
+    if actions.is_empty() {
+        return Ok(None);
+    }
+    let policy = settings::load_ce_settings()?.post_actions;
+    let id = NEXT_COUNTDOWN_ID.fetch_add(1, Ordering::SeqCst);
+    *ACTIVE_COUNTDOWN.lock().map_err(|e| format!("Lock error: {}", e))? = Some(id);
+    let steps = actions.steps();
+    let names: Vec<&str> = steps.iter().map(|s| s.description()).collect();
+    let _ = log(
+        LogSource::Backend,
+        LogLevel::INFO,
+        format!(
+            "任务全部完成，{} 秒后执行: {}{}",
+            policy.countdown_secs,
+            names.join("、"),
+            if policy.dry_run { "（演练模式）" } else { "" }
+        ),
+    );
+    Ok(Some(Countdown { id, steps, policy }))
+}
+
+impl Countdown {
+    fn run(self) {
+        let started = Instant::now();
+        let total = Duration::from_secs(self.policy.countdown_secs);
+        let mut last_reported = None;
+        loop {
+            if !is_active(self.id) {
+                return;
+            }
+            let elapsed = started.elapsed();
+            if elapsed >= total {
+                break;
+            }
+            let remaining = ((total - elapsed).as_millis() as u64).div_ceil(1000);
+            if last_reported != Some(remaining) {
+                last_reported = Some(remaining);
+                events::emit("post-action-countdown", CountdownEvent {
+                    remaining_secs: remaining,
+                    steps: self.steps.clone(),
+                    dry_run: self.policy.dry_run,
+                });
+            }
+            thread::sleep(Duration::from_millis(100));
+        }
+        // 倒计时结束时在锁内确认未被取消，之后再取消无效
+        let confirmed = match ACTIVE_COUNTDOWN.lock() {
+            Ok(mut active) if *active == Some(self.id) => {
+                *active = None;
+                true
+            }
+            _ => false,
+        };
+        if confirmed {
+            execute(&self.steps, self.policy.dry_run);
+        }
+    }
+}
+
+// 取消倒计时，返回是否有正在进行的倒计时
+pub fn cancel() -> Result<bool, String> {
+    let cancelled = ACTIVE_COUNTDOWN
+        .lock()
+        .map_err(|e| format!("Lock error: {}", e))?
+        .take()
+        .is_some();
+    if cancelled {
+        let _ = log(LogSource::Backend, LogLevel::INFO, "已取消任务完成后的操作".to_string());
+        events::emit("post-action-cancelled", ());
+    }
+    Ok(cancelled)
+}
+
+// 依次执行操作，演练模式下只记录
+fn execute(steps: &[PostActionStep], dry_run: bool) {
+    let actions = current_system_actions();
+    for step in steps {
+        if dry_run {
+            let _ = log(LogSource::Backend, LogLevel::INFO, format!("[演练] 将执行: {}", step.description()));
+            events::emit("post-action-executed", ExecutedEvent { step: *step, dry_run: true, error: None });
+            continue;
+        }
+
+        let _ = log(LogSource::Backend, LogLevel::INFO, format!("正在执行: {}", step.description()));
+        let result = match step {
+            PostActionStep::ExitGame => actions.exit_game(),
+            PostActionStep::Logout => actions.logout(),
+            PostActionStep::Sleep => actions.sleep(),
+            PostActionStep::Shutdown => actions.shutdown(),
+            PostActionStep::ExitApp => actions.exit_app(),
+        };
+        if let Err(ref e) = result {
+            let _ = log(LogSource::Backend, LogLevel::ERR, format!("{}失败: {}", step.description(), e));
+        }
+        events::emit("post-action-executed", ExecutedEvent { step: *step, dry_run: false, error: result.err() });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_backend::tests::setup;
+    use std::sync::atomic::AtomicUsize;
+
+    // 记录调用的系统操作
+    #[derive(Default)]
+    struct RecordingActions {
+        pub calls: Mutex<Vec<&'static str>>,
+    }
+
+    impl RecordingActions {
+        fn record(&self, name: &'static str) -> Result<(), String> {
+            self.calls.lock().unwrap().push(name);
+            Ok(())
+        }
+    }
+
+    impl SystemActions for RecordingActions {
+        fn exit_game(&self) -> Result<(), String> {
+            self.record("exit_game")
+        }
+        fn logout(&self) -> Result<(), String> {
+            self.record("logout")
+        }
+        fn sleep(&self) -> Result<(), String> {
+            self.record("sleep")
+        }
+        fn shutdown(&self) -> Result<(), String> {
+            self.record("shutdown")
+        }
+        fn exit_app(&self) -> Result<(), String> {
+            self.record("exit_app")
+        }
+    }
+
+    #[test]
+    fn test_flags_are_merged_and_ordered() {
+        let a = PostActions::from_config(&serde_json::json!({ "AfterExitGame": true, "AfterSleep": true }));
+        let b = PostActions::from_config(&serde_json::json!({ "AfterShutdown": true, "AfterExitApp": false }));
+        assert!(PostActions::from_config(&serde_json::json!({})).is_empty());
+
+        let merged = a.union(b);
+        // 关机优先于睡眠，只执行一项
+        assert_eq!(merged.steps(), vec![PostActionStep::ExitGame, PostActionStep::Shutdown]);
+        assert_eq!(a.steps(), vec![PostActionStep::ExitGame, PostActionStep::Sleep]);
+    }
+
+    #[test]
+    fn test_post_actions_countdown_cancel_and_dry_run() {
+        let (_guard, _mock) = setup("");
+        let recorder = Arc::new(RecordingActions::default());
+        set_system_actions(recorder.clone());
+        let actions = PostActions { exit_game: true, sleep: true, ..Default::default() };
+
+        // 倒计时期间取消，不执行任何操作
+        settings::save_post_action_settings(settings::PostActionSettings { countdown_secs: 1, dry_run: false }).unwrap();
+        let countdown = Arc::new(AtomicUsize::new(0));
+        let counter = countdown.clone();
+        let sub = events::subscribe(move |event, _| {
+            if event == "post-action-countdown" {
+                counter.fetch_add(1, Ordering::SeqCst);
+            }
+        });
+        schedule(actions).unwrap();
+        assert!(process::wait_until(Duration::from_millis(1000), || countdown.load(Ordering::SeqCst) > 0));
+        // 倒计时结束时在锁内确认未被取消，取消成功后不会再执行
+        assert!(cancel().unwrap());
+        events::unsubscribe(sub);
+        assert!(!cancel().unwrap());
+
+        // 演练模式只记录（倒计时为 0 时 schedule_and_wait 同步执行完成）
+        settings::save_post_action_settings(settings::PostActionSettings { countdown_secs: 0, dry_run: true }).unwrap();
+        schedule_and_wait(actions).unwrap();
+        assert!(recorder.calls.lock().unwrap().is_empty());
+
+        settings::save_post_action_settings(settings::PostActionSettings { countdown_secs: 0, dry_run: false }).unwrap();
+        schedule_and_wait(actions).unwrap();
+        assert_eq!(*recorder.calls.lock().unwrap(), vec!["exit_game", "sleep"]);
+    }
+
+    #[test]
+    fn test_headless_scheduled_task_order_does_not_exit() {
+        let (_guard, mock) = setup("prompt\nexpect: task run Headless\nout: [Start] Headless\nout: [Done] Headless\nprompt\n");
+        let recorder = Arc::new(RecordingActions::default());
+        set_system_actions(recorder.clone());
+        set_headless(true);
+        settings::save_post_action_settings(settings::PostActionSettings { countdown_secs: 1, dry_run: false }).unwrap();
+        // 倒计时事件中带有将要执行的全部步骤
+        let steps = Arc::new(Mutex::new(None));
+        let sink = steps.clone();
+        let sub = events::subscribe(move |event, payload| {
+            if event == "post-action-countdown" {
+                sink.lock().unwrap().get_or_insert_with(|| payload["steps"].clone());
+            }
+        });
+
+        // 任务顺序中的配置开启了退出游戏和退出程序
+        config::create_new_config("Headless".to_string()).unwrap();
+        let mut headless_config = config::load_config("Headless".to_string()).unwrap();
+        headless_config["AfterExitGame"] = serde_json::json!(true);
+        headless_config["AfterExitApp"] = serde_json::json!(true);
+        config::save_config(headless_config).unwrap();
+        let task_order = config::load_task_order().unwrap();
+        config::save_task_order(vec!["Headless".to_string()]).unwrap();
+
+        let rule = crate::scheduler::save_schedule(crate::scheduler::ScheduleRule {
+            name: "任务顺序".to_string(),
+            cron: "0 4 * * *".to_string(),
+            target: crate::scheduler::ScheduleTarget::TaskOrder,
+            ..Default::default()
+        })
+        .unwrap();
+        crate::scheduler::run_schedule_now(&rule.id).unwrap();
+        assert!(process::wait_until(Duration::from_millis(5000), || !recorder.calls.lock().unwrap().is_empty()));
+        events::unsubscribe(sub);
+
+        config::save_task_order(task_order).unwrap();
+        config::delete_config("Headless".to_string()).unwrap();
+        // 只退出游戏，不结束常驻进程
+        assert_eq!(*steps.lock().unwrap(), Some(serde_json::json!(["exit-game"])));
+        assert_eq!(recorder.calls.lock().unwrap()[0], "exit_game");
+        assert_eq!(mock.received_commands(), vec!["task run Headless"]);
+        process::stop_sra_process_command(crate::types::DEFAULT_INSTANCE).unwrap();
+    }
+}
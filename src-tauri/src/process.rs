@@ -1,443 +1,880 @@
 // SRA 进程管理模块
+// 支持同时运行多个 SRA-cli 实例（按实例 ID 区分，如不同账号/配置），每个实例有独立的状态、解析器和 stdin
 
-use crate::types::{SraProcess, SraStatus};
-use crate::logger::{log, LogSource};
-use crate::sra_parser;
-use std::sync::Mutex;
-use std::process::{Child, Command, Stdio};
-use std::io::{BufRead, BufReader, Write};
+use crate::types::{CaptureEvent, CaptureSlot, CommandResponse, InstanceStatus, LaunchRequest, SraProcess, SraStatus, StatusEvent, StopProgress, DEFAULT_INSTANCE};
+use crate::logger::{log_instance, LogSource};
+use crate::sra_parser::{ParsedLog, SraLogParser};
+use crate::args::split_args;
+use crate::backend::{self, LaunchSpec, ProcessHandle};
+use crate::events;
+use crate::line_codec::{CarriageReturnMode, LineCodec, OutputEncoding};
+use crate::redact;
+use crate::run_history;
+use crate::settings;
+use crate::state::StateTransition;
+use crate::transcript::{self, TranscriptRecorder};
+use crate::watchdog;
+use std::collections::BTreeMap;
+use std::sync::{mpsc, Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::io::Read;
 use std::thread;
-use std::time::Duration;
-use tauri::Emitter;
+use std::time::{Duration, Instant};
 
-pub static SRA_PROCESS: Mutex<Option<SraProcess>> = Mutex::new(None);
+// 所有实例（实例 ID -> 进程）
+pub static SRA_PROCESSES: Mutex<BTreeMap<String, SraProcess>> = Mutex::new(BTreeMap::new());
 
-// 启动 SRA-cli 进程
-pub fn start_sra_process(app_handle: tauri::AppHandle, arguments: Option<String>) -> Result<Child, String> {
-    
-    // 获取当前可执行文件目录
-    let exe_dir = std::env::current_exe()
-        .map_err(|e| format!("Failed to get current exe path: {}", e))?
-        .parent()
-        .ok_or("Failed to get parent directory")?
-        .to_path_buf();
-    
-    let (sra_cli_path, working_dir) = if let Some(target_dir) = exe_dir.parent() {
-        if let Some(src_tauri_dir) = target_dir.parent() {
-            if let Some(project_root) = src_tauri_dir.parent() {
-                let dev_path = project_root.join("StarRailAssistant").join("SRA-cli.exe");
-                let dev_working_dir = project_root.join("StarRailAssistant");
-                
-                if dev_path.exists() {
-                    (dev_path, dev_working_dir)
-                } else {
-                    let prod_path = exe_dir.join("SRA-cli.exe");
-                    if prod_path.exists() {
-                        (prod_path, exe_dir.clone())
-                    } else {
-                        let error_msg = format!(
-                            "无法找到文件 SRA-cli.exe，请检查安装完整性。\n路径: {}",
-                            prod_path.display()
-                        );
-                        let _ = app_handle.emit("console-output", error_msg.clone());
-                        return Err(error_msg);
-                    }
-                }
-            } else {
-                let prod_path = exe_dir.join("SRA-cli.exe");
-                if prod_path.exists() {
-                    (prod_path, exe_dir.clone())
-                } else {
-                    let error_msg = format!(
-                        "无法找到文件 SRA-cli.exe，请检查安装完整性。\n路径: {}",
-                        prod_path.display()
-                    );
-                    let _ = app_handle.emit("console-output", error_msg.clone());
-                    return Err(error_msg);
-                }
+// 进程会话计数器（每次启动递增，所有实例共用）
+static NEXT_SESSION_ID: AtomicU64 = AtomicU64::new(1);
+
+// 规范化前端传入的实例 ID（未指定时使用默认实例）
+pub fn instance_key(instance_id: Option<String>) -> String {
+    match instance_id {
+        Some(id) if !id.trim().is_empty() => id.trim().to_string(),
+        _ => DEFAULT_INSTANCE.to_string(),
+    }
+}
+
+// 发送状态变更事件（attempt 为自动重启的尝试次数）
+pub fn emit_status(instance_id: &str, status: &str, attempt: Option<u32>) {
+    events::emit("sra-status-changed", StatusEvent {
+        instance_id: instance_id.to_string(),
+        status: status.to_string(),
+        attempt,
+    });
+}
+
+// 在已持有锁的进程上执行状态转换，状态改变时发送事件
+fn apply_transition(proc: &mut SraProcess, to: SraStatus, reason: &str, attempt: Option<u32>) -> bool {
+    let from = proc.state.current();
+    match proc.state.transition(to, reason) {
+        Ok(changed) => {
+            if changed {
+                run_history::on_transition(&proc.instance_id, from, to);
+                emit_status(&proc.instance_id, to.as_str(), attempt);
             }
-        } else {
-            let prod_path = exe_dir.join("SRA-cli.exe");
-            if prod_path.exists() {
-                (prod_path, exe_dir.clone())
-            } else {
-                let error_msg = format!(
-                    "无法找到文件 SRA-cli.exe，请检查安装完整性。\n路径: {}",
-                    prod_path.display()
-                );
-                let _ = app_handle.emit("console-output", error_msg.clone());
-                return Err(error_msg);
+            changed
+        }
+        Err(e) => {
+            let _ = log_instance(&proc.instance_id, LogSource::Backend, crate::logger::LogLevel::WARN, e);
+            false
+        }
+    }
+}
+
+// 对指定实例执行状态转换（供看门狗等外部模块使用）
+pub fn transition_state(instance_id: &str, to: SraStatus, reason: &str, attempt: Option<u32>) -> Result<(), String> {
+    let mut processes = SRA_PROCESSES.lock().map_err(|e| format!("Lock error: {}", e))?;
+    match processes.get_mut(instance_id) {
+        Some(proc) => {
+            apply_transition(proc, to, reason, attempt);
+            Ok(())
+        }
+        None => Err(format!("实例 {} 未启动过", instance_id)),
+    }
+}
+
+// 对指定实例的本次会话执行状态转换（读取线程使用，会话已被取代时忽略）
+fn transition_session(instance_id: &str, session_id: u64, to: SraStatus, reason: &str) {
+    if let Ok(mut processes) = SRA_PROCESSES.lock() {
+        if let Some(proc) = processes.get_mut(instance_id) {
+            if proc.session_id == session_id {
+                apply_transition(proc, to, reason, None);
             }
         }
-    } else {
-        let prod_path = exe_dir.join("SRA-cli.exe");
-        if prod_path.exists() {
-            (prod_path, exe_dir.clone())
-        } else {
-            let error_msg = format!(
-                "无法找到文件 SRA-cli.exe，请检查安装完整性。\n路径: {}",
-                prod_path.display()
-            );
-            let _ = app_handle.emit("console-output", error_msg.clone());
+    }
+}
+
+// 默认的命令响应等待时间（毫秒）
+const DEFAULT_COMMAND_TIMEOUT_MS: u64 = 10_000;
+
+// 输出解析后的日志，并转发给正在等待响应的命令
+fn dispatch_parsed(instance_id: &str, capture: &CaptureSlot, parsed_logs: Vec<ParsedLog>) {
+    if parsed_logs.is_empty() {
+        return;
+    }
+    // 运行记录中的错误和命令响应会保存或返回给调用方，在此统一脱敏
+    let parsed_logs: Vec<ParsedLog> = parsed_logs
+        .into_iter()
+        .map(|parsed| ParsedLog { message: redact::redact(&parsed.message), ..parsed })
+        .collect();
+    run_history::note_logs(instance_id, &parsed_logs);
+    let guard = capture.lock().ok();
+    for parsed in parsed_logs {
+        if let Some(Some(ref tx)) = guard.as_deref() {
+            let _ = tx.send(CaptureEvent::Line(parsed.clone()));
+        }
+        let _ = crate::logger::log_process(instance_id, parsed.level, parsed.message, parsed.time);
+    }
+}
+
+// 通知正在等待响应的命令（prompt 出现或进程退出）
+fn signal_capture(capture: &CaptureSlot, event: CaptureEvent) {
+    if let Ok(guard) = capture.lock() {
+        if let Some(ref tx) = *guard {
+            let _ = tx.send(event);
+        }
+    }
+}
+
+// 将读取到的字节交给分行解码器，自动识别出 GBK 编码时提示一次
+fn push_chunk(instance_id: &str, codec: &mut LineCodec, chunk: &[u8], lines: &mut Vec<String>) {
+    let encoding = codec.active_encoding();
+    codec.push(chunk, lines);
+    if codec.active_encoding() != encoding {
+        let _ = log_instance(
+            instance_id,
+            LogSource::Backend,
+            crate::logger::LogLevel::WARN,
+            "检测到 SRA-cli 输出为 GBK 编码，已自动切换（建议在启动配置中设置 PYTHONIOENCODING=utf-8）".to_string(),
+        );
+    }
+}
+
+// 处理 stdout 的一行输出：识别 prompt 和任务标记，解析日志
+fn handle_stdout_line(instance_id: &str, session_id: u64, capture: &CaptureSlot, parser: &SraLogParser, line: &str) {
+    // 过滤空行
+    let trimmed = line.trim();
+    if trimmed.is_empty() {
+        return;
+    }
+    
+    // 调试：记录原始行
+    let _ = log_instance(instance_id, LogSource::Backend, crate::logger::LogLevel::TRACE, format!("[RAW] {}", line));
+    run_history::note_output(instance_id);
+    
+    // 如果是 prompt，触发刷新但不解析
+    if trimmed == "sra>" {
+        dispatch_parsed(instance_id, capture, parser.flush());
+        signal_capture(capture, CaptureEvent::Prompt);
+        return;
+    }
+    
+    // 解析日志
+    dispatch_parsed(instance_id, capture, parser.parse_line(line));
+    
+    // 更新运行状态
+    if line.contains("[Start]") {
+        transition_session(instance_id, session_id, SraStatus::TaskRunning, "[Start]");
+        run_history::note_start_marker(instance_id, line);
+    } else if line.contains("[Done]") {
+        transition_session(instance_id, session_id, SraStatus::Idle, "[Done]");
+    }
+}
+
+// 启动后的进程句柄和输出（读取线程在进程登记到 SRA_PROCESSES 之后才启动）
+pub struct SpawnedSession {
+    child: Box<dyn ProcessHandle>,
+    output: SessionOutput,
+}
+
+// 读取线程使用的输出管道、会话记录和解码设置
+struct SessionOutput {
+    stdout: Box<dyn Read + Send>,
+    stderr: Box<dyn Read + Send>,
+    transcript: Option<Arc<TranscriptRecorder>>,
+    output_encoding: OutputEncoding,
+    carriage_return: CarriageReturnMode,
+}
+
+// 启动 SRA-cli 进程（不持有进程锁调用）
+pub fn start_sra_process(instance_id: &str, request: &LaunchRequest) -> Result<SpawnedSession, String> {
+    let backend = backend::current_backend()?;
+    let profile = settings::find_launch_profile(request.profile.as_deref())?;
+    
+    // 定位 SRA-cli.exe（系统后端按 环境变量 -> 设置 -> 开发目录 -> 安装目录 的顺序查找）
+    let (sra_cli_path, working_dir) = match backend.resolve_program() {
+        Ok(resolved) => resolved,
+        Err(error_msg) => {
+            events::emit("console-output", redact::redact(&error_msg));
             return Err(error_msg);
         }
     };
-    
-    // 构建命令
-    let mut command = Command::new(&sra_cli_path);
+    let _ = log_instance(
+        instance_id,
+        LogSource::Backend,
+        crate::logger::LogLevel::DEBUG,
+        format!(
+            "使用 {} 后端启动 SRA-cli: {}（启动配置: {}）",
+            backend.name(),
+            sra_cli_path.display(),
+            if profile.name.is_empty() { "内置" } else { &profile.name }
+        ),
+    );
     
     // 添加 --inline 参数（隐藏 prompt）
-    command.arg("--inline");
+    let mut args = vec!["--inline".to_string()];
     
-    // 添加用户指定的参数
-    if let Some(args) = arguments {
-        for arg in args.split_whitespace() {
-            command.arg(arg);
-        }
+    // 添加启动配置和用户指定的参数
+    args.extend(split_args(&profile.arguments)?);
+    if let Some(ref arguments) = request.arguments {
+        args.extend(split_args(arguments)?);
     }
     
-    command
-        .current_dir(&working_dir)
-        .stdin(Stdio::piped())
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .env("PYTHONUNBUFFERED", "1")  // 强制 Python 无缓冲输出
-        .env("PYTHONIOENCODING", "utf-8");  // 强制 Python 使用 UTF-8 编码
+    // 启动配置可指定工作目录（相对路径基于 SRA-cli 所在目录）
+    let working_dir = match profile.working_dir {
+        Some(ref dir) if !dir.trim().is_empty() => working_dir.join(dir.trim()),
+        _ => working_dir,
+    };
     
-    // 生产环境下隐藏命令行窗口
-    #[cfg(target_os = "windows")]
-    {
-        if !cfg!(debug_assertions) {
-            use std::os::windows::process::CommandExt;
-            const CREATE_NO_WINDOW: u32 = 0x08000000;
-            command.creation_flags(CREATE_NO_WINDOW);
-        }
+    let mut env = Vec::new();
+    if profile.python_unbuffered {
+        env.push(("PYTHONUNBUFFERED".to_string(), "1".to_string()));  // 强制 Python 无缓冲输出
     }
+    if let Some(ref encoding) = profile.python_io_encoding {
+        env.push(("PYTHONIOENCODING".to_string(), encoding.clone()));  // 指定 Python 标准输入输出编码
+    }
+    env.extend(profile.env.clone());
+    
+    let spec = LaunchSpec {
+        program: sra_cli_path,
+        args,
+        working_dir,
+        env,
+    };
     
-    let mut child = command
-        .spawn()
-        .map_err(|e| format!("启动失败: {}", e))?;
+    let mut child = backend.spawn(&spec)?;
     
     // 获取标准输出和错误输出用于日志解析
-    let stdout = child.stdout.take().ok_or("Failed to capture stdout")?;
-    let stderr = child.stderr.take().ok_or("Failed to capture stderr")?;
+    let stdout = child.take_stdout().ok_or("Failed to capture stdout")?;
+    let stderr = child.take_stderr().ok_or("Failed to capture stderr")?;
+    
+    // 按设置记录会话（原始输出、stdin 命令和时间），失败不影响启动
+    let transcript_settings = settings::load_ce_settings_or_default().transcript;
+    let transcript = if transcript_settings.enabled {
+        match transcript::create_recorder(
+            instance_id,
+            &spec.program,
+            &spec.args,
+            profile.output_encoding,
+            profile.carriage_return,
+            transcript_settings.max_files,
+        ) {
+            Ok(recorder) => {
+                let _ = log_instance(
+                    instance_id,
+                    LogSource::Backend,
+                    crate::logger::LogLevel::DEBUG,
+                    format!("会话记录: {}", recorder.path().display()),
+                );
+                Some(Arc::new(recorder))
+            }
+            Err(e) => {
+                let _ = log_instance(instance_id, LogSource::Backend, crate::logger::LogLevel::WARN, format!("无法创建会话记录: {}", e));
+                None
+            }
+        }
+    } else {
+        None
+    };
+    
+    Ok(SpawnedSession {
+        child,
+        output: SessionOutput {
+            stdout,
+            stderr,
+            transcript,
+            output_encoding: profile.output_encoding,
+            carriage_return: profile.carriage_return,
+        },
+    })
+}
+
+// 启动本会话的日志刷新和输出读取线程
+fn spawn_readers(instance_id: &str, session_id: u64, capture: CaptureSlot, parser: Arc<SraLogParser>, output: SessionOutput) {
+    let SessionOutput { stdout, stderr, transcript, output_encoding, carriage_return } = output;
     
     // 启动定时刷新线程（使用更长的间隔减少 callback 调用）
+    let flush_instance = instance_id.to_string();
+    let flush_capture = capture.clone();
+    let flush_parser = parser.clone();
     thread::spawn(move || {
         loop {
             thread::sleep(Duration::from_secs(1)); // 从 300ms 改为 1s
             
             // 刷新待处理的日志
-            let flushed_logs = sra_parser::flush_parser();
-            for parsed in flushed_logs {
-                let _ = log(LogSource::Process, parsed.level, parsed.message);
-            }
+            dispatch_parsed(&flush_instance, &flush_capture, flush_parser.flush());
             
             // 检查进程是否还在运行
-            let process_guard = SRA_PROCESS.lock();
-            if let Ok(guard) = process_guard {
-                if let Some(ref proc) = *guard {
-                    if proc.child.is_none() {
-                        break; // 进程已退出，停止刷新线程
-                    }
-                } else {
-                    break;
-                }
-            } else {
-                break;
+            let still_running = SRA_PROCESSES
+                .lock()
+                .map(|processes| {
+                    processes.get(&flush_instance)
+                        .is_some_and(|proc| proc.session_id == session_id && proc.child.is_some())
+                })
+                .unwrap_or(false);
+            if !still_running {
+                break; // 进程已退出，停止刷新线程
             }
         }
     });
     
     // 启动线程读取并解析标准输出
-    let app_handle_clone = app_handle.clone();
+    let stdout_instance = instance_id.to_string();
+    let stdout_capture = capture.clone();
+    let stdout_parser = parser.clone();
+    let mut stdout_codec = LineCodec::new(output_encoding, carriage_return);
+    let stdout_transcript = transcript.clone();
     thread::spawn(move || {
-        use std::io::Read;
-        let mut reader = BufReader::new(stdout);
+        let instance_id = stdout_instance;
+        let mut reader = stdout;
         
         // 记录开始读取
-        let _ = log(LogSource::Backend, crate::logger::LogLevel::DEBUG, "开始读取 SRA 进程输出".to_string());
+        let _ = log_instance(&instance_id, LogSource::Backend, crate::logger::LogLevel::DEBUG, "开始读取 SRA 进程输出".to_string());
         
-        let mut buffer = String::new();
         let mut read_buf = [0u8; 4096];
+        let mut lines = Vec::new();
         
         loop {
             match reader.read(&mut read_buf) {
                 Ok(0) => break, // EOF
                 Ok(n) => {
-                    // 将读取的字节转换为字符串（处理 UTF-8）
-                    let text = String::from_utf8_lossy(&read_buf[..n]);
-                    buffer.push_str(&text);
+                    if let Some(ref recorder) = stdout_transcript {
+                        recorder.record_stdout(&read_buf[..n]);
+                    }
                     
-                    // 按行分割处理
-                    while let Some(pos) = buffer.find('\n') {
-                        let line = buffer[..pos].to_string();
-                        buffer = buffer[pos + 1..].to_string();
-                        
-                        // 过滤空行
-                        let trimmed = line.trim();
-                        if trimmed.is_empty() {
-                            continue;
-                        }
-                        
-                        // 调试：记录原始行
-                        let _ = log(LogSource::Backend, crate::logger::LogLevel::TRACE, format!("[RAW] {}", line));
-                        
-                        // 如果是 prompt，触发刷新但不解析
-                        if trimmed == "sra>" {
-                            let flushed_logs = sra_parser::flush_parser();
-                            for parsed in flushed_logs {
-                                let _ = log(LogSource::Process, parsed.level, parsed.message);
-                            }
-                            continue;
-                        }
-                        
-                        // 解析日志
-                        let parsed_logs = sra_parser::parse_line(&line);
-                        for parsed in parsed_logs {
-                            let _ = log(LogSource::Process, parsed.level, parsed.message);
-                        }
-                        
-                        // 更新运行状态
-                        if line.contains("[Start]") {
-                            let _ = app_handle_clone.emit("sra-status-changed", "task-running");
-                        } else if line.contains("[Done]") {
-                            let _ = app_handle_clone.emit("sra-status-changed", "running");
-                        }
+                    // 按字节分行后逐行解码，多字节字符不会被读取块截断
+                    push_chunk(&instance_id, &mut stdout_codec, &read_buf[..n], &mut lines);
+                    for line in lines.drain(..) {
+                        handle_stdout_line(&instance_id, session_id, &stdout_capture, &stdout_parser, &line);
+                    }
+                    
+                    // prompt 通常不带换行，等待输入时单独留在缓冲区中
+                    if stdout_codec.partial().trim() == "sra>" {
+                        stdout_codec.clear_partial();
+                        dispatch_parsed(&instance_id, &stdout_capture, stdout_parser.flush());
+                        signal_capture(&stdout_capture, CaptureEvent::Prompt);
                     }
                 }
                 Err(e) => {
-                    let _ = log(LogSource::Backend, crate::logger::LogLevel::ERR, format!("读取 stdout 错误: {}", e));
+                    let _ = log_instance(&instance_id, LogSource::Backend, crate::logger::LogLevel::ERR, format!("读取 stdout 错误: {}", e));
                     break;
                 }
             }
         }
         
         // 处理剩余的缓冲区内容
-        if !buffer.trim().is_empty() {
-            let parsed_logs = sra_parser::parse_line(&buffer);
-            for parsed in parsed_logs {
-                let _ = log(LogSource::Process, parsed.level, parsed.message);
-            }
+        stdout_codec.finish(&mut lines);
+        for line in lines.drain(..) {
+            handle_stdout_line(&instance_id, session_id, &stdout_capture, &stdout_parser, &line);
         }
         
         // 进程退出，刷新剩余的日志
-        let flushed_logs = sra_parser::flush_parser();
-        for parsed in flushed_logs {
-            let _ = log(LogSource::Process, parsed.level, parsed.message);
+        dispatch_parsed(&instance_id, &stdout_capture, stdout_parser.flush());
+        signal_capture(&stdout_capture, CaptureEvent::Exited);
+        
+        // 更新进程状态为已退出（仅处理本会话，避免覆盖重启后的新进程）
+        let mut processes = SRA_PROCESSES.lock().unwrap();
+        let mut exit_info = None;
+        if let Some(proc) = processes.get_mut(&instance_id) {
+            if proc.session_id == session_id {
+                let exit_code = proc.child.as_mut()
+                    .and_then(|child| child.try_wait().ok().flatten())
+                    .and_then(|exit| exit.code);
+                exit_info = Some((proc.stop_requested, exit_code, proc.launch.clone()));
+                proc.child = None;
+                if proc.stop_requested {
+                    apply_transition(proc, SraStatus::NotRunning, "进程已退出", None);
+                } else {
+                    apply_transition(proc, SraStatus::Crashed, "进程意外退出", None);
+                }
+            }
         }
+        drop(processes);
         
-        // 更新进程状态为已退出
-        let mut process_guard = SRA_PROCESS.lock().unwrap();
-        if let Some(ref mut proc) = *process_guard {
-            proc.child = None;
-            proc.status = SraStatus::NotRunning;
+        if let Some(ref recorder) = stdout_transcript {
+            recorder.record_exit(exit_info.as_ref().and_then(|(_, code, _)| *code));
         }
-        drop(process_guard);
         
-        let _ = app_handle_clone.emit("sra-status-changed", "not-running");
-        let _ = log(LogSource::Backend, crate::logger::LogLevel::WARN, "SRA 进程意外退出".to_string());
+        let (stop_requested, exit_code, launch) = match exit_info {
+            Some(info) => info,
+            None => return, // 已被新的进程会话取代
+        };
+        
+        if stop_requested {
+            let _ = log_instance(&instance_id, LogSource::Backend, crate::logger::LogLevel::INFO, "SRA 进程已退出".to_string());
+        } else {
+            let code = exit_code.map_or("未知".to_string(), |c| c.to_string());
+            let _ = log_instance(&instance_id, LogSource::Backend, crate::logger::LogLevel::WARN, format!("SRA 进程意外退出（退出码: {}）", code));
+            watchdog::handle_unexpected_exit(&instance_id, launch);
+        }
     });
     
     // 启动线程读取并解析错误输出
+    let stderr_instance = instance_id.to_string();
+    let stderr_capture = capture;
+    let mut stderr_codec = LineCodec::new(output_encoding, carriage_return);
+    let stderr_transcript = transcript.clone();
     thread::spawn(move || {
-        let reader = BufReader::new(stderr);
-        for line in reader.lines() {
-            if let Ok(line) = line {
-                if line.trim().is_empty() {
-                    continue;
+        let mut reader = stderr;
+        let mut read_buf = [0u8; 4096];
+        let mut lines = Vec::new();
+        loop {
+            match reader.read(&mut read_buf) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => {
+                    if let Some(ref recorder) = stderr_transcript {
+                        recorder.record_stderr(&read_buf[..n]);
+                    }
+                    push_chunk(&stderr_instance, &mut stderr_codec, &read_buf[..n], &mut lines);
                 }
-                
+            }
+            for line in lines.drain(..) {
                 // 解析日志（可能返回多条）
-                let parsed_logs = sra_parser::parse_line(&line);
-                for parsed in parsed_logs {
-                    let _ = log(LogSource::Process, parsed.level, parsed.message);
-                }
+                dispatch_parsed(&stderr_instance, &stderr_capture, parser.parse_line(&line));
             }
         }
+        stderr_codec.finish(&mut lines);
+        for line in lines.drain(..) {
+            dispatch_parsed(&stderr_instance, &stderr_capture, parser.parse_line(&line));
+        }
     });
-    
-    Ok(child)
 }
 
 // 启动SRA进程
-pub fn start_sra_process_command(app_handle: tauri::AppHandle, arguments: Option<String>) -> Result<(), String> {
-    let mut process_guard = SRA_PROCESS.lock().map_err(|e| format!("Lock error: {}", e))?;
-    
-    // 检查是否已经在运行
-    if let Some(ref mut proc) = *process_guard {
-        if let Some(ref mut child) = proc.child {
-            // 检查进程是否还活着
-            match child.try_wait() {
-                Ok(None) => {
-                    // 进程还在运行，不重复启动
-                    return Ok(());
-                }
-                _ => {
-                    // 进程已退出，继续启动新进程
+pub fn start_sra_process_command(instance_id: &str, request: LaunchRequest) -> Result<(), String> {
+    // 用户手动启动，清空崩溃记录
+    watchdog::reset(instance_id);
+    launch_sra_process(instance_id, request, None)
+}
+
+// 启动进程（手动启动与崩溃自动重启共用，attempt 为自动重启次数）
+// 在锁内登记会话并转为 Starting，启动进程期间（查找程序、读取启动配置、创建会话记录）不持有进程锁
+pub fn launch_sra_process(instance_id: &str, request: LaunchRequest, attempt: Option<u32>) -> Result<(), String> {
+    let (session_id, capture, parser) = {
+        let mut processes = SRA_PROCESSES.lock().map_err(|e| format!("Lock error: {}", e))?;
+        
+        // 检查是否已经在运行
+        if let Some(proc) = processes.get_mut(instance_id) {
+            if let Some(ref mut child) = proc.child {
+                // 检查进程是否还活着
+                match child.try_wait() {
+                    Ok(None) => {
+                        // 进程还在运行，不重复启动
+                        return Ok(());
+                    }
+                    _ => {
+                        // 进程已退出，继续启动新进程
+                    }
                 }
+            } else if proc.state.current() == SraStatus::Starting {
+                // 其他调用方正在启动
+                return Ok(());
             }
         }
-    }
+        
+        // 沿用上一个会话的状态机，保留状态历史
+        let mut state = processes.remove(instance_id)
+            .map(|proc| proc.state)
+            .unwrap_or_default();
+        
+        // 旧进程已退出但读取线程尚未处理，先补记退出状态
+        match state.current() {
+            SraStatus::Stopping => { let _ = state.transition(SraStatus::NotRunning, "进程已退出"); }
+            SraStatus::Starting | SraStatus::Idle | SraStatus::TaskRunning => {
+                let _ = state.transition(SraStatus::Crashed, "进程已退出");
+            }
+            _ => {}
+        }
+        
+        let session_id = NEXT_SESSION_ID.fetch_add(1, Ordering::SeqCst);
+        let capture: CaptureSlot = Arc::new(Mutex::new(None));
+        // 每个实例使用独立的解析器，避免多行日志（如 Traceback）互相混杂
+        let parser = Arc::new(SraLogParser::new());
+        let proc = processes.entry(instance_id.to_string()).or_insert(SraProcess {
+            instance_id: instance_id.to_string(),
+            child: None,
+            state,
+            session_id,
+            stop_requested: false,
+            stopping: false,
+            launch: request.clone(),
+            capture: capture.clone(),
+            parser: parser.clone(),
+            transcript: None,
+        });
+        if !apply_transition(proc, SraStatus::Starting, "启动进程", attempt) {
+            return Err(format!("当前状态无法启动: {}", proc.state.current().as_str()));
+        }
+        (session_id, capture, parser)
+    };
     
-    // 初始化解析器
-    sra_parser::init_parser();
+    // 启动新进程（不持有锁）
+    let spawned = start_sra_process(instance_id, &request);
     
-    // 启动新进程
-    match start_sra_process(app_handle.clone(), arguments.clone()) {
-        Ok(child) => {
-            *process_guard = Some(SraProcess {
-                child: Some(child),
-                status: SraStatus::Running,
-            });
+    // 登记进程（启动期间会话可能已被停止）
+    let mut processes = SRA_PROCESSES.lock().map_err(|e| format!("Lock error: {}", e))?;
+    let proc = match processes.get_mut(instance_id) {
+        Some(proc) if proc.session_id == session_id => proc,
+        _ => {
+            drop(processes);
+            if let Ok(mut spawned) = spawned {
+                let _ = spawned.child.kill_tree();
+            }
+            return Err("启动期间会话已被替换".to_string());
+        }
+    };
+    match spawned {
+        Ok(mut spawned) if proc.stop_requested => {
+            let _ = spawned.child.kill_tree();
+            apply_transition(proc, SraStatus::Stopping, "用户停止", attempt);
+            apply_transition(proc, SraStatus::NotRunning, "启动期间已停止", attempt);
+            Err("启动期间已停止".to_string())
+        }
+        Ok(spawned) => {
+            proc.child = Some(spawned.child);
+            proc.transcript = spawned.output.transcript.clone();
             
             // 记录启动日志
-            let _ = log(LogSource::Backend, crate::logger::LogLevel::INFO, "SRA 进程已启动".to_string());
+            let _ = log_instance(instance_id, LogSource::Backend, crate::logger::LogLevel::INFO, "SRA 进程已启动".to_string());
             
             // 发送状态变更事件
-            let _ = app_handle.emit("sra-status-changed", "running");
+            apply_transition(proc, SraStatus::Idle, "进程已启动", attempt);
+            drop(processes);
+            
+            // 进程登记后再开始读取输出，退出处理总能找到本会话的进程
+            spawn_readers(instance_id, session_id, capture, parser, spawned.output);
             Ok(())
         }
         Err(e) => {
-            *process_guard = Some(SraProcess {
-                child: None,
-                status: SraStatus::Error,
-            });
-            
-            let _ = log(LogSource::Backend, crate::logger::LogLevel::ERR, format!("启动失败: {}", e));
-            let _ = app_handle.emit("sra-status-changed", "error");
+            let _ = log_instance(instance_id, LogSource::Backend, crate::logger::LogLevel::ERR, format!("启动失败: {}", e));
+            apply_transition(proc, SraStatus::Error, "启动失败", attempt);
             Err(e)
         }
     }
 }
 
+// 发送停止进度事件
+fn emit_stop_progress(instance_id: &str, step: &str, message: &str, timeout_ms: Option<u64>) {
+    events::emit("sra-stop-progress", StopProgress {
+        instance_id: instance_id.to_string(),
+        step: step.to_string(),
+        message: message.to_string(),
+        timeout_ms,
+    });
+}
+
+// 检查本次会话的进程是否已退出（读取线程可能已先行处理退出，会话被取代也视为已退出）
+fn session_exited(instance_id: &str, session_id: u64) -> bool {
+    let mut processes = match SRA_PROCESSES.lock() {
+        Ok(processes) => processes,
+        Err(_) => return true,
+    };
+    match processes.get_mut(instance_id) {
+        Some(proc) if proc.session_id == session_id => match proc.child {
+            Some(ref mut child) => !matches!(child.try_wait(), Ok(None)),
+            None => true,
+        },
+        _ => true,
+    }
+}
+
+// 轮询等待条件成立（每次检查只短暂持有锁），超时返回 false
+pub fn wait_until<F: Fn() -> bool>(timeout: Duration, condition: F) -> bool {
+    let started = Instant::now();
+    loop {
+        if condition() {
+            return true;
+        }
+        if started.elapsed() >= timeout {
+            return false;
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+}
+
+// 向本次会话的进程写入 stdin（短暂持有锁）
+fn write_session_stdin(instance_id: &str, session_id: u64, data: &[u8]) -> Result<(), String> {
+    let mut processes = SRA_PROCESSES.lock().map_err(|e| format!("Lock error: {}", e))?;
+    match processes.get_mut(instance_id) {
+        Some(proc) if proc.session_id == session_id => match proc.child {
+            Some(ref mut child) => {
+                run_history::note_command(instance_id, &String::from_utf8_lossy(data));
+                child.write_stdin(data).map_err(|e| format!("写入失败: {}", e))?;
+                if let Some(ref recorder) = proc.transcript {
+                    recorder.record_stdin(&String::from_utf8_lossy(data));
+                }
+                Ok(())
+            }
+            None => Err("进程未运行".to_string()),
+        },
+        _ => Err("进程未运行".to_string()),
+    }
+}
+
 // 停止SRA进程
-pub fn stop_sra_process_command(app_handle: tauri::AppHandle) -> Result<(), String> {
-    let mut process_guard = SRA_PROCESS.lock().map_err(|e| format!("Lock error: {}", e))?;
-    
-    if let Some(ref mut proc) = *process_guard {
-        if let Some(ref mut child) = proc.child {
-            if child.try_wait().map_or(false, |status| status.is_some()) {
-                // 进程已经退出
-                proc.child = None;
-                proc.status = SraStatus::NotRunning;
-                return Ok(());
+// 按 task stop -> exit -> 结束进程树 的顺序逐步升级，每一步的等待期间都不持有进程锁
+pub fn stop_sra_process_command(instance_id: &str) -> Result<(), String> {
+    // 用户主动停止，取消挂起的自动重启
+    watchdog::reset(instance_id);
+
+    // 标记为主动停止并登记停止流程，记录当前会话
+    let (session_id, task_running) = {
+        let mut processes = SRA_PROCESSES.lock().map_err(|e| format!("Lock error: {}", e))?;
+        let proc = match processes.get_mut(instance_id) {
+            Some(proc) => proc,
+            None => return Ok(()),
+        };
+        if proc.stopping {
+            // 已有停止流程在进行，等待其结束，不重复发送 task stop / exit
+            let session_id = proc.session_id;
+            drop(processes);
+            return wait_for_stop(instance_id, session_id);
+        }
+        proc.stop_requested = true;
+        let alive = proc.child.as_mut().is_some_and(|child| matches!(child.try_wait(), Ok(None)));
+        if !alive {
+            // 进程已经退出
+            if proc.child.take().is_some() {
+                apply_transition(proc, SraStatus::Stopping, "用户停止", None);
+                apply_transition(proc, SraStatus::NotRunning, "进程已退出", None);
             }
-            
-            // 优先发送停止命令，优雅退出
-            if let Some(ref mut stdin) = child.stdin {
-                let _ = stdin.write_all(b"exit\n");
-                let _ = stdin.flush();
+            return Ok(());
+        }
+        proc.stopping = true;
+        (proc.session_id, proc.state.current() == SraStatus::TaskRunning)
+    };
+
+    let result = stop_session(instance_id, session_id, task_running);
+
+    if let Ok(mut processes) = SRA_PROCESSES.lock() {
+        if let Some(proc) = processes.get_mut(instance_id) {
+            if proc.session_id == session_id {
+                proc.stopping = false;
             }
-            
-            // 等待1秒，若未退出则强制终止
-            thread::sleep(Duration::from_secs(1));
-            if child.try_wait().map_or(false, |status| status.is_some()) {
-                // 进程已正常退出
-                proc.child = None;
-                proc.status = SraStatus::NotRunning;
-                let _ = app_handle.emit("sra-status-changed", "not-running");
-                return Ok(());
+        }
+    }
+    result
+}
+
+// 停止流程是否仍在进行
+fn stop_in_progress(instance_id: &str, session_id: u64) -> bool {
+    SRA_PROCESSES.lock().is_ok_and(|processes| {
+        processes
+            .get(instance_id)
+            .is_some_and(|proc| proc.session_id == session_id && proc.stopping)
+    })
+}
+
+// 等待其他调用方发起的停止流程结束
+fn wait_for_stop(instance_id: &str, session_id: u64) -> Result<(), String> {
+    let policy = settings::load_ce_settings_or_default().stop_policy;
+    let total = policy.task_stop_timeout_ms + policy.exit_timeout_ms + policy.kill_timeout_ms;
+    wait_until(Duration::from_millis(total), || !stop_in_progress(instance_id, session_id));
+    if session_exited(instance_id, session_id) {
+        Ok(())
+    } else {
+        Err("无法结束 SRA 进程".to_string())
+    }
+}
+
+// 停止流程（调用方已登记停止流程）
+fn stop_session(instance_id: &str, session_id: u64, task_running: bool) -> Result<(), String> {
+    let policy = settings::load_ce_settings_or_default().stop_policy;
+
+    // 1. 任务执行中，先停止任务并等待 [Done]
+    if task_running && policy.task_stop_timeout_ms > 0 {
+        emit_stop_progress(instance_id, "task-stop", "正在停止当前任务", Some(policy.task_stop_timeout_ms));
+        if write_session_stdin(instance_id, session_id, b"task stop\n").is_ok() {
+            let _ = log_instance(instance_id, LogSource::Process, crate::logger::LogLevel::MSG, ">>> task stop".to_string());
+            let task_stopped = wait_until(Duration::from_millis(policy.task_stop_timeout_ms), || {
+                session_exited(instance_id, session_id)
+                    || get_instance_status(instance_id).map_or(true, |status| status != SraStatus::TaskRunning.as_str())
+            });
+            if !task_stopped {
+                let _ = log_instance(instance_id, LogSource::Backend, crate::logger::LogLevel::WARN, "等待任务停止超时，继续退出进程".to_string());
             }
-            
-            // 强制终止
-            let _ = child.kill();
-            let _ = child.wait();
-            
+        }
+    }
+
+    if let Ok(mut processes) = SRA_PROCESSES.lock() {
+        if let Some(proc) = processes.get_mut(instance_id) {
+            if proc.session_id == session_id && proc.child.is_some() {
+                apply_transition(proc, SraStatus::Stopping, "用户停止", None);
+            }
+        }
+    }
+
+    // 2. 发送 exit，等待进程自行退出
+    let mut reason = "进程已退出";
+    if !session_exited(instance_id, session_id) {
+        emit_stop_progress(instance_id, "exit", "正在退出 SRA 进程", Some(policy.exit_timeout_ms));
+        let _ = write_session_stdin(instance_id, session_id, b"exit\n");
+        let exited = wait_until(Duration::from_millis(policy.exit_timeout_ms), || session_exited(instance_id, session_id));
+
+        // 3. 未能按时退出，强制结束进程树
+        if !exited {
+            emit_stop_progress(instance_id, "kill", "进程未响应，正在强制结束", Some(policy.kill_timeout_ms));
+            let _ = log_instance(instance_id, LogSource::Backend, crate::logger::LogLevel::WARN, "SRA 进程未响应 exit，强制结束进程树".to_string());
+            if let Ok(mut processes) = SRA_PROCESSES.lock() {
+                if let Some(proc) = processes.get_mut(instance_id) {
+                    if proc.session_id == session_id {
+                        if let Some(ref mut child) = proc.child {
+                            let _ = child.kill_tree();
+                        }
+                    }
+                }
+            }
+            if !wait_until(Duration::from_millis(policy.kill_timeout_ms), || session_exited(instance_id, session_id)) {
+                let error_msg = "无法结束 SRA 进程".to_string();
+                emit_stop_progress(instance_id, "failed", &error_msg, None);
+                let _ = transition_state(instance_id, SraStatus::Error, &error_msg, None);
+                return Err(error_msg);
+            }
+            reason = "进程已强制终止";
+        }
+    }
+
+    // 收尾（读取线程可能已经处理过退出）
+    let mut processes = SRA_PROCESSES.lock().map_err(|e| format!("Lock error: {}", e))?;
+    if let Some(proc) = processes.get_mut(instance_id) {
+        if proc.session_id == session_id {
             proc.child = None;
-            proc.status = SraStatus::NotRunning;
-            let _ = app_handle.emit("sra-status-changed", "not-running");
-            return Ok(());
+            apply_transition(proc, SraStatus::NotRunning, reason, None);
         }
     }
-    
+    drop(processes);
+    emit_stop_progress(instance_id, "done", reason, None);
+
     Ok(())
 }
 
-// 重启SRA进程
-pub fn restart_sra_process_command(app_handle: tauri::AppHandle, arguments: Option<String>) -> Result<(), String> {
+// 停止所有实例（更新后端、退出程序时使用），各实例并行停止
+pub fn stop_all_sra_processes() -> Result<(), String> {
+    let instance_ids: Vec<String> = SRA_PROCESSES
+        .lock()
+        .map_err(|e| format!("Lock error: {}", e))?
+        .keys()
+        .cloned()
+        .collect();
+
+    let errors: Vec<String> = thread::scope(|scope| {
+        let handles: Vec<_> = instance_ids
+            .iter()
+            .map(|instance_id| scope.spawn(move || {
+                stop_sra_process_command(instance_id).map_err(|e| format!("{}: {}", instance_id, e))
+            }))
+            .collect();
+        handles
+            .into_iter()
+            .filter_map(|handle| handle.join().unwrap_or_else(|_| Err("停止线程异常退出".to_string())).err())
+            .collect()
+    });
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("部分实例停止失败: {}", errors.join("; ")))
+    }
+}
+
+// 重启SRA进程（停止流程返回时进程已完全退出）
+pub fn restart_sra_process_command(instance_id: &str, request: LaunchRequest) -> Result<(), String> {
     // 先停止进程
-    stop_sra_process_command(app_handle.clone())?;
-    
-    // 等待进程完全退出
-    thread::sleep(Duration::from_millis(500));
-    
+    stop_sra_process_command(instance_id)?;
+
     // 启动新进程，传播错误
-    start_sra_process_command(app_handle, arguments)
+    start_sra_process_command(instance_id, request)
 }
 
-// 获取SRA状态
-pub fn get_sra_status() -> Result<String, String> {
-    let process_guard = SRA_PROCESS.lock().map_err(|e| format!("Lock error: {}", e))?;
+// 获取所有实例的状态
+pub fn get_sra_status() -> Result<Vec<InstanceStatus>, String> {
+    let processes = SRA_PROCESSES.lock().map_err(|e| format!("Lock error: {}", e))?;
     
-    if let Some(ref proc) = *process_guard {
-        let status = match proc.status {
-            SraStatus::NotRunning => "not-running",
-            SraStatus::Running => "running",
-            SraStatus::TaskRunning => "task-running",
-            SraStatus::Error => "error",
-        };
-        Ok(status.to_string())
-    } else {
-        Ok("not-running".to_string())
-    }
+    Ok(processes.values()
+        .map(|proc| InstanceStatus {
+            instance_id: proc.instance_id.clone(),
+            status: proc.state.current().as_str().to_string(),
+            pid: proc.child.as_ref().map(|child| child.id()),
+            transcript: proc.transcript.as_ref().map(|t| t.path().display().to_string()),
+            profile: proc.launch.profile.clone(),
+            arguments: proc.launch.arguments.clone(),
+        })
+        .collect())
+}
+
+// 实例最近一次的启动参数（重启时沿用）
+pub fn current_launch(instance_id: &str) -> LaunchRequest {
+    SRA_PROCESSES
+        .lock()
+        .ok()
+        .and_then(|processes| processes.get(instance_id).map(|proc| proc.launch.clone()))
+        .unwrap_or_default()
+}
+
+// 获取指定实例的状态（未启动过的实例视为未运行）
+pub fn get_instance_status(instance_id: &str) -> Result<String, String> {
+    let processes = SRA_PROCESSES.lock().map_err(|e| format!("Lock error: {}", e))?;
+    Ok(processes.get(instance_id)
+        .map(|proc| proc.state.current().as_str().to_string())
+        .unwrap_or_else(|| SraStatus::NotRunning.as_str().to_string()))
+}
+
+// 获取状态转换历史
+pub fn get_sra_state_history(instance_id: &str) -> Result<Vec<StateTransition>, String> {
+    let processes = SRA_PROCESSES.lock().map_err(|e| format!("Lock error: {}", e))?;
+    Ok(processes.get(instance_id)
+        .map(|proc| proc.state.history().to_vec())
+        .unwrap_or_default())
 }
 
 // 发送输入到进程
-pub fn send_input_to_sra(_app_handle: tauri::AppHandle, input: String) -> Result<(), String> {
+pub fn send_input_to_sra(instance_id: &str, input: String) -> Result<(), String> {
     // 检查输入是否为空
     if input.trim().is_empty() {
-        let _ = log(LogSource::Backend, crate::logger::LogLevel::WARN, "尝试发送空输入到 SRA 进程".to_string());
+        let _ = log_instance(instance_id, LogSource::Backend, crate::logger::LogLevel::WARN, "尝试发送空输入到 SRA 进程".to_string());
         return Err("输入不能为空".to_string());
     }
     
-    let mut process_guard = SRA_PROCESS.lock().map_err(|e| format!("Lock error: {}", e))?;
+    let mut processes = SRA_PROCESSES.lock().map_err(|e| format!("Lock error: {}", e))?;
     
     // 检查进程是否存在
-    if let Some(ref mut proc) = *process_guard {
+    if let Some(proc) = processes.get_mut(instance_id) {
+        // 用户手动输入 exit 也视为主动停止，不触发自动重启
+        let alive = proc.child.as_mut().is_some_and(|child| matches!(child.try_wait(), Ok(None)));
+        if alive && input.trim() == "exit" {
+            proc.stop_requested = true;
+            apply_transition(proc, SraStatus::Stopping, "用户输入 exit", None);
+        }
+        
         if let Some(ref mut child) = proc.child {
             // 检查进程是否还在运行
             if child.try_wait().map_or(false, |status| status.is_some()) {
                 let error_msg = format!("发送失败: 进程未运行（输入: {}）", input);
-                let _ = log(LogSource::Backend, crate::logger::LogLevel::WARN, error_msg.clone());
+                let _ = log_instance(instance_id, LogSource::Backend, crate::logger::LogLevel::WARN, error_msg.clone());
                 return Err("进程未运行".to_string());
             }
             
-            // 发送输入到 stdin
-            if let Some(ref mut stdin) = child.stdin {
-                // 记录发送日志
-                let _ = log(
-                    LogSource::Backend, 
-                    crate::logger::LogLevel::INFO, 
-                    format!("发送输入到 SRA 进程: {}", input)
-                );
-                
-                // 写入命令
-                let command = format!("{}\n", input);
-                match stdin.write_all(command.as_bytes()) {
-                    Ok(_) => {
-                        match stdin.flush() {
-                            Ok(_) => {
-                                // 发送成功，记录用户输入为 MSG 日志
-                                let _ = log(
-                                    LogSource::Process,
-                                    crate::logger::LogLevel::MSG,
-                                    format!(">>> {}", input)
-                                );
-                                return Ok(());
-                            }
-                            Err(e) => {
-                                let error_msg = format!("发送失败: {}（输入: {}）", e, input);
-                                let _ = log(LogSource::Backend, crate::logger::LogLevel::ERR, error_msg.clone());
-                                return Err(format!("刷新失败: {}", e));
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        let error_msg = format!("发送失败: {}（输入: {}）", e, input);
-                        let _ = log(LogSource::Backend, crate::logger::LogLevel::ERR, error_msg.clone());
-                        return Err(format!("写入失败: {}", e));
+            // 记录发送日志
+            let _ = log_instance(
+                instance_id,
+                LogSource::Backend,
+                crate::logger::LogLevel::INFO,
+                format!("发送输入到 SRA 进程: {}", input)
+            );
+            
+            // 写入命令（先登记到运行历史，避免 [Start] 先于登记到达）
+            run_history::note_command(instance_id, &input);
+            let command = format!("{}\n", input);
+            match child.write_stdin(command.as_bytes()) {
+                Ok(_) => {
+                    if let Some(ref recorder) = proc.transcript {
+                        recorder.record_stdin(&input);
                     }
+                    
+                    // 发送成功，记录用户输入为 MSG 日志
+                    let _ = log_instance(
+                        instance_id,
+                        LogSource::Process,
+                        crate::logger::LogLevel::MSG,
+                        format!(">>> {}", input)
+                    );
+                    return Ok(());
+                }
+                Err(e) => {
+                    let error_msg = format!("发送失败: {}（输入: {}）", e, input);
+                    let _ = log_instance(instance_id, LogSource::Backend, crate::logger::LogLevel::ERR, error_msg.clone());
+                    return Err(format!("写入失败: {}", e));
                 }
             }
         }
@@ -445,12 +882,71 @@ pub fn send_input_to_sra(_app_handle: tauri::AppHandle, input: String) -> Result
     
     // 进程不存在或未运行
     let error_msg = format!("发送失败: 进程未运行（输入: {}）", input);
-    let _ = log(LogSource::Backend, crate::logger::LogLevel::WARN, error_msg);
+    let _ = log_instance(instance_id, LogSource::Backend, crate::logger::LogLevel::WARN, error_msg);
     Err("进程未运行".to_string())
 }
 
+// 发送命令并等待下一个 sra> 提示符，返回期间解析到的输出
+pub fn send_command_and_wait(instance_id: &str, input: String, timeout_ms: Option<u64>) -> Result<CommandResponse, String> {
+    let timeout = Duration::from_millis(timeout_ms.unwrap_or(DEFAULT_COMMAND_TIMEOUT_MS));
+    
+    // 注册输出捕获（同一实例同一时间只允许一个命令等待响应）
+    let capture = {
+        let processes = SRA_PROCESSES.lock().map_err(|e| format!("Lock error: {}", e))?;
+        match processes.get(instance_id) {
+            Some(proc) if proc.child.is_some() => proc.capture.clone(),
+            _ => return Err("进程未运行".to_string()),
+        }
+    };
+    let (tx, rx) = mpsc::channel();
+    {
+        let mut slot = capture.lock().map_err(|e| format!("Lock error: {}", e))?;
+        if slot.is_some() {
+            return Err("已有命令正在等待响应".to_string());
+        }
+        *slot = Some(tx);
+    }
+    
+    let started = Instant::now();
+    let result = send_input_to_sra(instance_id, input.clone()).and_then(|_| {
+        let mut lines = Vec::new();
+        loop {
+            let remaining = timeout.saturating_sub(started.elapsed());
+            match rx.recv_timeout(remaining) {
+                Ok(CaptureEvent::Line(parsed)) => lines.push(parsed),
+                Ok(CaptureEvent::Prompt) => break,
+                Ok(CaptureEvent::Exited) => {
+                    return Err(format!("等待响应时进程已退出（已收到 {} 行输出）", lines.len()));
+                }
+                Err(_) => {
+                    return Err(format!(
+                        "等待响应超时（{} ms，已收到 {} 行输出）",
+                        timeout.as_millis(),
+                        lines.len()
+                    ));
+                }
+            }
+        }
+        Ok(CommandResponse {
+            command: redact::redact(&input),
+            lines,
+            elapsed_ms: started.elapsed().as_millis() as u64,
+        })
+    });
+    
+    // 注销输出捕获
+    if let Ok(mut slot) = capture.lock() {
+        *slot = None;
+    }
+    
+    if let Err(ref e) = result {
+        let _ = log_instance(instance_id, LogSource::Backend, crate::logger::LogLevel::WARN, format!("命令 `{}` 未完成: {}", input, e));
+    }
+    result
+}
+
 // 执行任务
-pub fn task_run(app_handle: tauri::AppHandle, config_name: Option<String>) -> Result<(), String> {
+pub fn task_run(instance_id: &str, config_name: Option<String>) -> Result<(), String> {
     let command = if let Some(config) = config_name {
         if config.is_empty() {
             "task run".to_string()
@@ -461,10 +957,126 @@ pub fn task_run(app_handle: tauri::AppHandle, config_name: Option<String>) -> Re
         "task run".to_string()
     };
     
-    send_input_to_sra(app_handle, command)
+    send_input_to_sra(instance_id, command)
 }
 
 // 停止任务
-pub fn task_stop(app_handle: tauri::AppHandle) -> Result<(), String> {
-    send_input_to_sra(app_handle, "task stop".to_string())
+pub fn task_stop(instance_id: &str) -> Result<(), String> {
+    send_input_to_sra(instance_id, "task stop".to_string())
+}
+
+// 任务开始（进入 task-running）的最长等待时间
+const TASK_START_TIMEOUT: Duration = Duration::from_secs(60);
+
+// 执行任务并等待运行结束，返回运行记录（供计划任务等无人值守场景使用）
+pub fn run_task_and_wait(instance_id: &str, config_name: Option<String>) -> Result<run_history::TaskRun, String> {
+    let (tx, rx) = mpsc::channel();
+    let target = instance_id.to_string();
+    let sub = events::subscribe(move |event, payload| {
+        if event == "sra-task-run-finished" && payload["instance_id"] == target.as_str() {
+            let _ = tx.send(payload.clone());
+        }
+    });
+
+    let result = (|| {
+        task_run(instance_id, config_name)?;
+        let sent = Instant::now();
+        let mut started = false;
+        loop {
+            match rx.recv_timeout(Duration::from_millis(500)) {
+                Ok(payload) => {
+                    return serde_json::from_value(payload).map_err(|e| format!("Failed to parse task run: {}", e));
+                }
+                Err(mpsc::RecvTimeoutError::Timeout) => {}
+                Err(mpsc::RecvTimeoutError::Disconnected) => return Err("事件订阅已断开".to_string()),
+            }
+            let status = get_instance_status(instance_id)?;
+            if status == SraStatus::TaskRunning.as_str() {
+                started = true;
+            } else if !started && sent.elapsed() > TASK_START_TIMEOUT {
+                return Err(format!("任务未在 {} 秒内开始", TASK_START_TIMEOUT.as_secs()));
+            } else if !started && matches!(status.as_str(), "not-running" | "crashed" | "error") {
+                return Err(format!("任务开始前进程已退出（{}）", status));
+            }
+        }
+    })();
+
+    events::unsubscribe(sub);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_backend::tests::{collect_statuses, setup, BASIC_SESSION};
+
+    #[test]
+    fn test_launch_profile_arguments_and_env() {
+        let (_guard, mock) = setup("prompt\n");
+        let mut env = BTreeMap::new();
+        env.insert("SRA_DEBUG".to_string(), "1".to_string());
+        settings::save_launch_profile(settings::LaunchProfile {
+            name: "调试".to_string(),
+            arguments: r#"--log-level DEBUG --config "My Config""#.to_string(),
+            env,
+            python_io_encoding: None,
+            ..Default::default()
+        })
+        .unwrap();
+
+        let request = LaunchRequest {
+            profile: Some("调试".to_string()),
+            arguments: Some(r#""C:\Program Files\extra.txt""#.to_string()),
+        };
+        start_sra_process_command(DEFAULT_INSTANCE, request).unwrap();
+        stop_sra_process_command(DEFAULT_INSTANCE).unwrap();
+
+        let spec = mock.launch_specs().pop().unwrap();
+        assert_eq!(
+            spec.args,
+            vec!["--inline", "--log-level", "DEBUG", "--config", "My Config", r"C:\Program Files\extra.txt"]
+        );
+        assert!(spec.env.contains(&("SRA_DEBUG".to_string(), "1".to_string())));
+        assert!(spec.env.iter().any(|(k, _)| k == "PYTHONUNBUFFERED"));
+        assert!(!spec.env.iter().any(|(k, _)| k == "PYTHONIOENCODING"));
+
+        // 不存在的启动配置导致启动失败
+        let missing = LaunchRequest { profile: Some("不存在".to_string()), arguments: None };
+        assert!(start_sra_process_command(DEFAULT_INSTANCE, missing).is_err());
+        assert_eq!(get_instance_status(DEFAULT_INSTANCE).unwrap(), "error");
+        settings::delete_launch_profile("调试").unwrap();
+    }
+
+    #[test]
+    fn test_instances_run_independently() {
+        let (_guard, mock) = setup(BASIC_SESSION);
+        let (sub, statuses) = collect_statuses("account-b");
+
+        start_sra_process_command(DEFAULT_INSTANCE, LaunchRequest::default()).unwrap();
+        start_sra_process_command("account-b", LaunchRequest::default()).unwrap();
+        assert_eq!(mock.spawn_count(), 2);
+        let default_before = get_sra_state_history(DEFAULT_INSTANCE).unwrap().len();
+
+        // 只有 account-b 执行任务，默认实例保持空闲
+        let response = send_command_and_wait("account-b", "task run Default".to_string(), Some(3000)).unwrap();
+        assert!(response.lines.iter().any(|l| l.message == "任务完成"));
+        assert_eq!(get_instance_status(DEFAULT_INSTANCE).unwrap(), "running");
+        assert!(get_sra_state_history(DEFAULT_INSTANCE)
+            .unwrap()
+            .iter()
+            .skip(default_before)
+            .all(|t| t.to != SraStatus::TaskRunning));
+
+        // 停止 account-b 不影响默认实例
+        stop_sra_process_command("account-b").unwrap();
+        events::unsubscribe(sub);
+        let all = get_sra_status().unwrap();
+        let status_of = |id: &str| all.iter().find(|s| s.instance_id == id).map(|s| s.status.clone());
+        assert_eq!(status_of(DEFAULT_INSTANCE).as_deref(), Some("running"));
+        assert_eq!(status_of("account-b").as_deref(), Some("not-running"));
+        assert!(statuses.lock().unwrap().contains(&"task-running".to_string()));
+
+        stop_all_sra_processes().unwrap();
+        assert_eq!(get_instance_status(DEFAULT_INSTANCE).unwrap(), "not-running");
+    }
 }
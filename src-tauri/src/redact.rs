@@ -0,0 +1,236 @@
+// 日志脱敏
+// 日志写入文件、缓冲区和发送到前端之前，替换已知的敏感值（配置中的账号密码、邮件授权码、Mirror酱 CDK、远程控制令牌）
+// 和匹配脱敏规则的内容；会话记录、运行记录中的错误和命令响应同样经过脱敏。已知的敏感值在加载或保存配置和设置时登记
+
+use crate::config;
+use crate::settings;
+use regex::{Captures, Regex};
+use serde_json::Value;
+use std::sync::Mutex;
+
+// 替换后的内容
+pub const MASK: &str = "******";
+
+// 过短的值（如 1 位数字）替换后会误伤大量正常内容，不登记
+const MIN_SECRET_LEN: usize = 4;
+
+// 配置中的敏感字段
+pub const CONFIG_SECRET_KEYS: [&str; 3] = ["StartGamePassword", "StartGameUsername", "CurrencyWarsUsername"];
+
+struct Redactor {
+    secrets: Vec<String>, // 按长度从长到短排列，避免较短的值先替换掉较长值的一部分
+    patterns: Vec<Regex>,
+}
+
+impl Redactor {
+    const fn new() -> Self {
+        Self { secrets: Vec::new(), patterns: Vec::new() }
+    }
+
+    fn add_secret(&mut self, value: &str) {
+        let value = value.trim();
+        if value.chars().count() < MIN_SECRET_LEN || self.secrets.iter().any(|s| s == value) {
+            return;
+        }
+        let index = self.secrets.partition_point(|s| s.len() >= value.len());
+        self.secrets.insert(index, value.to_string());
+    }
+
+    fn set_patterns(&mut self, patterns: Vec<Regex>) {
+        self.patterns = patterns;
+    }
+
+    // 脱敏（规则含捕获组时只替换第一个捕获组，保留如 password= 之类的前缀）
+    fn redact(&self, message: &str) -> String {
+        let mut text = message.to_string();
+        for secret in &self.secrets {
+            if text.contains(secret.as_str()) {
+                text = text.replace(secret.as_str(), MASK);
+            }
+        }
+        for pattern in &self.patterns {
+            text = pattern
+                .replace_all(&text, |caps: &Captures| {
+                    let whole = caps.get(0).unwrap();
+                    match caps.get(1) {
+                        Some(group) => {
+                            let (start, end) = (group.start() - whole.start(), group.end() - whole.start());
+                            format!("{}{}{}", &whole.as_str()[..start], MASK, &whole.as_str()[end..])
+                        }
+                        None => MASK.to_string(),
+                    }
+                })
+                .into_owned();
+        }
+        text
+    }
+
+    // 原始输出脱敏：UTF-8 内容按文本处理，其它编码（如 GBK）只替换已知敏感值的字节序列
+    fn redact_bytes(&self, data: &[u8]) -> Vec<u8> {
+        if let Ok(text) = std::str::from_utf8(data) {
+            return self.redact(text).into_bytes();
+        }
+        let mut bytes = data.to_vec();
+        for secret in &self.secrets {
+            bytes = replace_bytes(&bytes, secret.as_bytes(), MASK.as_bytes());
+        }
+        bytes
+    }
+}
+
+fn replace_bytes(data: &[u8], from: &[u8], to: &[u8]) -> Vec<u8> {
+    let mut result = Vec::with_capacity(data.len());
+    let mut rest = data;
+    while let Some(index) = rest.windows(from.len()).position(|w| w == from) {
+        result.extend_from_slice(&rest[..index]);
+        result.extend_from_slice(to);
+        rest = &rest[index + from.len()..];
+    }
+    result.extend_from_slice(rest);
+    result
+}
+
+// 编译脱敏规则
+pub fn compile_patterns(patterns: &[String]) -> Result<Vec<Regex>, String> {
+    patterns
+        .iter()
+        .map(|p| Regex::new(p).map_err(|e| format!("无效的脱敏规则 {}: {}", p, e)))
+        .collect()
+}
+
+// 全局脱敏器（不写日志，可在日志锁内使用）
+static REDACTOR: Mutex<Redactor> = Mutex::new(Redactor::new());
+
+// 对日志内容脱敏
+pub fn redact(message: &str) -> String {
+    match REDACTOR.lock() {
+        Ok(redactor) => redactor.redact(message),
+        Err(_) => message.to_string(),
+    }
+}
+
+// 对原始输出脱敏（会话记录按读取块记录，跨越两次读取的敏感值无法识别）
+pub fn redact_bytes(data: &[u8]) -> Vec<u8> {
+    match REDACTOR.lock() {
+        Ok(redactor) => redactor.redact_bytes(data),
+        Err(_) => data.to_vec(),
+    }
+}
+
+// 登记敏感值
+pub fn add_secret(value: &str) {
+    if let Ok(mut redactor) = REDACTOR.lock() {
+        redactor.add_secret(value);
+    }
+}
+
+// 登记配置中的敏感值（需为解密后的配置）
+pub fn add_config_secrets(config: &Value) {
+    for key in CONFIG_SECRET_KEYS {
+        if let Some(value) = config.get(key).and_then(|v| v.as_str()) {
+            add_secret(value);
+        }
+    }
+}
+
+// 更新脱敏规则（立即生效）
+pub fn set_patterns(patterns: &[String]) -> Result<(), String> {
+    let compiled = compile_patterns(patterns)?;
+    let mut redactor = REDACTOR.lock().map_err(|e| format!("Lock error: {}", e))?;
+    redactor.set_patterns(compiled);
+    Ok(())
+}
+
+// 启动时读取脱敏规则，并从所有配置和设置中收集敏感值
+pub fn load_known_secrets() {
+    let patterns = settings::load_ce_settings_or_default().redaction.patterns;
+    if set_patterns(&patterns).is_err() {
+        // 规则无效时使用默认规则
+        let _ = set_patterns(&settings::RedactionSettings::default().patterns);
+    }
+    for name in config::get_config_list().unwrap_or_default() {
+        // load_config 会登记配置中的敏感值
+        let _ = config::load_config(name);
+    }
+    // 以下读取会登记邮件授权码、CDK 和远程控制令牌
+    let _ = settings::load_settings();
+    let _ = settings::load_remote_api_token();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_backend::tests::setup;
+    use crate::types::{LaunchRequest, DEFAULT_INSTANCE};
+    use crate::transcript::{self, TranscriptEntry};
+    use crate::{process, run_history};
+    use base64::{engine::general_purpose, Engine as _};
+
+    #[test]
+    fn test_redact_secrets_and_patterns() {
+        let mut redactor = Redactor::new();
+        redactor.add_secret("hunter2-pass");
+        redactor.add_secret("hunter2");
+        redactor.add_secret("abc");
+        redactor.set_patterns(compile_patterns(&settings::RedactionSettings::default().patterns).unwrap());
+
+        assert_eq!(redactor.redact("login hunter2-pass / hunter2"), "login ****** / ******");
+        assert_eq!(redactor.redact("abc 不会被替换"), "abc 不会被替换");
+        assert_eq!(redactor.redact("set Password=\"s3cret\" ok"), "set Password=\"******\" ok");
+        assert_eq!(redactor.redact("token: 0123abcd"), "token: ******");
+        assert_eq!(redactor.redact("task run Default"), "task run Default");
+        assert!(compile_patterns(&["(".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_redact_raw_bytes() {
+        let mut redactor = Redactor::new();
+        redactor.add_secret("hunter2-pass");
+        assert_eq!(redactor.redact_bytes(b"login hunter2-pass\n"), b"login ******\n");
+        // GBK 编码的输出只替换敏感值，其余字节保持不变
+        let gbk = [0xb5, 0xc7, 0xc2, 0xbc, b' ', b'h', b'u', b'n', b't', b'e', b'r', b'2', b'-', b'p', b'a', b's', b's'];
+        assert_eq!(redactor.redact_bytes(&gbk), [&gbk[..5], MASK.as_bytes()].concat());
+    }
+
+    #[test]
+    fn test_secret_never_reaches_transcript_or_run_history() {
+        let secret = "Pa55-never-logged";
+        let (_guard, _mock) = setup(&format!(
+            "expect: task run Default\nout: [Start] Default\nout: 14:20:08[40401] | ERROR | 登录失败: {}\nout: [Done] Default\nprompt\n",
+            secret
+        ));
+        add_secret(secret);
+        settings::save_transcript_settings(settings::TranscriptSettings { enabled: true, ..Default::default() }).unwrap();
+
+        process::start_sra_process_command(DEFAULT_INSTANCE, LaunchRequest::default()).unwrap();
+        process::send_input_to_sra(DEFAULT_INSTANCE, format!("login {}", secret)).unwrap();
+        let response = process::send_command_and_wait(DEFAULT_INSTANCE, "task run Default".to_string(), Some(3000)).unwrap();
+        let path = process::get_sra_status()
+            .unwrap()
+            .into_iter()
+            .find(|s| s.instance_id == DEFAULT_INSTANCE)
+            .and_then(|s| s.transcript)
+            .unwrap();
+        process::stop_sra_process_command(DEFAULT_INSTANCE).unwrap();
+
+        assert!(response.lines.iter().any(|l| l.message == "登录失败: ******"));
+        // 会话记录中的输出为 base64 编码，解码后检查
+        let recorded: Vec<String> = transcript::read_transcript(std::path::Path::new(&path))
+            .unwrap()
+            .into_iter()
+            .filter_map(|entry| match entry {
+                TranscriptEntry::Stdout { data, .. } | TranscriptEntry::Stderr { data, .. } => {
+                    Some(String::from_utf8_lossy(&general_purpose::STANDARD.decode(data).unwrap()).to_string())
+                }
+                TranscriptEntry::Stdin { text, .. } => Some(text),
+                _ => None,
+            })
+            .collect();
+        assert!(recorded.iter().all(|text| !text.contains(secret)));
+        assert!(recorded.iter().any(|text| text.contains("登录失败: ******")));
+        assert!(recorded.iter().any(|text| text == "login ******"));
+
+        let runs = run_history::query_runs(&run_history::RunQuery::default()).unwrap();
+        assert_eq!(runs[0].errors, vec!["登录失败: ******"]);
+    }
+}
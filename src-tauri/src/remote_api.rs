@@ -0,0 +1,520 @@
+// 局域网远程控制接口
+// 在设置的地址上提供 HTTP 接口（启动 / 停止 SRA-cli、执行配置或任务顺序、查询状态）和 WebSocket 事件推送，
+// 调用与 commands.rs 中命令相同的函数；请求需携带 Bearer 令牌，默认关闭，每次远程调用都会记录日志
+//
+// 接口（请求和响应均为 JSON，成功返回 {"ok":true,"data":...}，失败返回 {"ok":false,"error":"..."}）:
+//   GET  /api/status                              各实例状态
+//   GET  /api/plans                               正在执行的计划
+//   GET  /api/logs                                最近的日志（?since=序号&limit=条数 时只返回该序号之后的，见 get_logs_since）
+//   POST /api/start      {instance_id, profile, arguments}
+//   POST /api/stop       {instance_id}
+//   POST /api/run        {instance_id, config}    指定 config 时执行该配置，否则按任务顺序执行
+//   POST /api/task/stop  {instance_id}
+//   POST /api/cancel     {instance_id}            取消正在执行的计划
+//   GET  /api/ws                                  WebSocket，推送 log-message 等事件（浏览器无法设置请求头，可用 ?token= 传令牌）
+
+use crate::control::{blocking, to_value};
+use crate::events;
+use crate::logger::{self, log, LogLevel, LogSource};
+use crate::process;
+use crate::run_plan::{self, RunPlan};
+use crate::settings;
+use crate::types::LaunchRequest;
+use crate::websocket::{self, Frame, OP_CLOSE, OP_PING, OP_PONG, OP_TEXT};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::sync::{mpsc, watch};
+
+const MAX_HEADER_BYTES: usize = 16 * 1024;
+const MAX_BODY_BYTES: usize = 64 * 1024;
+const MAX_FRAME_BYTES: usize = 64 * 1024;
+
+// 读取请求的超时时间
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+// 通过 WebSocket 推送的事件
+const FORWARDED_EVENTS: [&str; 4] = ["log-message", "sra-status-changed", "plan-progress", "plan-finished"];
+
+const ROUTES: [&str; 8] = [
+    "/api/status",
+    "/api/plans",
+    "/api/logs",
+    "/api/start",
+    "/api/stop",
+    "/api/run",
+    "/api/task/stop",
+    "/api/cancel",
+];
+
+// WebSocket 连接待发送的事件数上限，客户端读取过慢时断开连接，避免事件在内存中堆积
+const EVENT_QUEUE_CAPACITY: usize = 256;
+
+// 已读取、待处理的 WebSocket 帧数上限（读取任务等待处理）
+const FRAME_QUEUE_CAPACITY: usize = 16;
+
+struct RunningServer {
+    address: SocketAddr,
+    shutdown: watch::Sender<bool>,
+    thread: JoinHandle<()>,
+}
+
+static SERVER: Mutex<Option<RunningServer>> = Mutex::new(None);
+
+// 按设置启动或停止服务（设置或令牌修改后调用，已在运行的服务会先停止）
+pub fn apply_settings() -> Result<(), String> {
+    let mut server = SERVER.lock().map_err(|e| format!("Lock error: {}", e))?;
+    if let Some(running) = server.take() {
+        let _ = running.shutdown.send(true);
+        let _ = running.thread.join();
+        let _ = log(LogSource::Backend, LogLevel::INFO, format!("远程控制接口已停止（{}）", running.address));
+    }
+
+    let config = settings::load_ce_settings()?.remote_api;
+    if !config.enabled {
+        return Ok(());
+    }
+    let token = settings::load_remote_api_token()?;
+    if token.is_empty() {
+        return Err("未设置远程控制接口的访问令牌".to_string());
+    }
+
+    let listener = std::net::TcpListener::bind(&config.bind_address)
+        .map_err(|e| format!("无法监听 {}: {}", config.bind_address, e))?;
+    listener
+        .set_nonblocking(true)
+        .map_err(|e| format!("Failed to configure listener: {}", e))?;
+    let address = listener
+        .local_addr()
+        .map_err(|e| format!("Failed to get listener address: {}", e))?;
+    let (shutdown, shutdown_rx) = watch::channel(false);
+    let thread = thread::spawn(move || serve(listener, Arc::new(token), shutdown_rx));
+    *server = Some(RunningServer { address, shutdown, thread });
+
+    let _ = log(LogSource::Backend, LogLevel::INFO, format!("远程控制接口已启动: http://{}", address));
+    Ok(())
+}
+
+// 程序启动时按设置启动服务
+pub fn start_server() {
+    if let Err(e) = apply_settings() {
+        let _ = log(LogSource::Backend, LogLevel::ERR, format!("启动远程控制接口失败: {}", e));
+    }
+}
+
+// 服务正在监听的地址（未启动时为空）
+pub fn local_address() -> Option<SocketAddr> {
+    SERVER.lock().ok()?.as_ref().map(|s| s.address)
+}
+
+// 生成并保存新的访问令牌，返回明文（只在生成时显示一次）
+pub fn generate_token() -> Result<String, String> {
+    use rand::{distributions::Alphanumeric, Rng};
+    let token: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(32)
+        .map(char::from)
+        .collect();
+    settings::save_remote_api_token(&token)?;
+    Ok(token)
+}
+
+fn serve(listener: std::net::TcpListener, token: Arc<String>, mut shutdown: watch::Receiver<bool>) {
+    let runtime = match tokio::runtime::Builder::new_multi_thread().worker_threads(2).enable_all().build() {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            let _ = log(LogSource::Backend, LogLevel::ERR, format!("远程控制接口无法创建运行时: {}", e));
+            return;
+        }
+    };
+    runtime.block_on(async move {
+        let listener = match tokio::net::TcpListener::from_std(listener) {
+            Ok(listener) => listener,
+            Err(e) => {
+                let _ = log(LogSource::Backend, LogLevel::ERR, format!("远程控制接口无法监听: {}", e));
+                return;
+            }
+        };
+        loop {
+            tokio::select! {
+                _ = shutdown.changed() => break,
+                accepted = listener.accept() => {
+                    if let Ok((stream, peer)) = accepted {
+                        tokio::spawn(handle_connection(stream, peer, token.clone()));
+                    }
+                }
+            }
+        }
+    });
+    // 停止时丢弃未完成的连接（WebSocket 订阅随之取消）
+    runtime.shutdown_timeout(Duration::from_secs(1));
+}
+
+struct Request {
+    method: String,
+    path: String,
+    query: HashMap<String, String>,
+    headers: HashMap<String, String>, // 名称为小写
+    body: Vec<u8>,
+}
+
+// 读取一个 HTTP 请求（每个连接只处理一个请求）
+async fn read_request<R: AsyncRead + Unpin>(stream: &mut R) -> Result<Request, String> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 4096];
+    loop {
+        let n = stream.read(&mut chunk).await.map_err(|e| format!("读取请求失败: {}", e))?;
+        if n == 0 {
+            return Err("连接已关闭".to_string());
+        }
+        buffer.extend_from_slice(&chunk[..n]);
+
+        let mut headers = [httparse::EMPTY_HEADER; 64];
+        let mut parsed = httparse::Request::new(&mut headers);
+        let header_len = match parsed.parse(&buffer).map_err(|e| format!("无效的请求: {}", e))? {
+            httparse::Status::Complete(len) => len,
+            httparse::Status::Partial if buffer.len() > MAX_HEADER_BYTES => return Err("请求头过大".to_string()),
+            httparse::Status::Partial => continue,
+        };
+
+        let headers: HashMap<String, String> = parsed
+            .headers
+            .iter()
+            .map(|h| (h.name.to_ascii_lowercase(), String::from_utf8_lossy(h.value).trim().to_string()))
+            .collect();
+        let content_length = match headers.get("content-length") {
+            Some(value) => value.parse::<usize>().map_err(|_| format!("无效的 Content-Length: {}", value))?,
+            None => 0,
+        };
+        if content_length > MAX_BODY_BYTES {
+            return Err("请求内容过大".to_string());
+        }
+        let mut body = buffer[header_len..].to_vec();
+        while body.len() < content_length {
+            let n = stream.read(&mut chunk).await.map_err(|e| format!("读取请求失败: {}", e))?;
+            if n == 0 {
+                return Err("请求内容不完整".to_string());
+            }
+            body.extend_from_slice(&chunk[..n]);
+        }
+        body.truncate(content_length);
+
+        let target = parsed.path.unwrap_or("/");
+        let (path, query) = target.split_once('?').unwrap_or((target, ""));
+        return Ok(Request {
+            method: parsed.method.unwrap_or_default().to_string(),
+            path: path.to_string(),
+            query: query
+                .split('&')
+                .filter_map(|pair| pair.split_once('='))
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            headers,
+            body,
+        });
+    }
+}
+
+// 校验令牌（逐字节比较全部内容，避免通过响应时间猜测令牌）
+fn authorized(request: &Request, token: &str) -> bool {
+    let provided = request
+        .headers
+        .get("authorization")
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(|value| value.trim())
+        .or_else(|| match request.path.as_str() {
+            "/api/ws" => request.query.get("token").map(|value| value.as_str()),
+            _ => None,
+        });
+    provided.is_some_and(|provided| {
+        provided.len() == token.len()
+            && provided.bytes().zip(token.bytes()).fold(0u8, |diff, (a, b)| diff | (a ^ b)) == 0
+    })
+}
+
+async fn handle_connection(mut stream: TcpStream, peer: SocketAddr, token: Arc<String>) {
+    let request = match tokio::time::timeout(REQUEST_TIMEOUT, read_request(&mut stream)).await {
+        Ok(Ok(request)) => request,
+        Ok(Err(e)) => {
+            let _ = write_response(&mut stream, 400, Some(&json!({ "ok": false, "error": e }))).await;
+            return;
+        }
+        Err(_) => return,
+    };
+
+    // 只允许非浏览器客户端调用：浏览器发起的请求（包括 WebSocket 握手）都带有 Origin，
+    // 拒绝后网页无法借助用户的浏览器尝试令牌
+    if let Some(origin) = request.headers.get("origin") {
+        let _ = log(
+            LogSource::Backend,
+            LogLevel::WARN,
+            format!("拒绝远程调用 {} {}（来自 {}）: 不接受网页请求（{}）", request.method, request.path, peer, origin),
+        );
+        let _ = write_response(&mut stream, 403, Some(&json!({ "ok": false, "error": "不接受网页发起的请求" }))).await;
+        return;
+    }
+    if !authorized(&request, &token) {
+        let _ = log(
+            LogSource::Backend,
+            LogLevel::WARN,
+            format!("拒绝远程调用 {} {}（来自 {}）: 令牌无效", request.method, request.path, peer),
+        );
+        let _ = write_response(&mut stream, 401, Some(&json!({ "ok": false, "error": "令牌无效" }))).await;
+        return;
+    }
+
+    if request.method == "GET" && request.path == "/api/ws" {
+        let _ = log(LogSource::Backend, LogLevel::INFO, format!("远程调用 GET /api/ws（来自 {}）: 开始推送事件", peer));
+        websocket_session(stream, &request).await;
+        let _ = log(LogSource::Backend, LogLevel::INFO, format!("远程事件推送已断开（{}）", peer));
+        return;
+    }
+
+    let (status, body) = match route(&request).await {
+        Ok(data) => (200, json!({ "ok": true, "data": data })),
+        Err((status, error)) => (status, json!({ "ok": false, "error": error })),
+    };
+    let level = if status == 200 { LogLevel::INFO } else { LogLevel::WARN };
+    let _ = log(
+        LogSource::Backend,
+        level,
+        format!("远程调用 {} {}（来自 {}）: {}", request.method, request.path, peer, status),
+    );
+    let _ = write_response(&mut stream, status, Some(&body)).await;
+}
+
+// 请求参数（均可省略，instance_id 为空时作用于默认实例）
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct ApiParams {
+    instance_id: Option<String>,
+    profile: Option<String>,
+    arguments: Option<String>,
+    config: Option<String>,
+}
+
+// 执行请求对应的命令，失败时返回状态码和错误信息
+async fn route(request: &Request) -> Result<Value, (u16, String)> {
+    let params: ApiParams = if request.body.is_empty() {
+        ApiParams::default()
+    } else {
+        serde_json::from_slice(&request.body).map_err(|e| (400, format!("无效的请求内容: {}", e)))?
+    };
+
+    let instance = process::instance_key(params.instance_id.clone());
+
+    let result = match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/api/status") => to_value(process::get_sra_status()),
+        ("GET", "/api/plans") => to_value(Ok(run_plan::get_active_plans())),
+        ("GET", "/api/logs") => match request.query.get("since") {
+            Some(since) => {
+                let since = since.parse().map_err(|_| (400, format!("无效的序号: {}", since)))?;
+                let limit = match request.query.get("limit") {
+                    Some(limit) => Some(limit.parse().map_err(|_| (400, format!("无效的条数: {}", limit)))?),
+                    None => None,
+                };
+                to_value(logger::get_logs_since(since, limit))
+            }
+            None => to_value(logger::get_all_logs()),
+        },
+        ("POST", "/api/start") => {
+            let request = LaunchRequest { profile: params.profile, arguments: params.arguments };
+            to_value(blocking(move || process::start_sra_process_command(&instance, request)).await)
+        }
+        ("POST", "/api/stop") => to_value(blocking(move || process::stop_sra_process_command(&instance)).await),
+        ("POST", "/api/run") => match params.config {
+            Some(config) => to_value(blocking(move || process::task_run(&instance, Some(config))).await),
+            None => blocking(move || run_plan::start_plan(RunPlan::from_task_order(params.instance_id)?))
+                .await
+                .map(|plan_id| json!({ "plan_id": plan_id })),
+        },
+        ("POST", "/api/task/stop") => to_value(blocking(move || process::task_stop(&instance)).await),
+        ("POST", "/api/cancel") => to_value(blocking(move || run_plan::cancel_plan(&instance)).await),
+        (method, path) if ROUTES.contains(&path) => return Err((405, format!("{} 不支持 {}", path, method))),
+        (_, path) => return Err((404, format!("未知接口: {}", path))),
+    };
+    result.map_err(|e| (400, e))
+}
+
+async fn write_response(stream: &mut TcpStream, status: u16, body: Option<&Value>) -> std::io::Result<()> {
+    let reason = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        _ => "Error",
+    };
+    let body = body.map(|b| b.to_string()).unwrap_or_default();
+    let head = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        reason,
+        body.len()
+    );
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(body.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+// 事件订阅，连接结束（包括服务停止时任务被丢弃）时取消
+struct Subscription(u64);
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        events::unsubscribe(self.0);
+    }
+}
+
+// WebSocket 会话：推送事件，回应 ping，收到 close 或连接断开时结束
+async fn websocket_session(mut stream: TcpStream, request: &Request) {
+    let upgrade = request
+        .headers
+        .get("upgrade")
+        .is_some_and(|value| value.eq_ignore_ascii_case("websocket"));
+    let key = match request.headers.get("sec-websocket-key") {
+        Some(key) if upgrade => key,
+        _ => {
+            let _ = write_response(&mut stream, 400, Some(&json!({ "ok": false, "error": "需要 WebSocket 握手" }))).await;
+            return;
+        }
+    };
+    let handshake = format!(
+        "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+        websocket::accept_key(key)
+    );
+    if stream.write_all(handshake.as_bytes()).await.is_err() {
+        return;
+    }
+
+    // 订阅回调在发送事件的线程中执行，不能等待：队列已满时丢弃事件并标记，由会话断开连接
+    let (events_tx, mut events_rx) = mpsc::channel::<String>(EVENT_QUEUE_CAPACITY);
+    let overflowed = Arc::new(AtomicBool::new(false));
+    let overflow_flag = overflowed.clone();
+    let _subscription = Subscription(events::subscribe(move |event, payload| {
+        if FORWARDED_EVENTS.contains(&event) && !overflow_flag.load(Ordering::Relaxed) {
+            let message = json!({ "event": event, "payload": payload }).to_string();
+            if let Err(mpsc::error::TrySendError::Full(_)) = events_tx.try_send(message) {
+                overflow_flag.store(true, Ordering::Relaxed);
+            }
+        }
+    }));
+
+    // 读取帧不能被 select 中断，放到单独的任务
+    let (mut reader, mut writer) = stream.into_split();
+    let (frames_tx, mut frames_rx) = mpsc::channel::<Frame>(FRAME_QUEUE_CAPACITY);
+    let reader_task = tokio::spawn(async move {
+        while let Ok(frame) = websocket::read_frame(&mut reader, MAX_FRAME_BYTES).await {
+            if frames_tx.send(frame).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    loop {
+        if overflowed.load(Ordering::Relaxed) {
+            let _ = log(LogSource::Backend, LogLevel::WARN, "远程事件推送的客户端读取过慢，已断开连接".to_string());
+            let _ = writer.write_all(&websocket::encode_frame(OP_CLOSE, &[])).await;
+            break;
+        }
+        let outgoing = tokio::select! {
+            Some(message) = events_rx.recv() => websocket::encode_frame(OP_TEXT, message.as_bytes()),
+            frame = frames_rx.recv() => match frame {
+                Some(Frame { opcode: OP_PING, payload }) => websocket::encode_frame(OP_PONG, &payload),
+                Some(Frame { opcode: OP_CLOSE, .. }) | None => {
+                    let _ = writer.write_all(&websocket::encode_frame(OP_CLOSE, &[])).await;
+                    break;
+                }
+                Some(_) => continue,
+            },
+        };
+        if writer.write_all(&outgoing).await.is_err() {
+            break;
+        }
+    }
+    reader_task.abort();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_backend::tests::{setup, BASIC_SESSION};
+    use crate::process;
+    use crate::types::DEFAULT_INSTANCE;
+
+    // 发送一个 HTTP 请求，返回完整响应
+    fn http_request(address: std::net::SocketAddr, head: &str, body: &str) -> String {
+        use std::io::{Read, Write};
+        let mut stream = std::net::TcpStream::connect(address).unwrap();
+        write!(stream, "{}\r\nContent-Length: {}\r\n\r\n{}", head, body.len(), body).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    #[test]
+    fn test_remote_api_requires_token_and_streams_events() {
+        use std::io::{Read, Write};
+        let (_guard, _mock) = setup(BASIC_SESSION);
+        settings::save_remote_api_token("secret-token").unwrap();
+        settings::save_remote_api_settings(true, "127.0.0.1:0".to_string()).unwrap();
+        apply_settings().unwrap();
+        let address = local_address().unwrap();
+
+        let denied = http_request(address, "GET /api/status HTTP/1.1\r\nAuthorization: Bearer wrong-token", "");
+        assert!(denied.starts_with("HTTP/1.1 401"), "{}", denied);
+        let status = http_request(address, "GET /api/status HTTP/1.1\r\nAuthorization: Bearer secret-token", "");
+        assert!(status.starts_with("HTTP/1.1 200") && status.contains("\"ok\":true"), "{}", status);
+        let missing = http_request(address, "GET /api/missing HTTP/1.1\r\nAuthorization: Bearer secret-token", "");
+        assert!(missing.starts_with("HTTP/1.1 404"), "{}", missing);
+        // 网页发起的请求即使带有正确的令牌也拒绝，且响应不含跨域头
+        let from_page = http_request(address, "GET /api/status HTTP/1.1\r\nOrigin: https://example.com\r\nAuthorization: Bearer secret-token", "");
+        assert!(from_page.starts_with("HTTP/1.1 403") && !from_page.contains("Access-Control"), "{}", from_page);
+
+        // WebSocket 握手（令牌通过查询参数传递）
+        let mut ws = std::net::TcpStream::connect(address).unwrap();
+        ws.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        ws.write_all(b"GET /api/ws?token=secret-token HTTP/1.1\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\nSec-WebSocket-Version: 13\r\n\r\n").unwrap();
+        let mut handshake = Vec::new();
+        while !handshake.ends_with(b"\r\n\r\n") {
+            let mut byte = [0u8; 1];
+            ws.read_exact(&mut byte).unwrap();
+            handshake.push(byte[0]);
+        }
+        let handshake = String::from_utf8(handshake).unwrap();
+        assert!(handshake.starts_with("HTTP/1.1 101") && handshake.contains("s3pPLMBiTxaQ9kYGzzhZRbK+xOo="), "{}", handshake);
+
+        // 远程启动后，状态变化通过 WebSocket 推送
+        let started = http_request(address, "POST /api/start HTTP/1.1\r\nAuthorization: Bearer secret-token", "{}");
+        assert!(started.starts_with("HTTP/1.1 200"), "{}", started);
+        let mut pushed = false;
+        while !pushed {
+            let mut header = [0u8; 2];
+            ws.read_exact(&mut header).unwrap();
+            let len = match header[1] & 0x7F {
+                126 => {
+                    let mut ext = [0u8; 2];
+                    ws.read_exact(&mut ext).unwrap();
+                    u16::from_be_bytes(ext) as usize
+                }
+                len => len as usize,
+            };
+            let mut payload = vec![0u8; len];
+            ws.read_exact(&mut payload).unwrap();
+            pushed = String::from_utf8_lossy(&payload).contains("\"event\":\"sra-status-changed\"");
+        }
+
+        settings::save_remote_api_settings(false, "127.0.0.1:8765".to_string()).unwrap();
+        apply_settings().unwrap();
+        assert!(local_address().is_none());
+        process::stop_sra_process_command(DEFAULT_INSTANCE).unwrap();
+    }
+}
@@ -0,0 +1,135 @@
+// 游戏日与刷新时间
+// 星穹铁道每天 04:00（服务器时间）刷新，每周一 04:00 周常刷新；服务器时间取决于区服，
+// 「游戏日」以刷新时间为界（如国服 03:59 仍属于前一天），运行历史和计划按游戏日统计
+
+use crate::config;
+use crate::settings;
+use chrono::{DateTime, Datelike, Duration, FixedOffset, NaiveDate, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+
+// 每日刷新时间（服务器时间的小时）
+const DAILY_RESET_HOUR: i64 = 4;
+
+// 区服
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum ServerRegion {
+    #[default]
+    Cn,      // 国服（官服、B 服），UTC+8
+    Asia,    // 亚服 / 港澳台服，UTC+8
+    America, // 美服，UTC-5
+    Europe,  // 欧服，UTC+1
+}
+
+impl ServerRegion {
+    // 服务器时区（不随夏令时变化）
+    pub fn utc_offset_hours(&self) -> i32 {
+        match self {
+            ServerRegion::Cn | ServerRegion::Asia => 8,
+            ServerRegion::America => -5,
+            ServerRegion::Europe => 1,
+        }
+    }
+
+    fn offset(&self) -> FixedOffset {
+        FixedOffset::east_opt(self.utc_offset_hours() * 3600).unwrap()
+    }
+
+    // 某一时刻所属的游戏日
+    pub fn game_day<Tz: TimeZone>(&self, time: &DateTime<Tz>) -> NaiveDate {
+        (time.with_timezone(&self.offset()) - Duration::hours(DAILY_RESET_HOUR)).date_naive()
+    }
+
+    // 游戏日开始（即当天刷新）的时刻
+    fn game_day_start(&self, day: NaiveDate) -> DateTime<Utc> {
+        let naive = day.and_hms_opt(DAILY_RESET_HOUR as u32, 0, 0).unwrap();
+        self.offset().from_local_datetime(&naive).unwrap().with_timezone(&Utc)
+    }
+
+    // 下一次每日刷新
+    pub fn next_daily_reset<Tz: TimeZone>(&self, time: &DateTime<Tz>) -> DateTime<Utc> {
+        self.game_day_start(self.game_day(time) + Duration::days(1))
+    }
+
+    // 下一次周常刷新（周一）
+    pub fn next_weekly_reset<Tz: TimeZone>(&self, time: &DateTime<Tz>) -> DateTime<Utc> {
+        let day = self.game_day(time);
+        let days_to_monday = 7 - day.weekday().num_days_from_monday() as i64;
+        self.game_day_start(day + Duration::days(days_to_monday))
+    }
+}
+
+// 根据配置的启动渠道推断区服（官服、B 服均为国服）
+fn region_from_config(config_name: &str) -> Option<ServerRegion> {
+    let config = config::load_config(config_name.to_string()).ok()?;
+    match config.get("StartGameChannel")?.as_i64()? {
+        0 | 1 => Some(ServerRegion::Cn),
+        _ => None,
+    }
+}
+
+// 确定区服：CE 设置中明确指定的优先，其次由配置推断，默认为国服
+pub fn resolve_region(config_name: Option<&str>) -> ServerRegion {
+    if let Some(region) = settings::load_ce_settings_or_default().server_region {
+        return region;
+    }
+    config_name
+        .and_then(region_from_config)
+        .unwrap_or_default()
+}
+
+// 刷新时间信息
+#[derive(Debug, Clone, Serialize)]
+pub struct ResetClockInfo {
+    pub region: ServerRegion,
+    pub utc_offset_hours: i32,
+    pub game_day: String,          // YYYY-MM-DD
+    pub next_daily_reset: String,  // ISO 8601 格式（本地时间）
+    pub next_weekly_reset: String,
+}
+
+pub fn reset_clock_info(region: ServerRegion, now: DateTime<Utc>) -> ResetClockInfo {
+    let local = |time: DateTime<Utc>| time.with_timezone(&chrono::Local).to_rfc3339();
+    ResetClockInfo {
+        region,
+        utc_offset_hours: region.utc_offset_hours(),
+        game_day: region.game_day(&now).format("%Y-%m-%d").to_string(),
+        next_daily_reset: local(region.next_daily_reset(&now)),
+        next_weekly_reset: local(region.next_weekly_reset(&now)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_game_day_boundaries() {
+        // 国服 2025-12-01（周一）03:59 仍属于周日的游戏日
+        let before = utc("2025-11-30T19:59:00Z");
+        let after = utc("2025-11-30T20:00:00Z");
+        assert_eq!(ServerRegion::Cn.game_day(&before).to_string(), "2025-11-30");
+        assert_eq!(ServerRegion::Cn.game_day(&after).to_string(), "2025-12-01");
+
+        // 同一时刻在美服仍是前一个游戏日（服务器时间 15:00）
+        assert_eq!(ServerRegion::America.game_day(&after).to_string(), "2025-11-30");
+    }
+
+    #[test]
+    fn test_next_resets() {
+        let now = utc("2025-11-30T19:59:00Z");
+        assert_eq!(ServerRegion::Cn.next_daily_reset(&now), utc("2025-11-30T20:00:00Z"));
+        assert_eq!(ServerRegion::Cn.next_weekly_reset(&now), utc("2025-11-30T20:00:00Z"));
+
+        // 周一刷新之后，下一次周常刷新在一周后
+        let monday = utc("2025-11-30T20:00:00Z");
+        assert_eq!(ServerRegion::Cn.next_daily_reset(&monday), utc("2025-12-01T20:00:00Z"));
+        assert_eq!(ServerRegion::Cn.next_weekly_reset(&monday), utc("2025-12-07T20:00:00Z"));
+
+        // 欧服周一 04:00 (UTC+1) 即 UTC 03:00
+        assert_eq!(ServerRegion::Europe.next_weekly_reset(&monday), utc("2025-12-01T03:00:00Z"));
+    }
+}
@@ -0,0 +1,252 @@
+// SRA-cli 可执行文件定位模块
+// 按固定顺序检查候选路径：环境变量 -> CE 设置 -> 开发目录 -> 安装目录
+// 每个候选都会记录到诊断报告中，便于排查非标准安装
+
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+// SRA-cli 可执行文件名
+pub const SRA_CLI_EXE: &str = "SRA-cli.exe";
+
+// 覆盖 SRA-cli 路径的环境变量（可指向 exe 文件或其所在目录）
+pub const SRA_CLI_PATH_ENV: &str = "SRA_CLI_PATH";
+
+// 候选路径来源
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum CandidateSource {
+    EnvOverride,   // 环境变量 SRA_CLI_PATH
+    Settings,      // SRA-CE-Settings.json 中的 SraCliPath
+    DevLayout,     // 开发环境: <项目根>/StarRailAssistant/SRA-cli.exe
+    InstallLayout, // 安装环境: <exe 目录>/SRA-cli.exe
+}
+
+impl CandidateSource {
+    pub fn as_str(&self) -> &str {
+        match self {
+            CandidateSource::EnvOverride => "环境变量",
+            CandidateSource::Settings => "设置",
+            CandidateSource::DevLayout => "开发目录",
+            CandidateSource::InstallLayout => "安装目录",
+        }
+    }
+}
+
+// 单个候选的检查结果
+#[derive(Debug, Clone, Serialize)]
+pub struct ResolveAttempt {
+    pub source: CandidateSource,
+    pub path: Option<String>,
+    pub rejected: Option<String>, // None 表示该候选被采用
+}
+
+// 最终选中的 SRA-cli 路径
+#[derive(Debug, Clone, Serialize)]
+pub struct ResolvedCli {
+    pub source: CandidateSource,
+    pub path: PathBuf,
+    pub working_dir: PathBuf,
+}
+
+// 定位诊断报告
+#[derive(Debug, Clone, Serialize)]
+pub struct ResolveReport {
+    pub resolved: Option<ResolvedCli>,
+    pub attempts: Vec<ResolveAttempt>,
+}
+
+impl ResolveReport {
+    // 生成多行可读摘要（用于错误信息和控制台输出）
+    pub fn summary(&self) -> String {
+        let mut lines = Vec::new();
+        for attempt in &self.attempts {
+            let path = attempt.path.as_deref().unwrap_or("-");
+            match attempt.rejected {
+                Some(ref reason) => lines.push(format!("[{}] {} -> {}", attempt.source.as_str(), path, reason)),
+                None => lines.push(format!("[{}] {} -> 已采用", attempt.source.as_str(), path)),
+            }
+        }
+        lines.join("\n")
+    }
+}
+
+// 检查单个候选路径；目录会自动拼接 SRA-cli.exe
+fn check_candidate(path: &Path) -> Result<(PathBuf, PathBuf), String> {
+    let exe_path = if path.is_dir() {
+        path.join(SRA_CLI_EXE)
+    } else {
+        path.to_path_buf()
+    };
+
+    if !exe_path.exists() {
+        return Err("路径不存在".to_string());
+    }
+    if !exe_path.is_file() {
+        return Err("不是文件".to_string());
+    }
+
+    let working_dir = exe_path
+        .parent()
+        .map(|p| p.to_path_buf())
+        .ok_or("无法确定工作目录")?;
+
+    Ok((exe_path, working_dir))
+}
+
+// 按顺序检查所有候选路径（纯函数，便于测试）
+pub fn resolve(exe_dir: &Path, env_override: Option<String>, settings_path: Option<String>) -> ResolveReport {
+    let mut candidates: Vec<(CandidateSource, Result<PathBuf, String>)> = Vec::new();
+
+    candidates.push((
+        CandidateSource::EnvOverride,
+        match env_override {
+            Some(value) if !value.trim().is_empty() => Ok(PathBuf::from(value.trim())),
+            _ => Err(format!("未设置 {}", SRA_CLI_PATH_ENV)),
+        },
+    ));
+
+    candidates.push((
+        CandidateSource::Settings,
+        match settings_path {
+            Some(value) if !value.trim().is_empty() => Ok(PathBuf::from(value.trim())),
+            _ => Err("设置中未配置 SraCliPath".to_string()),
+        },
+    ));
+
+    // 开发环境下 exe 位于 <项目根>/src-tauri/target/<profile>/
+    candidates.push((
+        CandidateSource::DevLayout,
+        exe_dir
+            .ancestors()
+            .nth(3)
+            .map(|project_root| project_root.join("StarRailAssistant").join(SRA_CLI_EXE))
+            .ok_or_else(|| "目录层级不足，不是开发目录结构".to_string()),
+    ));
+
+    candidates.push((CandidateSource::InstallLayout, Ok(exe_dir.join(SRA_CLI_EXE))));
+
+    let mut report = ResolveReport {
+        resolved: None,
+        attempts: Vec::new(),
+    };
+
+    for (source, candidate) in candidates {
+        // 已找到时，剩余候选不再检查
+        if report.resolved.is_some() {
+            break;
+        }
+
+        let (path, checked) = match candidate {
+            Ok(path) => {
+                let checked = check_candidate(&path);
+                (Some(path.display().to_string()), checked)
+            }
+            Err(reason) => (None, Err(reason)),
+        };
+
+        match checked {
+            Ok((exe_path, working_dir)) => {
+                report.attempts.push(ResolveAttempt {
+                    source,
+                    path: Some(exe_path.display().to_string()),
+                    rejected: None,
+                });
+                report.resolved = Some(ResolvedCli {
+                    source,
+                    path: exe_path,
+                    working_dir,
+                });
+            }
+            Err(reason) => {
+                report.attempts.push(ResolveAttempt {
+                    source,
+                    path,
+                    rejected: Some(reason),
+                });
+            }
+        }
+    }
+
+    report
+}
+
+// 使用当前环境（exe 目录、环境变量、CE 设置）定位 SRA-cli
+pub fn resolve_sra_cli() -> Result<ResolveReport, String> {
+    let exe_dir = std::env::current_exe()
+        .map_err(|e| format!("Failed to get current exe path: {}", e))?
+        .parent()
+        .ok_or("Failed to get parent directory")?
+        .to_path_buf();
+
+    let env_override = std::env::var(SRA_CLI_PATH_ENV).ok();
+    let settings_path = crate::settings::load_ce_settings_or_default().sra_cli_path;
+
+    Ok(resolve(&exe_dir, env_override, settings_path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn temp_layout(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("sra-resolver-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("src-tauri").join("target").join("debug")).unwrap();
+        root
+    }
+
+    #[test]
+    fn test_install_layout_fallback() {
+        let root = temp_layout("install");
+        let exe_dir = root.join("src-tauri").join("target").join("debug");
+        fs::write(exe_dir.join(SRA_CLI_EXE), b"").unwrap();
+
+        let report = resolve(&exe_dir, None, None);
+        let resolved = report.resolved.expect("should resolve");
+        assert_eq!(resolved.source, CandidateSource::InstallLayout);
+        assert_eq!(resolved.working_dir, exe_dir);
+        assert_eq!(report.attempts.len(), 4);
+        assert!(report.attempts[..3].iter().all(|a| a.rejected.is_some()));
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_env_override_takes_precedence() {
+        let root = temp_layout("env");
+        let exe_dir = root.join("src-tauri").join("target").join("debug");
+        let custom_dir = root.join("custom");
+        fs::create_dir_all(&custom_dir).unwrap();
+        fs::write(custom_dir.join(SRA_CLI_EXE), b"").unwrap();
+        fs::write(exe_dir.join(SRA_CLI_EXE), b"").unwrap();
+
+        // 环境变量指向目录，设置指向不存在的文件
+        let report = resolve(
+            &exe_dir,
+            Some(custom_dir.display().to_string()),
+            Some(root.join("missing.exe").display().to_string()),
+        );
+        let resolved = report.resolved.expect("should resolve");
+        assert_eq!(resolved.source, CandidateSource::EnvOverride);
+        assert_eq!(resolved.path, custom_dir.join(SRA_CLI_EXE));
+        assert_eq!(report.attempts.len(), 1);
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_report_lists_every_rejection() {
+        let root = temp_layout("missing");
+        let exe_dir = root.join("src-tauri").join("target").join("debug");
+
+        let report = resolve(&exe_dir, None, Some(root.join("nope.exe").display().to_string()));
+        assert!(report.resolved.is_none());
+        assert_eq!(report.attempts.len(), 4);
+        assert_eq!(report.attempts[1].source, CandidateSource::Settings);
+        assert_eq!(report.attempts[1].rejected.as_deref(), Some("路径不存在"));
+        assert!(report.summary().lines().count() == 4);
+
+        let _ = fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,425 @@
+// 任务运行历史
+// 根据 [Start]/[Done] 标记和状态转换记录每次任务运行（配置、起止时间、结果、错误、日志行数），
+// 追加保存到 JSON Lines 文件，供按配置和日期范围查询
+
+use crate::events;
+use crate::logger::{self, LogLevel, LogSource};
+use crate::reset_clock;
+use crate::sra_parser::ParsedLog;
+use crate::types::SraStatus;
+use chrono::{DateTime, Local, NaiveDate, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Instant;
+
+// 每次运行最多保留的错误条数
+const MAX_ERRORS_PER_RUN: usize = 50;
+
+// 任务运行结果
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RunOutcome {
+    Done,    // 正常完成（[Done]）
+    Stopped, // 用户停止（task stop 或停止进程）
+    Crashed, // 运行中进程意外退出
+    Errored, // 运行中进程进入错误状态
+    TimedOut, // 超过最长运行时间或长时间无输出
+}
+
+// 一次任务运行的记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskRun {
+    pub run_id: String,
+    pub instance_id: String,
+    pub config: Option<String>, // 配置名（[Start] 标记或 task run 命令中的名称）
+    pub started: String,        // ISO 8601 格式
+    pub ended: String,
+    pub duration_ms: u64,
+    pub outcome: RunOutcome,
+    pub errors: Vec<String>,
+    pub log_lines: u64,
+    #[serde(default)]
+    pub game_day: Option<String>, // 开始时所属的游戏日（YYYY-MM-DD，以区服的每日刷新为界）
+}
+
+// 正在进行的运行
+struct ActiveRun {
+    run_id: String,
+    config: Option<String>,
+    started: DateTime<Local>,
+    started_at: Instant,
+    stop_requested: bool,
+    timed_out: bool,
+    last_output: Instant,
+    errors: Vec<String>,
+    log_lines: u64,
+}
+
+// 正在进行的运行（供超时检测使用）
+#[derive(Debug, Clone)]
+pub struct ActiveRunInfo {
+    pub instance_id: String,
+    pub config: Option<String>,
+    pub started_at: Instant,
+    pub last_output: Instant,
+}
+
+// 每个实例的运行跟踪
+#[derive(Default)]
+struct InstanceRuns {
+    pending_config: Option<String>, // 最近一次 task run 命令指定的配置
+    active: Option<ActiveRun>,
+}
+
+static RUNS: Mutex<BTreeMap<String, InstanceRuns>> = Mutex::new(BTreeMap::new());
+
+// 历史文件写入锁
+static HISTORY_FILE_LOCK: Mutex<()> = Mutex::new(());
+
+// 历史文件路径
+fn history_file() -> Result<PathBuf, String> {
+    Ok(crate::paths::data_dir()?.join("SRA-CE-History").join("task-runs.jsonl"))
+}
+
+// 记录发送给 SRA-cli 的命令（task run 的配置名、task stop 请求）
+pub fn note_command(instance_id: &str, command: &str) {
+    let command = command.trim();
+    let mut runs = match RUNS.lock() {
+        Ok(runs) => runs,
+        Err(_) => return,
+    };
+    let entry = runs.entry(instance_id.to_string()).or_default();
+    if let Some(rest) = command.strip_prefix("task run") {
+        if rest.is_empty() || rest.starts_with(char::is_whitespace) {
+            let config = rest.trim();
+            entry.pending_config = if config.is_empty() { None } else { Some(config.to_string()) };
+        }
+    } else if command == "task stop" {
+        if let Some(ref mut active) = entry.active {
+            active.stop_requested = true;
+        }
+    }
+}
+
+// 从 [Start] 标记行中取出配置名
+pub fn note_start_marker(instance_id: &str, line: &str) {
+    let config = match line.split_once("[Start]") {
+        Some((_, rest)) if !rest.trim().is_empty() => rest.trim().to_string(),
+        _ => return,
+    };
+    if let Ok(mut runs) = RUNS.lock() {
+        if let Some(active) = runs.get_mut(instance_id).and_then(|r| r.active.as_mut()) {
+            active.config = Some(config);
+        }
+    }
+}
+
+// 记录运行期间的输出时间（用于检测无输出的卡死）
+pub fn note_output(instance_id: &str) {
+    if let Ok(mut runs) = RUNS.lock() {
+        if let Some(active) = runs.get_mut(instance_id).and_then(|r| r.active.as_mut()) {
+            active.last_output = Instant::now();
+        }
+    }
+}
+
+// 实例正在进行的运行 ID
+pub fn current_run_id(instance_id: &str) -> Option<String> {
+    let runs = RUNS.lock().ok()?;
+    runs.get(instance_id)?.active.as_ref().map(|active| active.run_id.clone())
+}
+
+// 所有未标记超时的运行
+pub fn active_runs() -> Vec<ActiveRunInfo> {
+    let runs = match RUNS.lock() {
+        Ok(runs) => runs,
+        Err(_) => return Vec::new(),
+    };
+    runs.iter()
+        .filter_map(|(instance_id, r)| r.active.as_ref().map(|active| (instance_id, active)))
+        .filter(|(_, active)| !active.timed_out)
+        .map(|(instance_id, active)| ActiveRunInfo {
+            instance_id: instance_id.clone(),
+            config: active.config.clone(),
+            started_at: active.started_at,
+            last_output: active.last_output,
+        })
+        .collect()
+}
+
+// 将运行标记为超时并记录原因，运行已结束或已标记时返回 false
+pub fn mark_timed_out(instance_id: &str, reason: &str) -> bool {
+    let mut runs = match RUNS.lock() {
+        Ok(runs) => runs,
+        Err(_) => return false,
+    };
+    match runs.get_mut(instance_id).and_then(|r| r.active.as_mut()) {
+        Some(active) if !active.timed_out => {
+            active.timed_out = true;
+            active.errors.push(reason.to_string());
+            true
+        }
+        _ => false,
+    }
+}
+
+// 统计运行期间解析出的日志
+pub fn note_logs(instance_id: &str, logs: &[ParsedLog]) {
+    let mut runs = match RUNS.lock() {
+        Ok(runs) => runs,
+        Err(_) => return,
+    };
+    if let Some(active) = runs.get_mut(instance_id).and_then(|r| r.active.as_mut()) {
+        for log in logs {
+            active.log_lines += 1;
+            if matches!(log.level, LogLevel::ERR) && active.errors.len() < MAX_ERRORS_PER_RUN {
+                active.errors.push(log.message.clone());
+            }
+        }
+    }
+}
+
+// 状态转换时开始或结束运行记录
+pub fn on_transition(instance_id: &str, from: SraStatus, to: SraStatus) {
+    if to == SraStatus::TaskRunning && from != SraStatus::TaskRunning {
+        begin_run(instance_id);
+        return;
+    }
+    if from != SraStatus::TaskRunning || to == SraStatus::TaskRunning {
+        return;
+    }
+    let outcome = match to {
+        SraStatus::Crashed => RunOutcome::Crashed,
+        SraStatus::Error => RunOutcome::Errored,
+        SraStatus::Idle => RunOutcome::Done,
+        _ => RunOutcome::Stopped,
+    };
+    finish_run(instance_id, outcome);
+}
+
+fn begin_run(instance_id: &str) {
+    if let Ok(mut runs) = RUNS.lock() {
+        let entry = runs.entry(instance_id.to_string()).or_default();
+        let started = Local::now();
+        entry.active = Some(ActiveRun {
+            run_id: format!("{}-{}", instance_id, started.format("%Y%m%d%H%M%S%3f")),
+            config: entry.pending_config.take(),
+            started,
+            started_at: Instant::now(),
+            stop_requested: false,
+            timed_out: false,
+            last_output: Instant::now(),
+            errors: Vec::new(),
+            log_lines: 0,
+        });
+    }
+}
+
+// 结束的运行在进程锁内登记，解析区服、保存和发送事件在锁外进行
+struct FinishedRun {
+    instance_id: String,
+    active: ActiveRun,
+    outcome: RunOutcome,
+    ended: DateTime<Local>,
+    duration_ms: u64,
+}
+
+// 等待保存的运行记录（按结束顺序）
+static FINISHED_RUNS: Mutex<Vec<FinishedRun>> = Mutex::new(Vec::new());
+
+// 保证保存和事件的顺序与结束顺序一致
+static SAVE_LOCK: Mutex<()> = Mutex::new(());
+
+// 由状态转换调用（持有进程锁），只记录结束的运行，保存交给后台线程
+fn finish_run(instance_id: &str, outcome: RunOutcome) {
+    let active = match RUNS.lock() {
+        Ok(mut runs) => runs.get_mut(instance_id).and_then(|r| r.active.take()),
+        Err(_) => None,
+    };
+    let active = match active {
+        Some(active) => active,
+        None => return,
+    };
+
+    // 收到 task stop 后 SRA-cli 同样输出 [Done]，此时记为停止；超时中止的运行无论如何结束都记为超时
+    let outcome = if active.timed_out {
+        RunOutcome::TimedOut
+    } else if outcome == RunOutcome::Done && active.stop_requested {
+        RunOutcome::Stopped
+    } else {
+        outcome
+    };
+    let finished = FinishedRun {
+        instance_id: instance_id.to_string(),
+        duration_ms: active.started_at.elapsed().as_millis() as u64,
+        ended: Local::now(),
+        active,
+        outcome,
+    };
+    if let Ok(mut pending) = FINISHED_RUNS.lock() {
+        pending.push(finished);
+    }
+    thread::spawn(save_finished_runs);
+}
+
+// 保存结束的运行并发送 sra-task-run-finished 事件（不能在进程锁内调用）
+pub(crate) fn save_finished_runs() {
+    let _order = match SAVE_LOCK.lock() {
+        Ok(guard) => guard,
+        Err(_) => return,
+    };
+    let pending = match FINISHED_RUNS.lock() {
+        Ok(mut pending) => std::mem::take(&mut *pending),
+        Err(_) => return,
+    };
+    for finished in pending {
+        let active = finished.active;
+        let region = reset_clock::resolve_region(active.config.as_deref());
+        let game_day = region.game_day(&active.started).format("%Y-%m-%d").to_string();
+        let run = TaskRun {
+            run_id: active.run_id,
+            instance_id: finished.instance_id,
+            config: active.config,
+            started: active.started.to_rfc3339(),
+            ended: finished.ended.to_rfc3339(),
+            duration_ms: finished.duration_ms,
+            outcome: finished.outcome,
+            errors: active.errors,
+            log_lines: active.log_lines,
+            game_day: Some(game_day),
+        };
+        if let Err(e) = append_run(&run) {
+            let _ = logger::log(LogSource::Backend, LogLevel::ERR, format!("保存任务运行记录失败: {}", e));
+        }
+        events::emit("sra-task-run-finished", run);
+    }
+}
+
+// 追加一条运行记录
+fn append_run(run: &TaskRun) -> Result<(), String> {
+    let path = history_file()?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create history directory: {}", e))?;
+    }
+    let line = serde_json::to_string(run).map_err(|e| format!("Failed to serialize task run: {}", e))?;
+    let _guard = HISTORY_FILE_LOCK.lock().map_err(|e| format!("Lock error: {}", e))?;
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| format!("Failed to open history file: {}", e))?;
+    writeln!(file, "{}", line).map_err(|e| format!("Failed to write history file: {}", e))
+}
+
+// 解析查询时间：ISO 8601 时间或 YYYY-MM-DD 日期（日期作为结束时间时包含当天）
+pub(crate) fn parse_bound(value: &str, end_of_day: bool) -> Result<DateTime<Local>, String> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(time.with_timezone(&Local));
+    }
+    let date = NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| format!("无法识别的日期: {}（应为 YYYY-MM-DD 或 ISO 8601 时间）", value))?;
+    let date = if end_of_day { date.succ_opt().unwrap_or(date) } else { date };
+    Local
+        .from_local_datetime(&date.and_hms_opt(0, 0, 0).unwrap())
+        .earliest()
+        .ok_or_else(|| format!("无效的本地时间: {}", value))
+}
+
+// 运行历史查询条件
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct RunQuery {
+    pub config: Option<String>,
+    pub instance_id: Option<String>,
+    pub from: Option<String>, // 包含
+    pub to: Option<String>,   // 不包含（日期格式时包含当天）
+    pub outcome: Option<RunOutcome>,
+    pub game_day: Option<String>, // 按游戏日筛选（YYYY-MM-DD）
+    pub limit: Option<usize>,
+}
+
+// 查询运行历史（最新的在前）
+pub fn query_runs(query: &RunQuery) -> Result<Vec<TaskRun>, String> {
+    // 先保存已结束但尚未写入的运行
+    save_finished_runs();
+    let from = query.from.as_deref().map(|v| parse_bound(v, false)).transpose()?;
+    let to = query.to.as_deref().map(|v| parse_bound(v, true)).transpose()?;
+
+    let path = history_file()?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let file = fs::File::open(&path).map_err(|e| format!("Failed to open history file: {}", e))?;
+
+    let mut runs = Vec::new();
+    for line in BufReader::new(file).lines() {
+        let line = line.map_err(|e| format!("Failed to read history file: {}", e))?;
+        // 跳过损坏的行（如写入中断）
+        let run: TaskRun = match serde_json::from_str(&line) {
+            Ok(run) => run,
+            Err(_) => continue,
+        };
+        if query.config.as_ref().is_some_and(|c| run.config.as_ref() != Some(c)) {
+            continue;
+        }
+        if query.instance_id.as_ref().is_some_and(|i| &run.instance_id != i) {
+            continue;
+        }
+        if query.outcome.is_some_and(|o| run.outcome != o) {
+            continue;
+        }
+        if query.game_day.is_some() && run.game_day != query.game_day {
+            continue;
+        }
+        if from.is_some() || to.is_some() {
+            let started = match DateTime::parse_from_rfc3339(&run.started) {
+                Ok(time) => time.with_timezone(&Local),
+                Err(_) => continue,
+            };
+            if from.is_some_and(|f| started < f) || to.is_some_and(|t| started >= t) {
+                continue;
+            }
+        }
+        runs.push(run);
+    }
+
+    runs.reverse();
+    if let Some(limit) = query.limit {
+        runs.truncate(limit);
+    }
+    Ok(runs)
+}
+
+// 配置在当前游戏日是否已经完成过
+pub fn done_this_game_day(config: &str) -> Result<bool, String> {
+    let region = reset_clock::resolve_region(Some(config));
+    let query = RunQuery {
+        config: Some(config.to_string()),
+        outcome: Some(RunOutcome::Done),
+        game_day: Some(region.game_day(&Local::now()).format("%Y-%m-%d").to_string()),
+        limit: Some(1),
+        ..Default::default()
+    };
+    Ok(!query_runs(&query)?.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_bound_dates_and_times() {
+        let from = parse_bound("2025-11-29", false).unwrap();
+        let to = parse_bound("2025-11-29", true).unwrap();
+        assert_eq!(to - from, chrono::Duration::days(1));
+        assert_eq!(from.format("%Y-%m-%d %H:%M").to_string(), "2025-11-29 00:00");
+
+        let exact = parse_bound("2025-11-29T13:20:11+08:00", true).unwrap();
+        assert_eq!(exact.timestamp(), DateTime::parse_from_rfc3339("2025-11-29T05:20:11Z").unwrap().timestamp());
+        assert!(parse_bound("29/11/2025", false).is_err());
+    }
+}
@@ -0,0 +1,515 @@
+// 运行计划
+// 按顺序逐个执行配置，等待每个配置 [Done] 后再开始下一个；每一项可设置出错重试次数、
+// 失败后的处理（继续 / 跳过 / 中止）和执行条件（如「上一项成功时才执行」），进度通过 plan-progress 事件通知
+
+use crate::config;
+use crate::events;
+use crate::logger::{log_instance, LogLevel, LogSource};
+use crate::post_actions;
+use crate::process;
+use crate::run_history::{self, RunOutcome};
+use crate::types::SraStatus;
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+// 等待实例就绪（空闲）的最长时间，包括崩溃后等待看门狗重启
+const READY_TIMEOUT: Duration = Duration::from_secs(60);
+
+// 失败（重试用尽）后的处理
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FailurePolicy {
+    #[default]
+    Continue, // 记为失败，继续执行后续配置
+    Skip,     // 记为跳过（不算作计划失败），继续执行后续配置
+    Abort,    // 中止整个计划
+}
+
+// 执行条件
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum EntryCondition {
+    #[default]
+    Always,
+    PreviousSucceeded, // 上一项成功时执行
+    PreviousFailed,    // 上一项失败时执行（如备用配置）
+    AllSucceeded,      // 之前所有项都没有失败时执行
+    NotDoneToday,      // 当前游戏日尚未完成时执行
+}
+
+// 计划中的一项
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PlanEntry {
+    pub config: String,
+    pub retries: u32, // 出错（错误、崩溃、超时）后的重试次数
+    pub on_failure: FailurePolicy,
+    pub condition: EntryCondition,
+}
+
+impl Default for PlanEntry {
+    fn default() -> Self {
+        Self {
+            config: String::new(),
+            retries: 1,
+            on_failure: FailurePolicy::Continue,
+            condition: EntryCondition::Always,
+        }
+    }
+}
+
+// 运行计划
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RunPlan {
+    pub name: String,
+    pub instance_id: Option<String>,
+    pub entries: Vec<PlanEntry>,
+    pub post_actions: bool, // 计划结束后按各配置的 After* 开关执行完成后操作
+}
+
+impl RunPlan {
+    // 按 task-order.json 生成计划（每项使用默认策略）
+    pub fn from_task_order(instance_id: Option<String>) -> Result<Self, String> {
+        let entries: Vec<PlanEntry> = config::load_task_order()?
+            .into_iter()
+            .map(|config| PlanEntry { config, ..Default::default() })
+            .collect();
+        Ok(Self {
+            name: "任务顺序".to_string(),
+            instance_id,
+            entries,
+            post_actions: true,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum EntryStatus {
+    Pending,
+    Running,
+    Succeeded,
+    Failed,
+    Skipped,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PlanResult {
+    Running,
+    Succeeded, // 没有失败的项
+    Failed,    // 有失败的项（失败策略为继续）
+    Aborted,   // 因失败策略中止
+    Cancelled, // 用户取消
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EntryReport {
+    pub config: String,
+    pub status: EntryStatus,
+    pub attempts: u32,
+    pub run_ids: Vec<String>,
+    pub message: Option<String>,
+}
+
+// 计划执行情况（plan-progress 事件的内容）
+#[derive(Debug, Clone, Serialize)]
+pub struct PlanReport {
+    pub plan_id: String,
+    pub name: String,
+    pub instance_id: String,
+    pub started: String, // ISO 8601 格式
+    pub ended: Option<String>,
+    pub result: PlanResult,
+    pub current: Option<usize>, // 正在执行的项
+    pub entries: Vec<EntryReport>,
+}
+
+struct ActivePlan {
+    report: PlanReport,
+    cancel_requested: bool,
+}
+
+// 每个实例同时只能执行一个计划（实例 ID -> 计划）
+static ACTIVE_PLANS: Mutex<BTreeMap<String, ActivePlan>> = Mutex::new(BTreeMap::new());
+
+// 实例是否正在执行计划
+pub fn is_running(instance_id: &str) -> bool {
+    ACTIVE_PLANS.lock().is_ok_and(|plans| plans.contains_key(instance_id))
+}
+
+// 正在执行的计划
+pub fn get_active_plans() -> Vec<PlanReport> {
+    ACTIVE_PLANS
+        .lock()
+        .map(|plans| plans.values().map(|p| p.report.clone()).collect())
+        .unwrap_or_default()
+}
+
+// 更新计划进度并通知
+fn update_report<F: FnOnce(&mut PlanReport)>(instance_id: &str, update: F) {
+    let report = match ACTIVE_PLANS.lock() {
+        Ok(mut plans) => match plans.get_mut(instance_id) {
+            Some(plan) => {
+                update(&mut plan.report);
+                plan.report.clone()
+            }
+            None => return,
+        },
+        Err(_) => return,
+    };
+    events::emit("plan-progress", report);
+}
+
+fn cancel_requested(instance_id: &str) -> bool {
+    ACTIVE_PLANS
+        .lock()
+        .is_ok_and(|plans| plans.get(instance_id).is_some_and(|p| p.cancel_requested))
+}
+
+// 取消实例正在执行的计划（正在运行的任务会被 task stop），返回是否有计划
+pub fn cancel_plan(instance_id: &str) -> Result<bool, String> {
+    let found = {
+        let mut plans = ACTIVE_PLANS.lock().map_err(|e| format!("Lock error: {}", e))?;
+        match plans.get_mut(instance_id) {
+            Some(plan) => {
+                plan.cancel_requested = true;
+                true
+            }
+            None => false,
+        }
+    };
+    if found && process::get_instance_status(instance_id)? == SraStatus::TaskRunning.as_str() {
+        process::task_stop(instance_id)?;
+    }
+    Ok(found)
+}
+
+// 在后台执行计划，返回计划 ID
+pub fn start_plan(plan: RunPlan) -> Result<String, String> {
+    let instance_id = claim(&plan)?;
+    let plan_id = ACTIVE_PLANS
+        .lock()
+        .map_err(|e| format!("Lock error: {}", e))?
+        .get(&instance_id)
+        .map(|p| p.report.plan_id.clone())
+        .unwrap_or_default();
+    thread::spawn(move || run_claimed(&instance_id, &plan));
+    Ok(plan_id)
+}
+
+// 执行计划并等待结束
+pub fn execute_plan(plan: RunPlan) -> Result<PlanReport, String> {
+    let instance_id = claim(&plan)?;
+    Ok(run_claimed(&instance_id, &plan))
+}
+
+// 登记计划，实例已有计划在执行时失败
+fn claim(plan: &RunPlan) -> Result<String, String> {
+    if plan.entries.is_empty() {
+        return Err("计划中没有要执行的配置".to_string());
+    }
+    let instance_id = process::instance_key(plan.instance_id.clone());
+    let mut plans = ACTIVE_PLANS.lock().map_err(|e| format!("Lock error: {}", e))?;
+    if plans.contains_key(&instance_id) {
+        return Err(format!("实例 {} 正在执行其他计划", instance_id));
+    }
+    let now = Local::now();
+    plans.insert(instance_id.clone(), ActivePlan {
+        report: PlanReport {
+            plan_id: format!("plan-{}-{}", instance_id, now.format("%Y%m%d%H%M%S%3f")),
+            name: plan.name.clone(),
+            instance_id: instance_id.clone(),
+            started: now.to_rfc3339(),
+            ended: None,
+            result: PlanResult::Running,
+            current: None,
+            entries: plan
+                .entries
+                .iter()
+                .map(|e| EntryReport {
+                    config: e.config.clone(),
+                    status: EntryStatus::Pending,
+                    attempts: 0,
+                    run_ids: Vec::new(),
+                    message: None,
+                })
+                .collect(),
+        },
+        cancel_requested: false,
+    });
+    Ok(instance_id)
+}
+
+fn run_claimed(instance_id: &str, plan: &RunPlan) -> PlanReport {
+    let _ = log_instance(
+        instance_id,
+        LogSource::Backend,
+        LogLevel::INFO,
+        format!("开始执行计划「{}」（{} 项）", plan.name, plan.entries.len()),
+    );
+    let result = run_entries(instance_id, plan);
+
+    update_report(instance_id, |report| {
+        report.result = result;
+        report.current = None;
+        report.ended = Some(Local::now().to_rfc3339());
+        for entry in report.entries.iter_mut().filter(|e| e.status == EntryStatus::Pending) {
+            entry.status = EntryStatus::Skipped;
+        }
+    });
+    // 计划只在这里移除，此时一定存在
+    let report = ACTIVE_PLANS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .remove(instance_id)
+        .map(|p| p.report)
+        .expect("计划在结束前被移除");
+
+    let level = if result == PlanResult::Succeeded { LogLevel::SUCCESS } else { LogLevel::WARN };
+    let _ = log_instance(instance_id, LogSource::Backend, level, format!("计划「{}」结束: {:?}", plan.name, result));
+    events::emit("plan-finished", report.clone());
+
+    // 用户取消时不执行完成后操作
+    if plan.post_actions && result != PlanResult::Cancelled {
+        let configs: Vec<String> = plan.entries.iter().map(|e| e.config.clone()).collect();
+        if let Err(e) = post_actions::schedule(post_actions::actions_for_configs(&configs)) {
+            let _ = log_instance(instance_id, LogSource::Backend, LogLevel::ERR, format!("无法执行完成后操作: {}", e));
+        }
+    }
+    report
+}
+
+// 条件是否满足（previous 为上一项的结果，failed_so_far 表示之前是否有失败的项）
+fn condition_met(entry: &PlanEntry, previous: Option<EntryStatus>, failed_so_far: bool) -> Result<bool, String> {
+    Ok(match entry.condition {
+        EntryCondition::Always => true,
+        EntryCondition::PreviousSucceeded => previous.is_none_or(|s| s == EntryStatus::Succeeded),
+        EntryCondition::PreviousFailed => previous == Some(EntryStatus::Failed),
+        EntryCondition::AllSucceeded => !failed_so_far,
+        EntryCondition::NotDoneToday => !run_history::done_this_game_day(&entry.config)?,
+    })
+}
+
+fn run_entries(instance_id: &str, plan: &RunPlan) -> PlanResult {
+    let mut previous = None;
+    let mut failed_so_far = false;
+
+    for (index, entry) in plan.entries.iter().enumerate() {
+        if cancel_requested(instance_id) {
+            return PlanResult::Cancelled;
+        }
+
+        let run_entry = match condition_met(entry, previous, failed_so_far) {
+            Ok(met) => met,
+            Err(e) => {
+                let _ = log_instance(instance_id, LogSource::Backend, LogLevel::WARN, format!("无法判断 {} 的执行条件: {}", entry.config, e));
+                true
+            }
+        };
+        if !run_entry {
+            update_report(instance_id, |report| {
+                report.entries[index].status = EntryStatus::Skipped;
+                report.entries[index].message = Some(format!("条件不满足: {:?}", entry.condition));
+            });
+            previous = Some(EntryStatus::Skipped);
+            continue;
+        }
+
+        let status = run_entry_with_retries(instance_id, index, entry);
+        if cancel_requested(instance_id) {
+            return PlanResult::Cancelled;
+        }
+        match status {
+            EntryStatus::Failed if entry.on_failure == FailurePolicy::Abort => {
+                update_report(instance_id, |report| report.entries[index].status = EntryStatus::Failed);
+                return PlanResult::Aborted;
+            }
+            EntryStatus::Failed if entry.on_failure == FailurePolicy::Skip => {
+                update_report(instance_id, |report| report.entries[index].status = EntryStatus::Skipped);
+                previous = Some(EntryStatus::Skipped);
+            }
+            status => {
+                update_report(instance_id, |report| report.entries[index].status = status);
+                failed_so_far |= status == EntryStatus::Failed;
+                previous = Some(status);
+            }
+        }
+    }
+
+    if failed_so_far {
+        PlanResult::Failed
+    } else {
+        PlanResult::Succeeded
+    }
+}
+
+// 执行一项，出错时重试，返回成功或失败
+fn run_entry_with_retries(instance_id: &str, index: usize, entry: &PlanEntry) -> EntryStatus {
+    for attempt in 1..=entry.retries + 1 {
+        update_report(instance_id, |report| {
+            report.current = Some(index);
+            report.entries[index].status = EntryStatus::Running;
+            report.entries[index].attempts = attempt;
+        });
+
+        let result = ensure_idle(instance_id)
+            .and_then(|_| process::run_task_and_wait(instance_id, Some(entry.config.clone())));
+        let message = match result {
+            Ok(run) => {
+                update_report(instance_id, |report| report.entries[index].run_ids.push(run.run_id.clone()));
+                match run.outcome {
+                    RunOutcome::Done => {
+                        update_report(instance_id, |report| report.entries[index].message = None);
+                        return EntryStatus::Succeeded;
+                    }
+                    // 用户停止的任务不重试
+                    RunOutcome::Stopped => {
+                        update_report(instance_id, |report| report.entries[index].message = Some("任务被停止".to_string()));
+                        return EntryStatus::Failed;
+                    }
+                    outcome => format!("任务结果: {:?}", outcome),
+                }
+            }
+            Err(e) => e,
+        };
+
+        if cancel_requested(instance_id) {
+            return EntryStatus::Failed;
+        }
+        let retrying = attempt <= entry.retries;
+        let _ = log_instance(
+            instance_id,
+            LogSource::Backend,
+            LogLevel::WARN,
+            format!(
+                "配置 {} 第 {} 次执行失败（{}）{}",
+                entry.config,
+                attempt,
+                message,
+                if retrying { "，正在重试" } else { "" }
+            ),
+        );
+        update_report(instance_id, |report| report.entries[index].message = Some(message));
+    }
+    EntryStatus::Failed
+}
+
+// 等待实例空闲：崩溃后先等待看门狗重启，未运行时启动
+fn ensure_idle(instance_id: &str) -> Result<(), String> {
+    let status = || process::get_instance_status(instance_id).unwrap_or_default();
+    let idle = || status() == SraStatus::Idle.as_str();
+    if idle() {
+        return Ok(());
+    }
+    let current = status();
+    let may_recover = current != SraStatus::NotRunning.as_str() && current != SraStatus::Error.as_str();
+    if may_recover && process::wait_until(READY_TIMEOUT, idle) {
+        return Ok(());
+    }
+    let current = status();
+    if current == SraStatus::NotRunning.as_str() || current == SraStatus::Error.as_str() || current == SraStatus::Crashed.as_str() {
+        process::start_sra_process_command(instance_id, process::current_launch(instance_id))?;
+    }
+    if process::wait_until(READY_TIMEOUT, idle) {
+        Ok(())
+    } else {
+        Err(format!("实例未就绪（{}）", status()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_backend::tests::setup;
+    use crate::types::DEFAULT_INSTANCE;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_conditions() {
+        let entry = |condition| PlanEntry { config: "A".to_string(), condition, ..Default::default() };
+        let met = |condition, previous, failed| condition_met(&entry(condition), previous, failed).unwrap();
+
+        assert!(met(EntryCondition::PreviousSucceeded, None, false));
+        assert!(met(EntryCondition::PreviousSucceeded, Some(EntryStatus::Succeeded), true));
+        assert!(!met(EntryCondition::PreviousSucceeded, Some(EntryStatus::Skipped), false));
+        assert!(met(EntryCondition::PreviousFailed, Some(EntryStatus::Failed), true));
+        assert!(!met(EntryCondition::PreviousFailed, None, false));
+        assert!(!met(EntryCondition::AllSucceeded, Some(EntryStatus::Succeeded), true));
+        assert!(met(EntryCondition::Always, Some(EntryStatus::Failed), true));
+    }
+
+    #[test]
+    fn test_run_plan_retries_and_applies_policies() {
+        let (_guard, mock) = setup("\
+prompt
+expect: task run A
+out: [Start] A
+exit: 1
+---
+prompt
+expect: task run A
+out: [Start] A
+out: [Done] A
+prompt
+expect: task run B
+out: [Start] B
+exit: 1
+---
+prompt
+expect: task run D
+out: [Start] D
+out: [Done] D
+prompt
+");
+        let progress = Arc::new(AtomicUsize::new(0));
+        let counter = progress.clone();
+        let sub = events::subscribe(move |event, _| {
+            if event == "plan-progress" {
+                counter.fetch_add(1, Ordering::SeqCst);
+            }
+        });
+
+        let entry = |config: &str, retries, condition| PlanEntry {
+            config: config.to_string(),
+            retries,
+            condition,
+            ..Default::default()
+        };
+        let plan = RunPlan {
+            name: "多账号".to_string(),
+            entries: vec![
+                entry("A", 1, EntryCondition::Always),
+                entry("B", 0, EntryCondition::Always),
+                entry("C", 0, EntryCondition::PreviousSucceeded),
+                entry("D", 0, EntryCondition::Always),
+            ],
+            ..Default::default()
+        };
+        let report = execute_plan(plan).unwrap();
+        events::unsubscribe(sub);
+
+        let statuses: Vec<EntryStatus> = report.entries.iter().map(|e| e.status).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                EntryStatus::Succeeded,
+                EntryStatus::Failed,
+                EntryStatus::Skipped,
+                EntryStatus::Succeeded,
+            ]
+        );
+        assert_eq!(report.entries[0].attempts, 2);
+        assert_eq!(report.result, PlanResult::Failed);
+        assert_eq!(mock.spawn_count(), 3);
+        assert!(progress.load(Ordering::SeqCst) > 4);
+        assert!(!is_running(DEFAULT_INSTANCE));
+        process::stop_sra_process_command(DEFAULT_INSTANCE).unwrap();
+    }
+}
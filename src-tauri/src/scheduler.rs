@@ -0,0 +1,486 @@
+// 计划任务
+// 按 cron 规则定时执行单个配置或整个任务顺序（task-order.json），规则保存到 SRA-CE-Schedules.json；
+// 程序未运行期间错过的计划按补跑策略处理，实例正在执行任务时跳过本次运行
+
+use crate::cron::CronExpr;
+use crate::events;
+use crate::logger::{self, LogLevel, LogSource};
+use crate::process;
+use crate::run_plan::{self, EntryCondition, PlanEntry, RunPlan};
+use crate::types::SraStatus;
+use chrono::{DateTime, Duration as ChronoDuration, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+// 检查间隔
+const TICK_INTERVAL: Duration = Duration::from_secs(5);
+
+// 超过计划时间多久视为错过（程序休眠、未运行），此后按补跑策略处理
+const MISSED_GRACE_SECS: i64 = 120;
+
+// 提前多久发送即将运行的通知
+const UPCOMING_LEAD_SECS: i64 = 300;
+
+// 计划执行的内容
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum ScheduleTarget {
+    #[default]
+    Config,    // 执行 Config 指定的配置
+    TaskOrder, // 按 task-order.json 的顺序依次执行所有配置
+}
+
+// 错过计划时的补跑策略
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum CatchUpPolicy {
+    Skip, // 跳过错过的运行，等待下一次
+    #[default]
+    RunOnce, // 在补跑时限内补跑一次（多次错过也只补跑一次）
+}
+
+// 计划规则
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct ScheduleRule {
+    pub id: String,
+    pub name: String,
+    pub enabled: bool,
+    pub cron: String,                 // 分 时 日 月 周（本地时间）
+    pub target: ScheduleTarget,
+    pub config: Option<String>,       // Target 为 Config 时的配置名
+    pub instance_id: Option<String>,  // 运行的实例，为空时使用默认实例
+    pub catch_up: CatchUpPolicy,
+    pub catch_up_window_minutes: u32, // 错过超过该时间则不再补跑
+    pub skip_done_today: bool,        // 跳过当前游戏日已完成的配置
+    pub last_fired: Option<String>,   // 上次触发（或处理错过运行）的时间，ISO 8601 格式
+}
+
+impl Default for ScheduleRule {
+    fn default() -> Self {
+        Self {
+            id: String::new(),
+            name: String::new(),
+            enabled: true,
+            cron: "0 5 * * *".to_string(),
+            target: ScheduleTarget::Config,
+            config: None,
+            instance_id: None,
+            catch_up: CatchUpPolicy::RunOnce,
+            catch_up_window_minutes: 720,
+            skip_done_today: false,
+            last_fired: None,
+        }
+    }
+}
+
+impl ScheduleRule {
+    fn instance(&self) -> String {
+        process::instance_key(self.instance_id.clone())
+    }
+
+    fn last_fired_time(&self) -> Option<DateTime<Local>> {
+        self.last_fired
+            .as_deref()
+            .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+            .map(|t| t.with_timezone(&Local))
+    }
+}
+
+// 即将运行的计划
+#[derive(Debug, Clone, Serialize)]
+pub struct UpcomingRun {
+    pub rule_id: String,
+    pub name: String,
+    pub time: String, // ISO 8601 格式
+    pub target: ScheduleTarget,
+    pub config: Option<String>,
+    pub instance_id: String,
+}
+
+// 计划触发/跳过事件
+#[derive(Debug, Clone, Serialize)]
+pub struct ScheduleEvent {
+    pub rule_id: String,
+    pub name: String,
+    pub instance_id: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "PascalCase", default)]
+struct ScheduleStore {
+    rules: Vec<ScheduleRule>,
+}
+
+// 规则文件读写锁
+static STORE_LOCK: Mutex<()> = Mutex::new(());
+
+// 已发送即将运行通知的计划（规则 ID -> 运行时间）
+static ANNOUNCED: Mutex<BTreeMap<String, String>> = Mutex::new(BTreeMap::new());
+
+static SCHEDULER_STARTED: AtomicBool = AtomicBool::new(false);
+
+// 规则文件路径
+fn schedules_file() -> Result<PathBuf, String> {
+    Ok(crate::paths::data_dir()?.join("SRA-CE-Schedules.json"))
+}
+
+fn load_store() -> Result<ScheduleStore, String> {
+    let path = schedules_file()?;
+    if !path.exists() {
+        return Ok(ScheduleStore::default());
+    }
+    let content = fs::read_to_string(&path).map_err(|e| format!("Failed to read schedules file: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse schedules: {}", e))
+}
+
+fn save_store(store: &ScheduleStore) -> Result<(), String> {
+    let path = schedules_file()?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create SRA directory: {}", e))?;
+    }
+    let content = serde_json::to_string_pretty(store).map_err(|e| format!("Failed to serialize schedules: {}", e))?;
+    fs::write(&path, content).map_err(|e| format!("Failed to write schedules file: {}", e))
+}
+
+// 获取所有计划规则
+pub fn get_schedules() -> Result<Vec<ScheduleRule>, String> {
+    let _guard = STORE_LOCK.lock().map_err(|e| format!("Lock error: {}", e))?;
+    Ok(load_store()?.rules)
+}
+
+// 新增或更新计划规则（ID 为空时新建），返回保存后的规则
+pub fn save_schedule(mut rule: ScheduleRule) -> Result<ScheduleRule, String> {
+    rule.name = rule.name.trim().to_string();
+    CronExpr::parse(&rule.cron)?;
+    if rule.target == ScheduleTarget::Config && rule.config.as_deref().is_none_or(|c| c.trim().is_empty()) {
+        return Err("计划需要指定要执行的配置".to_string());
+    }
+
+    let _guard = STORE_LOCK.lock().map_err(|e| format!("Lock error: {}", e))?;
+    let mut store = load_store()?;
+    let now = Local::now();
+    match store.rules.iter_mut().find(|r| !rule.id.is_empty() && r.id == rule.id) {
+        Some(existing) => {
+            // 修改了时间规则时从现在开始计算，避免按旧规则补跑
+            rule.last_fired = if existing.cron == rule.cron { existing.last_fired.clone() } else { Some(now.to_rfc3339()) };
+            *existing = rule.clone();
+        }
+        None => {
+            if rule.id.is_empty() {
+                rule.id = format!("schedule-{}", now.format("%Y%m%d%H%M%S%3f"));
+            }
+            rule.last_fired = Some(now.to_rfc3339());
+            store.rules.push(rule.clone());
+        }
+    }
+    save_store(&store)?;
+    Ok(rule)
+}
+
+// 删除计划规则
+pub fn delete_schedule(id: &str) -> Result<(), String> {
+    let _guard = STORE_LOCK.lock().map_err(|e| format!("Lock error: {}", e))?;
+    let mut store = load_store()?;
+    let before = store.rules.len();
+    store.rules.retain(|r| r.id != id);
+    if store.rules.len() == before {
+        return Err(format!("计划不存在: {}", id));
+    }
+    save_store(&store)
+}
+
+// 列出接下来的运行（按时间排序）
+pub fn get_upcoming_runs(limit: usize) -> Result<Vec<UpcomingRun>, String> {
+    let now = Local::now();
+    let mut upcoming = Vec::new();
+    for rule in get_schedules()?.into_iter().filter(|r| r.enabled) {
+        let cron = match CronExpr::parse(&rule.cron) {
+            Ok(cron) => cron,
+            Err(_) => continue,
+        };
+        let mut after = now;
+        for _ in 0..limit {
+            match cron.next_after(after) {
+                Some(time) => {
+                    upcoming.push(upcoming_run(&rule, time));
+                    after = time;
+                }
+                None => break,
+            }
+        }
+    }
+    upcoming.sort_by(|a, b| a.time.cmp(&b.time));
+    upcoming.truncate(limit);
+    Ok(upcoming)
+}
+
+fn upcoming_run(rule: &ScheduleRule, time: DateTime<Local>) -> UpcomingRun {
+    UpcomingRun {
+        rule_id: rule.id.clone(),
+        name: rule.name.clone(),
+        time: time.to_rfc3339(),
+        target: rule.target,
+        config: rule.config.clone(),
+        instance_id: rule.instance(),
+    }
+}
+
+// 对某条规则在当前时间应执行的操作
+#[derive(Debug, PartialEq)]
+enum Decision {
+    Wait,        // 尚未到时间
+    Fire,        // 执行
+    SkipMissed,  // 错过且不补跑，只更新触发时间
+    Initialize,  // 没有触发记录，从现在开始计算
+}
+
+fn decide(rule: &ScheduleRule, cron: &CronExpr, now: DateTime<Local>) -> Decision {
+    let last = match rule.last_fired_time() {
+        Some(last) => last,
+        None => return Decision::Initialize,
+    };
+    let due = match cron.next_after(last) {
+        Some(due) if due <= now => due,
+        _ => return Decision::Wait,
+    };
+    let late = now - due;
+    if late <= ChronoDuration::seconds(MISSED_GRACE_SECS) {
+        return Decision::Fire;
+    }
+    match rule.catch_up {
+        CatchUpPolicy::RunOnce if late <= ChronoDuration::minutes(rule.catch_up_window_minutes as i64) => Decision::Fire,
+        _ => Decision::SkipMissed,
+    }
+}
+
+// 检查所有规则，触发到期的计划（由调度线程定时调用）
+pub fn tick(now: DateTime<Local>) -> Result<(), String> {
+    let mut fired = Vec::new();
+    {
+        let _guard = STORE_LOCK.lock().map_err(|e| format!("Lock error: {}", e))?;
+        let mut store = load_store()?;
+        let mut changed = false;
+        for rule in store.rules.iter_mut().filter(|r| r.enabled) {
+            let cron = match CronExpr::parse(&rule.cron) {
+                Ok(cron) => cron,
+                Err(_) => continue,
+            };
+            match decide(rule, &cron, now) {
+                Decision::Wait => announce_upcoming(rule, &cron, now),
+                Decision::Initialize => {
+                    rule.last_fired = Some(now.to_rfc3339());
+                    changed = true;
+                }
+                Decision::SkipMissed => {
+                    let _ = logger::log(
+                        LogSource::Backend,
+                        LogLevel::WARN,
+                        format!("计划「{}」错过了运行时间，按补跑策略跳过", rule.name),
+                    );
+                    rule.last_fired = Some(now.to_rfc3339());
+                    changed = true;
+                }
+                Decision::Fire => {
+                    rule.last_fired = Some(now.to_rfc3339());
+                    changed = true;
+                    fired.push(rule.clone());
+                }
+            }
+        }
+        if changed {
+            save_store(&store)?;
+        }
+    }
+
+    for rule in fired {
+        thread::spawn(move || run_rule(&rule, "计划时间到达"));
+    }
+    Ok(())
+}
+
+// 即将运行时通知一次
+fn announce_upcoming(rule: &ScheduleRule, cron: &CronExpr, now: DateTime<Local>) {
+    let next = match cron.next_after(now) {
+        Some(next) if next - now <= ChronoDuration::seconds(UPCOMING_LEAD_SECS) => next,
+        _ => return,
+    };
+    let time = next.to_rfc3339();
+    if let Ok(mut announced) = ANNOUNCED.lock() {
+        if announced.get(&rule.id) == Some(&time) {
+            return;
+        }
+        announced.insert(rule.id.clone(), time);
+    }
+    events::emit("schedule-upcoming", upcoming_run(rule, next));
+}
+
+// 立即执行一条计划（不影响其计划时间）
+pub fn run_schedule_now(id: &str) -> Result<(), String> {
+    let rule = get_schedules()?
+        .into_iter()
+        .find(|r| r.id == id)
+        .ok_or_else(|| format!("计划不存在: {}", id))?;
+    thread::spawn(move || run_rule(&rule, "手动执行"));
+    Ok(())
+}
+
+fn emit_schedule_event(event: &str, rule: &ScheduleRule, instance_id: &str, reason: String) {
+    events::emit(event, ScheduleEvent {
+        rule_id: rule.id.clone(),
+        name: rule.name.clone(),
+        instance_id: instance_id.to_string(),
+        reason,
+    });
+}
+
+// 执行计划：实例空闲时按运行计划依次执行配置，正在执行任务时跳过
+fn run_rule(rule: &ScheduleRule, trigger: &str) {
+    let instance_id = rule.instance();
+
+    let status = process::get_instance_status(&instance_id).unwrap_or_default();
+    let busy = run_plan::is_running(&instance_id)
+        || [SraStatus::TaskRunning, SraStatus::Starting, SraStatus::Stopping]
+            .iter()
+            .any(|s| s.as_str() == status);
+    let plan = if busy { Err("实例正在执行任务".to_string()) } else { build_plan(rule, &instance_id) };
+    let plan = match plan {
+        Ok(plan) => plan,
+        Err(reason) => {
+            let _ = logger::log_instance(
+                &instance_id,
+                LogSource::Backend,
+                LogLevel::WARN,
+                format!("计划「{}」已跳过: {}", rule.name, reason),
+            );
+            emit_schedule_event("schedule-skipped", rule, &instance_id, reason);
+            return;
+        }
+    };
+
+    let _ = logger::log_instance(&instance_id, LogSource::Backend, LogLevel::INFO, format!("开始执行计划「{}」（{}）", rule.name, trigger));
+    emit_schedule_event("schedule-fired", rule, &instance_id, trigger.to_string());
+
+    if let Err(e) = run_plan::execute_plan(plan) {
+        let _ = logger::log_instance(&instance_id, LogSource::Backend, LogLevel::ERR, format!("计划「{}」执行失败: {}", rule.name, e));
+    }
+}
+
+// 根据规则生成运行计划；整个任务顺序完成后执行配置中的完成后操作（关机、睡眠等）
+fn build_plan(rule: &ScheduleRule, instance_id: &str) -> Result<RunPlan, String> {
+    let mut plan = match rule.target {
+        ScheduleTarget::Config => RunPlan {
+            entries: vec![PlanEntry { config: rule.config.clone().unwrap_or_default(), ..Default::default() }],
+            ..Default::default()
+        },
+        ScheduleTarget::TaskOrder => RunPlan::from_task_order(None)?,
+    };
+    if plan.entries.is_empty() {
+        return Err("任务顺序为空".to_string());
+    }
+    plan.name = rule.name.clone();
+    plan.instance_id = Some(instance_id.to_string());
+    if rule.skip_done_today {
+        for entry in plan.entries.iter_mut() {
+            entry.condition = EntryCondition::NotDoneToday;
+        }
+    }
+    Ok(plan)
+}
+
+// 启动调度线程（只启动一次）
+pub fn start_scheduler() {
+    if SCHEDULER_STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    thread::spawn(|| loop {
+        if let Err(e) = tick(Local::now()) {
+            let _ = logger::log(LogSource::Backend, LogLevel::ERR, format!("计划任务检查失败: {}", e));
+        }
+        thread::sleep(TICK_INTERVAL);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_backend::tests::setup;
+    use crate::run_history;
+    use crate::types::DEFAULT_INSTANCE;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::Arc;
+
+    fn rule_with_last(cron: &str, last: DateTime<Local>, catch_up: CatchUpPolicy) -> ScheduleRule {
+        ScheduleRule {
+            cron: cron.to_string(),
+            catch_up,
+            catch_up_window_minutes: 60,
+            last_fired: Some(last.to_rfc3339()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_decide_catch_up_policy() {
+        let now = Local::now();
+        let cron = CronExpr::parse("* * * * *").unwrap();
+
+        assert_eq!(decide(&ScheduleRule::default(), &cron, now), Decision::Initialize);
+        assert_eq!(decide(&rule_with_last("* * * * *", now, CatchUpPolicy::RunOnce), &cron, now), Decision::Wait);
+        let recent = now - ChronoDuration::seconds(90);
+        assert_eq!(decide(&rule_with_last("* * * * *", recent, CatchUpPolicy::Skip), &cron, now), Decision::Fire);
+
+        // 错过 30 分钟：补跑策略决定是否执行
+        let missed = now - ChronoDuration::minutes(31);
+        assert_eq!(decide(&rule_with_last("* * * * *", missed, CatchUpPolicy::RunOnce), &cron, now), Decision::Fire);
+        assert_eq!(decide(&rule_with_last("* * * * *", missed, CatchUpPolicy::Skip), &cron, now), Decision::SkipMissed);
+
+        // 超过补跑时限
+        let long_ago = now - ChronoDuration::hours(3);
+        assert_eq!(decide(&rule_with_last("* * * * *", long_ago, CatchUpPolicy::RunOnce), &cron, now), Decision::SkipMissed);
+    }
+
+    #[test]
+    fn test_due_schedule_runs_config_and_skips_when_busy() {
+        let (_guard, mock) = setup("prompt\nexpect: task run Default\nout: [Start] Default\nsleep: 300\nout: [Done] Default\nprompt\n");
+        let skipped = Arc::new(AtomicUsize::new(0));
+        let counter = skipped.clone();
+        let sub = events::subscribe(move |event, _| {
+            if event == "schedule-skipped" {
+                counter.fetch_add(1, Ordering::SeqCst);
+            }
+        });
+
+        let rule = save_schedule(ScheduleRule {
+            name: "日常".to_string(),
+            cron: "* * * * *".to_string(),
+            config: Some("Default".to_string()),
+            ..Default::default()
+        })
+        .unwrap();
+
+        // 刚保存的规则在下一分钟之前不会触发
+        tick(chrono::Local::now()).unwrap();
+        assert_eq!(mock.spawn_count(), 0);
+
+        // 一分钟后到期：启动实例并执行配置
+        tick(chrono::Local::now() + chrono::Duration::seconds(61)).unwrap();
+        assert!(process::wait_until(Duration::from_millis(3000), || process::get_instance_status(DEFAULT_INSTANCE).unwrap() == "task-running"));
+
+        // 正在执行任务时再次触发会被跳过
+        run_schedule_now(&rule.id).unwrap();
+        assert!(process::wait_until(Duration::from_millis(2000), || skipped.load(Ordering::SeqCst) == 1));
+
+        assert!(process::wait_until(Duration::from_millis(3000), || !run_history::query_runs(&run_history::RunQuery::default()).unwrap().is_empty()));
+        events::unsubscribe(sub);
+        let runs = run_history::query_runs(&run_history::RunQuery::default()).unwrap();
+        assert_eq!(runs[0].outcome, run_history::RunOutcome::Done);
+        assert_eq!(mock.received_commands(), vec!["task run Default"]);
+        process::stop_sra_process_command(DEFAULT_INSTANCE).unwrap();
+    }
+}
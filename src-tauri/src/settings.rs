@@ -1,7 +1,12 @@
 // 应用设置管理模块
 
+use crate::line_codec::{CarriageReturnMode, OutputEncoding};
+use crate::logger::{self, LogLevel, LogSource};
+use crate::reset_clock::ServerRegion;
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
@@ -104,6 +109,273 @@ pub struct CESettings {
     pub download_region: String,
     #[serde(default)]
     pub subscription: Option<Subscription>,
+    #[serde(default)]
+    pub sra_cli_path: Option<String>,
+    #[serde(default)]
+    pub watchdog: WatchdogSettings,
+    #[serde(default)]
+    pub stop_policy: StopPolicy,
+    #[serde(default)]
+    pub launch_profiles: Vec<LaunchProfile>,
+    #[serde(default)]
+    pub default_launch_profile: Option<String>, // 未指定启动配置时使用（含程序启动时的自动启动）
+    #[serde(default)]
+    pub transcript: TranscriptSettings,
+    #[serde(default)]
+    pub task_timeout: TaskTimeoutSettings,
+    #[serde(default)]
+    pub post_actions: PostActionSettings,
+    #[serde(default)]
+    pub server_region: Option<ServerRegion>, // 区服（刷新时间），为空时由配置的启动渠道推断
+    #[serde(default)]
+    pub remote_api: RemoteApiSettings,
+    #[serde(default)]
+    pub log_retention: LogRetentionSettings,
+    #[serde(default = "default_ipc_enabled")]
+    pub ipc_enabled: bool, // 本地 IPC 接口（命名管道 / Unix 域套接字），默认关闭，修改后重启程序生效
+    #[serde(default)]
+    pub log_levels: LogLevelSettings,
+    #[serde(default)]
+    pub redaction: RedactionSettings,
+    #[serde(default = "default_log_buffer_capacity")]
+    pub log_buffer_capacity: usize, // 内存中保留的最近日志条数，修改后立即生效
+}
+
+// 内存中保留的最近日志条数（默认值和允许的范围）
+pub const DEFAULT_LOG_BUFFER_CAPACITY: usize = 1000;
+const LOG_BUFFER_CAPACITY_RANGE: std::ops::RangeInclusive<usize> = 100..=100_000;
+
+// 日志脱敏规则（正则表达式，含捕获组时只替换第一个捕获组），修改后立即生效
+// 配置和设置中的账号密码、授权码等已知敏感值总是会被替换，不需要在此配置
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct RedactionSettings {
+    pub patterns: Vec<String>,
+}
+
+impl Default for RedactionSettings {
+    fn default() -> Self {
+        Self {
+            // 形如 password=xxx、"token": "xxx"、cdk: xxx 的内容
+            patterns: vec![
+                r#"(?i)\b(?:password|passwd|pwd|token|secret|cdk|auth_?code)"?\s*[:=]\s*"?([^\s"',;]+)"#.to_string(),
+            ],
+        }
+    }
+}
+
+// 日志的最低级别：先按来源过滤，再按输出目标过滤，修改后立即生效
+// 级别从低到高: TRACE < DEBUG < INFO/SUCCESS/MSG < WARN < ERR
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct LogLevelSettings {
+    pub backend: LogLevel,
+    pub process: LogLevel,
+    pub frontend: LogLevel,
+    pub file: LogLevel,   // 文本日志文件
+    pub jsonl: LogLevel,  // 结构化日志文件
+    pub event: LogLevel,  // 发送到前端和远程接口的 log-message 事件
+    pub buffer: LogLevel, // 内存中的最近日志（get_all_logs、get_logs_since）
+}
+
+impl Default for LogLevelSettings {
+    fn default() -> Self {
+        // 默认不过滤，与之前的输出保持一致
+        Self {
+            backend: LogLevel::TRACE,
+            process: LogLevel::TRACE,
+            frontend: LogLevel::TRACE,
+            file: LogLevel::TRACE,
+            jsonl: LogLevel::TRACE,
+            event: LogLevel::TRACE,
+            buffer: LogLevel::TRACE,
+        }
+    }
+}
+
+// 日志轮转与清理（0 表示不限制），修改后下次启动生效
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct LogRetentionSettings {
+    pub max_file_size_mb: u64,  // 单个日志文件的大小上限，超过后切换到新文件
+    pub max_files: usize,       // 保留的日志会话数（同一次启动的文本日志、结构化日志及其轮转文件算一个）
+    pub max_age_days: u32,      // 保留天数
+    pub max_total_size_mb: u64, // 日志目录的总大小上限
+    pub compress_rotated: bool, // gzip 压缩切换下来的文件和之前的日志
+}
+
+impl Default for LogRetentionSettings {
+    fn default() -> Self {
+        Self {
+            max_file_size_mb: 10,
+            max_files: 30,
+            max_age_days: 30,
+            max_total_size_mb: 500,
+            compress_rotated: false,
+        }
+    }
+}
+
+// 局域网远程控制接口（HTTP + WebSocket），默认关闭
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct RemoteApiSettings {
+    pub enabled: bool,
+    pub bind_address: String, // 监听地址，局域网访问需改为 0.0.0.0:端口
+    pub token: String,        // 访问令牌（已加密）
+}
+
+impl Default for RemoteApiSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bind_address: "127.0.0.1:8765".to_string(),
+            token: String::new(),
+        }
+    }
+}
+
+// SRA-cli 崩溃自动重启策略
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct WatchdogSettings {
+    pub enabled: bool,
+    pub max_crashes: u32,      // 时间窗口内允许的最大崩溃次数，超过后放弃重启
+    pub window_secs: u64,      // 崩溃计数的时间窗口（秒）
+    pub base_delay_secs: u64,  // 首次重启前的等待时间，之后每次翻倍
+    pub max_delay_secs: u64,   // 重启等待时间上限
+}
+
+impl Default for WatchdogSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_crashes: 5,
+            window_secs: 1800,
+            base_delay_secs: 2,
+            max_delay_secs: 60,
+        }
+    }
+}
+
+// SRA-cli 停止流程各步骤的超时时间
+// 任务执行中先发送 task stop，再发送 exit，最后强制结束进程树
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct StopPolicy {
+    pub task_stop_timeout_ms: u64, // 等待任务停止（[Done]）的时间，0 表示跳过 task stop
+    pub exit_timeout_ms: u64,      // 发送 exit 后等待进程退出的时间
+    pub kill_timeout_ms: u64,      // 强制结束后等待进程退出的时间
+}
+
+impl Default for StopPolicy {
+    fn default() -> Self {
+        Self {
+            task_stop_timeout_ms: 5000,
+            exit_timeout_ms: 3000,
+            kill_timeout_ms: 2000,
+        }
+    }
+}
+
+// 任务超时与卡死检测（0 表示不限制）
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct TaskTimeoutSettings {
+    pub max_duration_minutes: u32,                       // 默认的最长运行时间
+    pub config_max_duration_minutes: BTreeMap<String, u32>, // 按配置单独设置的最长运行时间
+    pub hang_timeout_minutes: u32,                       // 无任何输出超过该时间视为卡死
+}
+
+impl Default for TaskTimeoutSettings {
+    fn default() -> Self {
+        Self {
+            max_duration_minutes: 0,
+            config_max_duration_minutes: BTreeMap::new(),
+            hang_timeout_minutes: 20,
+        }
+    }
+}
+
+impl TaskTimeoutSettings {
+    // 配置的最长运行时间（分钟），0 表示不限制
+    pub fn max_duration_for(&self, config: Option<&str>) -> u32 {
+        config
+            .and_then(|c| self.config_max_duration_minutes.get(c))
+            .copied()
+            .unwrap_or(self.max_duration_minutes)
+    }
+}
+
+// 任务完成后操作（关机、睡眠等）的执行方式
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct PostActionSettings {
+    pub countdown_secs: u64, // 执行前的倒计时，期间可以取消
+    pub dry_run: bool,       // 演练模式：只记录将要执行的操作
+}
+
+impl Default for PostActionSettings {
+    fn default() -> Self {
+        Self {
+            countdown_secs: 60,
+            dry_run: false,
+        }
+    }
+}
+
+// 会话记录设置（用于反馈问题时附带 SRA-cli 的原始输出）
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct TranscriptSettings {
+    pub enabled: bool,
+    pub max_files: usize, // 最多保留的会话记录数量
+}
+
+impl Default for TranscriptSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_files: 20,
+        }
+    }
+}
+
+// SRA-cli 启动配置（如「调试」「日常」），启动时可按名称选择
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct LaunchProfile {
+    pub name: String,
+    pub arguments: String,                 // 附加参数，支持引号
+    pub env: BTreeMap<String, String>,     // 额外的环境变量
+    pub working_dir: Option<String>,       // 工作目录，相对路径基于 SRA-cli 所在目录
+    pub python_unbuffered: bool,           // 设置 PYTHONUNBUFFERED=1
+    pub python_io_encoding: Option<String>, // PYTHONIOENCODING 的值，为空则不设置
+    pub output_encoding: OutputEncoding,   // 输出编码（Auto 自动识别 UTF-8 / GBK）
+    pub carriage_return: CarriageReturnMode, // \r 进度刷新的处理方式
+}
+
+impl Default for LaunchProfile {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            arguments: String::new(),
+            env: BTreeMap::new(),
+            working_dir: None,
+            python_unbuffered: true,
+            python_io_encoding: Some("utf-8".to_string()),
+            output_encoding: OutputEncoding::Auto,
+            carriage_return: CarriageReturnMode::Overwrite,
+        }
+    }
+}
+
+fn default_ipc_enabled() -> bool {
+    false
+}
+
+fn default_log_buffer_capacity() -> usize {
+    DEFAULT_LOG_BUFFER_CAPACITY
 }
 
 fn default_download_region() -> String {
@@ -117,6 +389,21 @@ impl Default for CESettings {
             skip_desktop_shortcut_prompt: false,
             download_region: "china".to_string(),
             subscription: None,
+            sra_cli_path: None,
+            watchdog: WatchdogSettings::default(),
+            stop_policy: StopPolicy::default(),
+            launch_profiles: Vec::new(),
+            default_launch_profile: None,
+            transcript: TranscriptSettings::default(),
+            task_timeout: TaskTimeoutSettings::default(),
+            post_actions: PostActionSettings::default(),
+            server_region: None,
+            remote_api: RemoteApiSettings::default(),
+            log_retention: LogRetentionSettings::default(),
+            ipc_enabled: false,
+            log_levels: LogLevelSettings::default(),
+            redaction: RedactionSettings::default(),
+            log_buffer_capacity: DEFAULT_LOG_BUFFER_CAPACITY,
         }
     }
 }
@@ -190,14 +477,60 @@ impl Default for AppSettings {
 
 // 获取主设置文件路径（settings.json）
 fn get_main_settings_file() -> Result<PathBuf, String> {
-    let appdata = std::env::var("APPDATA").map_err(|_| "Failed to get APPDATA path")?;
-    Ok(PathBuf::from(appdata).join("SRA").join("settings.json"))
+    Ok(crate::paths::data_dir()?.join("settings.json"))
 }
 
 // 获取 CE 设置文件路径（SRA-CE-Settings.json）
 fn get_ce_settings_file() -> Result<PathBuf, String> {
-    let appdata = std::env::var("APPDATA").map_err(|_| "Failed to get APPDATA path")?;
-    Ok(PathBuf::from(appdata).join("SRA").join("SRA-CE-Settings.json"))
+    Ok(crate::paths::data_dir()?.join("SRA-CE-Settings.json"))
+}
+
+// 读取 CE 设置（文件不存在时返回默认值，解析失败时返回错误，避免修改后写回时覆盖整个文件）
+pub fn load_ce_settings() -> Result<CESettings, String> {
+    let ce_settings_file = get_ce_settings_file()?;
+    if ce_settings_file.exists() {
+        let content = fs::read_to_string(&ce_settings_file)
+            .map_err(|e| format!("Failed to read CE settings file: {}", e))?;
+        serde_json::from_str(&content).map_err(|e| format!("Failed to parse CE settings file: {}", e))
+    } else {
+        Ok(CESettings::default())
+    }
+}
+
+// 设置文件无法读取的错误是否已记录（恢复正常后重置）
+static LOAD_ERROR_REPORTED: AtomicBool = AtomicBool::new(false);
+
+// 只读取设置时使用：无法读取时记录错误（出错期间只记录一次）并使用默认值，不能用于修改后写回
+pub fn load_ce_settings_or_default() -> CESettings {
+    match load_ce_settings() {
+        Ok(ce_settings) => {
+            LOAD_ERROR_REPORTED.store(false, Ordering::SeqCst);
+            ce_settings
+        }
+        Err(e) => {
+            if !LOAD_ERROR_REPORTED.load(Ordering::SeqCst)
+                && logger::log(LogSource::Backend, LogLevel::ERR, format!("{}，暂时使用默认设置", e)).is_ok()
+            {
+                LOAD_ERROR_REPORTED.store(true, Ordering::SeqCst);
+            }
+            CESettings::default()
+        }
+    }
+}
+
+// 写入 CE 设置
+pub fn save_ce_settings(ce_settings: &CESettings) -> Result<(), String> {
+    let ce_settings_file = get_ce_settings_file()?;
+    if let Some(parent) = ce_settings_file.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create CE settings directory: {}", e))?;
+    }
+    let content = serde_json::to_string_pretty(ce_settings)
+        .map_err(|e| format!("Failed to serialize CE settings: {}", e))?;
+    fs::write(&ce_settings_file, content)
+        .map_err(|e| format!("Failed to write CE settings file: {}", e))?;
+
+    Ok(())
 }
 
 // 加载设置
@@ -221,14 +554,7 @@ pub fn load_settings() -> Result<AppSettings, String> {
     };
 
     // 加载 CE 设置
-    let ce_settings_file = get_ce_settings_file()?;
-    let ce_settings = if ce_settings_file.exists() {
-        let content = fs::read_to_string(&ce_settings_file)
-            .map_err(|e| format!("Failed to read CE settings file: {}", e))?;
-        serde_json::from_str(&content).unwrap_or_else(|_| CESettings::default())
-    } else {
-        CESettings::default()
-    };
+    let ce_settings = load_ce_settings_or_default();
 
     // 解密邮件授权码（如果已加密）
     let email_auth_code = if !main_settings.email_auth_code.is_empty() {
@@ -242,6 +568,8 @@ pub fn load_settings() -> Result<AppSettings, String> {
     } else {
         main_settings.email_auth_code.clone()
     };
+    crate::redact::add_secret(&email_auth_code);
+    crate::redact::add_secret(&main_settings.mirror_chyan_cdk);
 
     // 合并设置 - zoom需要转换为百分比
     Ok(AppSettings {
@@ -272,31 +600,14 @@ pub fn load_settings() -> Result<AppSettings, String> {
 
 // 单独保存下载区域设置
 pub fn save_download_region(region: String) -> Result<(), String> {
-    let ce_settings_file = get_ce_settings_file()?;
-    
     // 读取现有的 CE 设置
-    let mut ce_settings = if ce_settings_file.exists() {
-        let content = fs::read_to_string(&ce_settings_file)
-            .map_err(|e| format!("Failed to read CE settings file: {}", e))?;
-        serde_json::from_str(&content).unwrap_or_else(|_| CESettings::default())
-    } else {
-        CESettings::default()
-    };
+    let mut ce_settings = load_ce_settings()?;
     
     // 更新下载区域
     ce_settings.download_region = region;
     
     // 保存
-    if let Some(parent) = ce_settings_file.parent() {
-        fs::create_dir_all(parent)
-            .map_err(|e| format!("Failed to create CE settings directory: {}", e))?;
-    }
-    let content = serde_json::to_string_pretty(&ce_settings)
-        .map_err(|e| format!("Failed to serialize CE settings: {}", e))?;
-    fs::write(&ce_settings_file, content)
-        .map_err(|e| format!("Failed to write CE settings file: {}", e))?;
-    
-    Ok(())
+    save_ce_settings(&ce_settings)
 }
 
 // 保存设置
@@ -311,6 +622,8 @@ pub fn save_settings(settings: AppSettings) -> Result<(), String> {
         serde_json::json!({})
     };
 
+    crate::redact::add_secret(&settings.email_auth_code);
+
     // 加密邮件授权码（如果不为空）
     let encrypted_auth_code = if !settings.email_auth_code.is_empty() {
         match crate::encryption::encrypt_string(&settings.email_auth_code) {
@@ -389,50 +702,21 @@ pub fn save_settings(settings: AppSettings) -> Result<(), String> {
     fs::write(&main_settings_file, main_content)
         .map_err(|e| format!("Failed to write main settings file: {}", e))?;
 
-    // 读取现有的 CE 设置以保留订阅信息
-    let ce_settings_file = get_ce_settings_file()?;
-    let existing_subscription = if ce_settings_file.exists() {
-        let content = fs::read_to_string(&ce_settings_file)
-            .map_err(|e| format!("Failed to read CE settings file: {}", e))?;
-        let existing: CESettings = serde_json::from_str(&content).unwrap_or_default();
-        existing.subscription
-    } else {
-        None
-    };
-
-    // 分离 CE 设置（保留订阅信息）
-    let ce_settings = CESettings {
-        wallpaper_path: settings.wallpaper_path,
-        skip_desktop_shortcut_prompt: settings.skip_desktop_shortcut_prompt,
-        download_region: settings.download_region,
-        subscription: existing_subscription,
-    };
+    // 读取现有的 CE 设置，只覆盖前端管理的字段（保留订阅信息等其他字段）
+    let mut ce_settings = load_ce_settings()?;
+    ce_settings.wallpaper_path = settings.wallpaper_path;
+    ce_settings.skip_desktop_shortcut_prompt = settings.skip_desktop_shortcut_prompt;
+    ce_settings.download_region = settings.download_region;
 
     // 保存 CE 设置到 SRA-CE-Settings.json
-    if let Some(parent) = ce_settings_file.parent() {
-        fs::create_dir_all(parent)
-            .map_err(|e| format!("Failed to create CE settings directory: {}", e))?;
-    }
-    let ce_content = serde_json::to_string_pretty(&ce_settings)
-        .map_err(|e| format!("Failed to serialize CE settings: {}", e))?;
-    fs::write(&ce_settings_file, ce_content)
-        .map_err(|e| format!("Failed to write CE settings file: {}", e))?;
+    save_ce_settings(&ce_settings)?;
 
     Ok(())
 }
 
 // 获取订阅信息
 pub fn get_subscription() -> Result<Option<Subscription>, String> {
-    let ce_settings_file = get_ce_settings_file()?;
-    
-    if ce_settings_file.exists() {
-        let content = fs::read_to_string(&ce_settings_file)
-            .map_err(|e| format!("Failed to read CE settings file: {}", e))?;
-        let ce_settings: CESettings = serde_json::from_str(&content).unwrap_or_default();
-        Ok(ce_settings.subscription)
-    } else {
-        Ok(None)
-    }
+    Ok(load_ce_settings_or_default().subscription)
 }
 
 // 保存订阅信息
@@ -440,13 +724,7 @@ pub fn save_subscription(sub_type: String, channel: String, version: String) ->
     let ce_settings_file = get_ce_settings_file()?;
     
     // 读取现有的 CE 设置
-    let mut ce_settings = if ce_settings_file.exists() {
-        let content = fs::read_to_string(&ce_settings_file)
-            .map_err(|e| format!("Failed to read CE settings file: {}", e))?;
-        serde_json::from_str(&content).unwrap_or_else(|_| CESettings::default())
-    } else {
-        CESettings::default()
-    };
+    let mut ce_settings = load_ce_settings()?;
     
     // 更新订阅信息
     let sub_info = SubscriptionInfo { channel, version };
@@ -472,3 +750,204 @@ pub fn save_subscription(sub_type: String, channel: String, version: String) ->
     
     Ok(())
 }
+
+// 保存 SRA-cli 路径覆盖（None 表示清除）
+pub fn save_sra_cli_path(path: Option<String>) -> Result<(), String> {
+    let mut ce_settings = load_ce_settings()?;
+    ce_settings.sra_cli_path = path.filter(|p| !p.trim().is_empty());
+    save_ce_settings(&ce_settings)
+}
+
+// 保存崩溃自动重启策略
+pub fn save_watchdog_settings(watchdog: WatchdogSettings) -> Result<(), String> {
+    let mut ce_settings = load_ce_settings()?;
+    ce_settings.watchdog = watchdog;
+    save_ce_settings(&ce_settings)
+}
+
+// 保存停止流程策略
+pub fn save_stop_policy(stop_policy: StopPolicy) -> Result<(), String> {
+    let mut ce_settings = load_ce_settings()?;
+    ce_settings.stop_policy = stop_policy;
+    save_ce_settings(&ce_settings)
+}
+
+// 查找启动配置（name 为空时使用默认启动配置，都未设置则返回内置配置）
+pub fn find_launch_profile(name: Option<&str>) -> Result<LaunchProfile, String> {
+    let ce_settings = load_ce_settings()?;
+    let name = name
+        .filter(|n| !n.trim().is_empty())
+        .map(|n| n.trim().to_string())
+        .or(ce_settings.default_launch_profile.clone());
+    match name {
+        Some(name) => ce_settings
+            .launch_profiles
+            .into_iter()
+            .find(|p| p.name == name)
+            .ok_or_else(|| format!("启动配置不存在: {}", name)),
+        None => Ok(LaunchProfile::default()),
+    }
+}
+
+// 保存启动配置（同名则覆盖）
+pub fn save_launch_profile(profile: LaunchProfile) -> Result<(), String> {
+    let name = profile.name.trim();
+    if name.is_empty() {
+        return Err("启动配置名称不能为空".to_string());
+    }
+    crate::args::split_args(&profile.arguments)?;
+    if let Some(key) = profile.env.keys().find(|k| k.trim().is_empty() || k.contains('=')) {
+        return Err(format!("无效的环境变量名: {}", key));
+    }
+
+    let profile = LaunchProfile { name: name.to_string(), ..profile };
+    let mut ce_settings = load_ce_settings()?;
+    match ce_settings.launch_profiles.iter_mut().find(|p| p.name == profile.name) {
+        Some(existing) => *existing = profile,
+        None => ce_settings.launch_profiles.push(profile),
+    }
+    save_ce_settings(&ce_settings)
+}
+
+// 删除启动配置
+pub fn delete_launch_profile(name: &str) -> Result<(), String> {
+    let mut ce_settings = load_ce_settings()?;
+    ce_settings.launch_profiles.retain(|p| p.name != name);
+    if ce_settings.default_launch_profile.as_deref() == Some(name) {
+        ce_settings.default_launch_profile = None;
+    }
+    save_ce_settings(&ce_settings)
+}
+
+// 设置默认启动配置
+pub fn set_default_launch_profile(name: Option<String>) -> Result<(), String> {
+    let mut ce_settings = load_ce_settings()?;
+    let name = name.filter(|n| !n.trim().is_empty());
+    if let Some(ref name) = name {
+        if !ce_settings.launch_profiles.iter().any(|p| &p.name == name) {
+            return Err(format!("启动配置不存在: {}", name));
+        }
+    }
+    ce_settings.default_launch_profile = name;
+    save_ce_settings(&ce_settings)
+}
+
+// 保存会话记录设置
+pub fn save_transcript_settings(transcript: TranscriptSettings) -> Result<(), String> {
+    let mut ce_settings = load_ce_settings()?;
+    ce_settings.transcript = transcript;
+    save_ce_settings(&ce_settings)
+}
+
+// 保存任务超时设置
+pub fn save_task_timeout_settings(task_timeout: TaskTimeoutSettings) -> Result<(), String> {
+    let mut ce_settings = load_ce_settings()?;
+    ce_settings.task_timeout = task_timeout;
+    save_ce_settings(&ce_settings)
+}
+
+// 保存任务完成后操作的设置
+pub fn save_post_action_settings(post_actions: PostActionSettings) -> Result<(), String> {
+    let mut ce_settings = load_ce_settings()?;
+    ce_settings.post_actions = post_actions;
+    save_ce_settings(&ce_settings)
+}
+
+// 保存区服（None 表示由配置推断）
+pub fn save_server_region(server_region: Option<ServerRegion>) -> Result<(), String> {
+    let mut ce_settings = load_ce_settings()?;
+    ce_settings.server_region = server_region;
+    save_ce_settings(&ce_settings)
+}
+
+// 保存日志轮转与清理设置
+pub fn save_log_retention_settings(log_retention: LogRetentionSettings) -> Result<(), String> {
+    let mut ce_settings = load_ce_settings()?;
+    ce_settings.log_retention = log_retention;
+    save_ce_settings(&ce_settings)
+}
+
+// 保存日志级别设置
+pub fn save_log_level_settings(log_levels: LogLevelSettings) -> Result<(), String> {
+    let mut ce_settings = load_ce_settings()?;
+    ce_settings.log_levels = log_levels;
+    save_ce_settings(&ce_settings)
+}
+
+// 保存日志缓冲区容量
+pub fn save_log_buffer_capacity(capacity: usize) -> Result<(), String> {
+    if !LOG_BUFFER_CAPACITY_RANGE.contains(&capacity) {
+        return Err(format!(
+            "日志缓冲区容量应在 {} 到 {} 之间",
+            LOG_BUFFER_CAPACITY_RANGE.start(),
+            LOG_BUFFER_CAPACITY_RANGE.end()
+        ));
+    }
+    let mut ce_settings = load_ce_settings()?;
+    ce_settings.log_buffer_capacity = capacity;
+    save_ce_settings(&ce_settings)
+}
+
+// 保存日志脱敏规则
+pub fn save_redaction_settings(redaction: RedactionSettings) -> Result<(), String> {
+    crate::redact::compile_patterns(&redaction.patterns)?;
+    let mut ce_settings = load_ce_settings()?;
+    ce_settings.redaction = redaction;
+    save_ce_settings(&ce_settings)
+}
+
+// 保存本地 IPC 接口开关
+pub fn save_ipc_enabled(enabled: bool) -> Result<(), String> {
+    let mut ce_settings = load_ce_settings()?;
+    ce_settings.ipc_enabled = enabled;
+    save_ce_settings(&ce_settings)
+}
+
+// 保存远程控制接口的开关和监听地址（令牌单独设置）
+pub fn save_remote_api_settings(enabled: bool, bind_address: String) -> Result<(), String> {
+    let bind_address = bind_address.trim().to_string();
+    bind_address
+        .parse::<std::net::SocketAddr>()
+        .map_err(|e| format!("无效的监听地址 {}: {}", bind_address, e))?;
+    let mut ce_settings = load_ce_settings()?;
+    ce_settings.remote_api.enabled = enabled;
+    ce_settings.remote_api.bind_address = bind_address;
+    save_ce_settings(&ce_settings)
+}
+
+// 保存远程控制接口的访问令牌（加密存储）
+pub fn save_remote_api_token(token: &str) -> Result<(), String> {
+    crate::redact::add_secret(token);
+    let mut ce_settings = load_ce_settings()?;
+    ce_settings.remote_api.token = crate::encryption::encrypt_string(token)?;
+    save_ce_settings(&ce_settings)
+}
+
+// 读取远程控制接口的访问令牌（未设置时为空）
+pub fn load_remote_api_token() -> Result<String, String> {
+    let token = crate::encryption::decrypt_string(&load_ce_settings()?.remote_api.token)?;
+    crate::redact::add_secret(&token);
+    Ok(token)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_backend::tests::setup;
+
+    #[test]
+    fn test_unparsable_settings_are_never_overwritten() {
+        let (_guard, _mock) = setup("");
+        let file = get_ce_settings_file().unwrap();
+        // 手动修改时写错了类型
+        let content = r#"{ "IpcEnabled": "yes", "SraCliPath": "D:\\SRA\\SRA-cli.exe" }"#;
+        fs::write(&file, content).unwrap();
+
+        assert!(load_ce_settings().unwrap_err().contains("Failed to parse"));
+        assert!(!load_ce_settings_or_default().ipc_enabled);
+        assert!(save_ipc_enabled(true).is_err());
+        assert!(save_download_region("global".to_string()).is_err());
+        assert_eq!(fs::read_to_string(&file).unwrap(), content);
+        let _ = fs::remove_file(&file);
+    }
+}
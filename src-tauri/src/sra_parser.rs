@@ -3,15 +3,15 @@
 
 use crate::logger::LogLevel;
 use regex::Regex;
+use serde::Serialize;
 use std::sync::Mutex;
 
 // 日志解析结果
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct ParsedLog {
     pub level: LogLevel,
     pub message: String,
-    #[allow(dead_code)]
-    pub time: String, // HH:MM:SS 格式（保留用于调试）
+    pub time: String, // HH:MM:SS 格式
 }
 
 // 日志解析器
@@ -219,35 +219,6 @@ impl SraLogParser {
     }
 }
 
-// 全局解析器实例
-static SRA_PARSER: Mutex<Option<SraLogParser>> = Mutex::new(None);
-
-// 初始化解析器
-pub fn init_parser() {
-    let mut guard = SRA_PARSER.lock().unwrap();
-    *guard = Some(SraLogParser::new());
-}
-
-// 解析行
-pub fn parse_line(line: &str) -> Vec<ParsedLog> {
-    let guard = SRA_PARSER.lock().unwrap();
-    if let Some(ref parser) = *guard {
-        parser.parse_line(line)
-    } else {
-        Vec::new()
-    }
-}
-
-// 刷新待处理的行
-pub fn flush_parser() -> Vec<ParsedLog> {
-    let guard = SRA_PARSER.lock().unwrap();
-    if let Some(ref parser) = *guard {
-        parser.flush()
-    } else {
-        Vec::new()
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
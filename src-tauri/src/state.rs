@@ -0,0 +1,137 @@
+// SRA 进程状态机
+// 只允许合法的状态转换，并记录每次转换的时间和原因
+
+use crate::types::SraStatus;
+use serde::Serialize;
+
+// 状态历史最多保留的条数
+const MAX_HISTORY: usize = 200;
+
+// 一次状态转换记录
+#[derive(Debug, Clone, Serialize)]
+pub struct StateTransition {
+    pub from: SraStatus,
+    pub to: SraStatus,
+    pub time: String, // ISO 8601 格式
+    pub reason: String,
+}
+
+pub struct SraStateMachine {
+    current: SraStatus,
+    history: Vec<StateTransition>,
+}
+
+impl Default for SraStateMachine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// 判断状态转换是否合法
+fn is_allowed(from: SraStatus, to: SraStatus) -> bool {
+    use SraStatus::*;
+    matches!(
+        (from, to),
+        (NotRunning, Starting)
+            | (Starting, Idle)
+            | (Starting, Stopping)
+            | (Starting, Crashed)
+            | (Starting, Error)
+            | (Idle, TaskRunning)
+            | (Idle, Stopping)
+            | (Idle, Crashed)
+            | (TaskRunning, Idle)
+            | (TaskRunning, Stopping)
+            | (TaskRunning, Crashed)
+            | (Stopping, NotRunning)
+            | (Stopping, Error)
+            | (Crashed, Starting)
+            | (Crashed, NotRunning)
+            | (Crashed, Error)
+            | (Error, Starting)
+            | (Error, NotRunning)
+    )
+}
+
+impl SraStateMachine {
+    pub fn new() -> Self {
+        Self {
+            current: SraStatus::NotRunning,
+            history: Vec::new(),
+        }
+    }
+
+    pub fn current(&self) -> SraStatus {
+        self.current
+    }
+
+    pub fn history(&self) -> &[StateTransition] {
+        &self.history
+    }
+
+    // 尝试转换到目标状态
+    // 返回 Ok(true) 表示状态已改变，Ok(false) 表示已处于目标状态，Err 表示非法转换
+    pub fn transition(&mut self, to: SraStatus, reason: &str) -> Result<bool, String> {
+        if self.current == to {
+            return Ok(false);
+        }
+        if !is_allowed(self.current, to) {
+            return Err(format!(
+                "非法状态转换: {} -> {}（{}）",
+                self.current.as_str(),
+                to.as_str(),
+                reason
+            ));
+        }
+
+        self.history.push(StateTransition {
+            from: self.current,
+            to,
+            time: chrono::Local::now().to_rfc3339(),
+            reason: reason.to_string(),
+        });
+        if self.history.len() > MAX_HISTORY {
+            let overflow = self.history.len() - MAX_HISTORY;
+            self.history.drain(..overflow);
+        }
+
+        self.current = to;
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normal_lifecycle() {
+        let mut machine = SraStateMachine::new();
+        assert_eq!(machine.transition(SraStatus::Starting, "启动"), Ok(true));
+        assert_eq!(machine.transition(SraStatus::Idle, "已启动"), Ok(true));
+        assert_eq!(machine.transition(SraStatus::TaskRunning, "[Start]"), Ok(true));
+        assert_eq!(machine.transition(SraStatus::Idle, "[Done]"), Ok(true));
+        assert_eq!(machine.transition(SraStatus::Stopping, "停止"), Ok(true));
+        assert_eq!(machine.transition(SraStatus::NotRunning, "已退出"), Ok(true));
+        assert_eq!(machine.history().len(), 6);
+        assert_eq!(machine.history()[2].from, SraStatus::Idle);
+        assert_eq!(machine.history()[2].to, SraStatus::TaskRunning);
+    }
+
+    #[test]
+    fn test_illegal_transition_is_rejected() {
+        let mut machine = SraStateMachine::new();
+        assert!(machine.transition(SraStatus::TaskRunning, "[Start]").is_err());
+        assert_eq!(machine.current(), SraStatus::NotRunning);
+        assert!(machine.history().is_empty());
+    }
+
+    #[test]
+    fn test_same_state_is_noop() {
+        let mut machine = SraStateMachine::new();
+        machine.transition(SraStatus::Starting, "启动").unwrap();
+        machine.transition(SraStatus::Idle, "已启动").unwrap();
+        assert_eq!(machine.transition(SraStatus::Idle, "[Done]"), Ok(false));
+        assert_eq!(machine.history().len(), 2);
+    }
+}
@@ -0,0 +1,195 @@
+// 任务超时与卡死检测
+// 任务运行超过配置的最长时间，或长时间没有任何输出（如卡在无法识别的界面）时，
+// 先发送 task stop，未能停止则重启 SRA-cli，本次运行记为超时
+
+use crate::events;
+use crate::logger::{log_instance, LogLevel, LogSource};
+use crate::process;
+use crate::run_history;
+use crate::settings::{self, TaskTimeoutSettings};
+use crate::types::SraStatus;
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// 检查间隔
+const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+static MONITOR_STARTED: AtomicBool = AtomicBool::new(false);
+
+// 超时事件
+#[derive(Debug, Clone, Serialize)]
+pub struct TimeoutEvent {
+    pub instance_id: String,
+    pub config: Option<String>,
+    pub reason: String,
+}
+
+// 运行超时的原因，未超时返回 None
+fn timeout_reason(run: &run_history::ActiveRunInfo, policy: &TaskTimeoutSettings, now: Instant) -> Option<String> {
+    let max_minutes = policy.max_duration_for(run.config.as_deref());
+    if max_minutes > 0 && now.duration_since(run.started_at) >= Duration::from_secs(max_minutes as u64 * 60) {
+        return Some(format!("任务运行超过最长时间 {} 分钟", max_minutes));
+    }
+    let hang_minutes = policy.hang_timeout_minutes;
+    if hang_minutes > 0 && now.duration_since(run.last_output) >= Duration::from_secs(hang_minutes as u64 * 60) {
+        return Some(format!("任务已 {} 分钟没有任何输出，可能卡在无法识别的界面", hang_minutes));
+    }
+    None
+}
+
+// 检查所有正在运行的任务（now 由调用方传入，便于测试）
+pub fn check(now: Instant) {
+    let policy = settings::load_ce_settings_or_default().task_timeout;
+
+    for run in run_history::active_runs() {
+        let reason = match timeout_reason(&run, &policy, now) {
+            Some(reason) => reason,
+            None => continue,
+        };
+        if !run_history::mark_timed_out(&run.instance_id, &reason) {
+            continue;
+        }
+        thread::spawn(move || abort_run(&run.instance_id, run.config, &reason));
+    }
+}
+
+// 中止超时的任务：task stop 无效时重启进程
+fn abort_run(instance_id: &str, config: Option<String>, reason: &str) {
+    let _ = log_instance(instance_id, LogSource::Backend, LogLevel::WARN, format!("{}，正在停止任务", reason));
+    events::emit("sra-task-timeout", TimeoutEvent {
+        instance_id: instance_id.to_string(),
+        config,
+        reason: reason.to_string(),
+    });
+
+    let stop_timeout = Duration::from_millis(settings::load_ce_settings_or_default().stop_policy.task_stop_timeout_ms);
+    let still_running = || process::get_instance_status(instance_id).is_ok_and(|s| s == SraStatus::TaskRunning.as_str());
+    if process::task_stop(instance_id).is_ok() && process::wait_until(stop_timeout, || !still_running()) {
+        let _ = log_instance(instance_id, LogSource::Backend, LogLevel::INFO, "超时的任务已停止".to_string());
+        return;
+    }
+    if !still_running() {
+        return;
+    }
+
+    let _ = log_instance(instance_id, LogSource::Backend, LogLevel::WARN, "任务未响应 task stop，正在重启 SRA 进程".to_string());
+    if let Err(e) = process::restart_sra_process_command(instance_id, process::current_launch(instance_id)) {
+        let _ = log_instance(instance_id, LogSource::Backend, LogLevel::ERR, format!("重启 SRA 进程失败: {}", e));
+    }
+}
+
+// 启动检测线程（只启动一次）
+pub fn start_monitor() {
+    if MONITOR_STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    thread::spawn(|| loop {
+        thread::sleep(CHECK_INTERVAL);
+        check(Instant::now());
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_backend::tests::setup;
+    use crate::types::{LaunchRequest, DEFAULT_INSTANCE};
+    use std::collections::BTreeMap;
+
+    fn run(config: &str, started_at: Instant, last_output: Instant) -> run_history::ActiveRunInfo {
+        run_history::ActiveRunInfo {
+            instance_id: DEFAULT_INSTANCE.to_string(),
+            config: Some(config.to_string()),
+            started_at,
+            last_output,
+        }
+    }
+
+    #[test]
+    fn test_timeout_reason() {
+        let start = Instant::now();
+        let minutes = |m: u64| start + Duration::from_secs(m * 60);
+        let mut limits = BTreeMap::new();
+        limits.insert("Long".to_string(), 90);
+        let policy = TaskTimeoutSettings {
+            max_duration_minutes: 60,
+            config_max_duration_minutes: limits,
+            hang_timeout_minutes: 20,
+        };
+
+        // 超过最长运行时间（按配置单独设置的优先）
+        let busy = run("Default", start, minutes(59));
+        assert_eq!(timeout_reason(&busy, &policy, minutes(59)), None);
+        assert!(timeout_reason(&busy, &policy, minutes(60)).unwrap().contains("60 分钟"));
+        let long = run("Long", start, minutes(89));
+        assert_eq!(timeout_reason(&long, &policy, minutes(60)), None);
+        assert!(timeout_reason(&long, &policy, minutes(90)).unwrap().contains("90 分钟"));
+
+        // 长时间没有输出
+        let quiet = run("Default", start, minutes(5));
+        assert_eq!(timeout_reason(&quiet, &policy, minutes(24)), None);
+        assert!(timeout_reason(&quiet, &policy, minutes(25)).unwrap().contains("没有任何输出"));
+
+        // 0 表示不限制
+        let unlimited = TaskTimeoutSettings {
+            max_duration_minutes: 0,
+            config_max_duration_minutes: BTreeMap::new(),
+            hang_timeout_minutes: 0,
+        };
+        assert_eq!(timeout_reason(&run("Default", start, start), &unlimited, minutes(24 * 60)), None);
+    }
+
+    #[test]
+    fn test_hung_task_is_stopped_and_marked_timed_out() {
+        let (_guard, mock) = setup("prompt\nexpect: task run Default\nout: [Start] Default\nexpect: task stop\nout: [Done] Default\nprompt\n");
+        process::start_sra_process_command(DEFAULT_INSTANCE, LaunchRequest::default()).unwrap();
+        process::task_run(DEFAULT_INSTANCE, Some("Default".to_string())).unwrap();
+        assert!(process::wait_until(Duration::from_millis(2000), || process::get_instance_status(DEFAULT_INSTANCE).unwrap() == "task-running"));
+
+        // 未到卡死时间不处理（check 只在超时时才启动停止线程）
+        check(Instant::now() + Duration::from_secs(60));
+        assert_eq!(process::get_instance_status(DEFAULT_INSTANCE).unwrap(), "task-running");
+        assert!(run_history::active_runs().iter().any(|r| r.instance_id == DEFAULT_INSTANCE));
+
+        check(Instant::now() + Duration::from_secs(21 * 60));
+        assert!(process::wait_until(Duration::from_millis(3000), || !run_history::query_runs(&run_history::RunQuery::default()).unwrap().is_empty()));
+        let run = run_history::query_runs(&run_history::RunQuery::default()).unwrap().remove(0);
+        assert_eq!(run.outcome, run_history::RunOutcome::TimedOut);
+        assert_eq!(mock.received_commands(), vec!["task run Default", "task stop"]);
+        assert_eq!(mock.spawn_count(), 1);
+        process::stop_sra_process_command(DEFAULT_INSTANCE).unwrap();
+    }
+
+    #[test]
+    fn test_task_over_max_duration_restarts_unresponsive_process() {
+        let (_guard, mock) = setup("prompt\nexpect: task run Default\nout: [Start] Default\nhang\n---\nprompt\n");
+        settings::save_stop_policy(settings::StopPolicy {
+            task_stop_timeout_ms: 200,
+            exit_timeout_ms: 200,
+            kill_timeout_ms: 1000,
+        })
+        .unwrap();
+        let mut limits = BTreeMap::new();
+        limits.insert("Default".to_string(), 30);
+        settings::save_task_timeout_settings(settings::TaskTimeoutSettings {
+            max_duration_minutes: 0,
+            config_max_duration_minutes: limits,
+            hang_timeout_minutes: 0,
+        })
+        .unwrap();
+
+        process::start_sra_process_command(DEFAULT_INSTANCE, LaunchRequest::default()).unwrap();
+        process::task_run(DEFAULT_INSTANCE, Some("Default".to_string())).unwrap();
+        assert!(process::wait_until(Duration::from_millis(2000), || process::get_instance_status(DEFAULT_INSTANCE).unwrap() == "task-running"));
+
+        check(Instant::now() + Duration::from_secs(31 * 60));
+        assert!(process::wait_until(Duration::from_millis(5000), || mock.spawn_count() == 2 && process::get_instance_status(DEFAULT_INSTANCE).unwrap() == "running"));
+
+        let run = run_history::query_runs(&run_history::RunQuery::default()).unwrap().remove(0);
+        assert_eq!(run.outcome, run_history::RunOutcome::TimedOut);
+        assert!(run.errors.iter().any(|e| e.contains("30 分钟")));
+        process::stop_sra_process_command(DEFAULT_INSTANCE).unwrap();
+    }
+}
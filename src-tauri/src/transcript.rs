@@ -0,0 +1,474 @@
+// SRA-cli 会话记录与回放
+// 记录每个会话的原始 stdout/stderr 字节、发送的 stdin 命令及其时间，
+// 回放时按原始顺序送入分行解码器和日志解析器，重现控制台当时的输出
+
+use crate::line_codec::{CarriageReturnMode, LineCodec, OutputEncoding};
+use crate::logger::{self, LogLevel, LogSource};
+use crate::redact;
+use crate::sra_parser::{ParsedLog, SraLogParser};
+use base64::{engine::general_purpose, Engine as _};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Instant;
+
+// 记录格式版本
+const TRANSCRIPT_VERSION: u32 = 1;
+
+// 实时输出时解析器的刷新间隔（与进程模块的定时刷新一致），回放时据此重现多行日志的分段
+const FLUSH_INTERVAL_MS: u64 = 1000;
+
+// 会话记录中的一条（JSON Lines，每行一条）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum TranscriptEntry {
+    Header {
+        version: u32,
+        instance: String,
+        started: String, // ISO 8601 格式
+        program: String,
+        args: Vec<String>,
+        output_encoding: OutputEncoding,
+        carriage_return: CarriageReturnMode,
+    },
+    Stdout { t_ms: u64, data: String }, // data 为 base64 编码的原始字节
+    Stderr { t_ms: u64, data: String },
+    Stdin { t_ms: u64, text: String },
+    Exit { t_ms: u64, code: Option<i32> },
+}
+
+// 会话记录文件信息
+#[derive(Debug, Clone, Serialize)]
+pub struct TranscriptInfo {
+    pub path: String,
+    pub file_name: String,
+    pub size: u64,
+    pub modified: String, // ISO 8601 格式
+}
+
+// 回放结果
+#[derive(Debug, Clone, Serialize)]
+pub struct ReplayResult {
+    pub instance: String,
+    pub started: String,
+    pub lines: Vec<ParsedLog>, // 控制台显示的日志（含 >>> 开头的 stdin 命令）
+    pub duration_ms: u64,      // 原会话的时长
+}
+
+// 会话记录器（读取线程和 stdin 写入共用）
+pub struct TranscriptRecorder {
+    path: PathBuf,
+    started: Instant,
+    file: Mutex<File>,
+}
+
+impl TranscriptRecorder {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn elapsed_ms(&self) -> u64 {
+        self.started.elapsed().as_millis() as u64
+    }
+
+    // 每条记录立即写入，进程崩溃时也能保留完整的记录
+    fn write(&self, entry: &TranscriptEntry) {
+        if let Ok(mut line) = serde_json::to_string(entry) {
+            line.push('\n');
+            if let Ok(mut file) = self.file.lock() {
+                let _ = file.write_all(line.as_bytes());
+            }
+        }
+    }
+
+    pub fn record_stdout(&self, data: &[u8]) {
+        self.write(&TranscriptEntry::Stdout {
+            t_ms: self.elapsed_ms(),
+            data: general_purpose::STANDARD.encode(redact::redact_bytes(data)),
+        });
+    }
+
+    pub fn record_stderr(&self, data: &[u8]) {
+        self.write(&TranscriptEntry::Stderr {
+            t_ms: self.elapsed_ms(),
+            data: general_purpose::STANDARD.encode(redact::redact_bytes(data)),
+        });
+    }
+
+    pub fn record_stdin(&self, text: &str) {
+        self.write(&TranscriptEntry::Stdin {
+            t_ms: self.elapsed_ms(),
+            text: redact::redact(text.trim_end_matches(['\r', '\n'])),
+        });
+    }
+
+    pub fn record_exit(&self, code: Option<i32>) {
+        self.write(&TranscriptEntry::Exit {
+            t_ms: self.elapsed_ms(),
+            code,
+        });
+    }
+}
+
+// 会话记录目录
+pub fn transcript_dir() -> Result<PathBuf, String> {
+    Ok(crate::paths::data_dir()?.join("SRA-CE-Transcripts"))
+}
+
+// 创建新的会话记录: transcript-default-2025-11-29_13-20-11.jsonl
+pub fn create_recorder(
+    instance: &str,
+    program: &Path,
+    args: &[String],
+    output_encoding: OutputEncoding,
+    carriage_return: CarriageReturnMode,
+    max_files: usize,
+) -> Result<TranscriptRecorder, String> {
+    let dir = transcript_dir()?;
+    fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create transcript directory: {}", e))?;
+    cleanup_old_transcripts(&dir, max_files.saturating_sub(1));
+
+    let now = chrono::Local::now();
+    let safe_instance: String = instance
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    let path = dir.join(format!(
+        "transcript-{}-{}.jsonl",
+        safe_instance,
+        now.format("%Y-%m-%d_%H-%M-%S%.3f")
+    ));
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| format!("Failed to create transcript file: {}", e))?;
+
+    let recorder = TranscriptRecorder {
+        path,
+        started: Instant::now(),
+        file: Mutex::new(file),
+    };
+    recorder.write(&TranscriptEntry::Header {
+        version: TRANSCRIPT_VERSION,
+        instance: instance.to_string(),
+        started: now.to_rfc3339(),
+        program: program.display().to_string(),
+        args: args.to_vec(),
+        output_encoding,
+        carriage_return,
+    });
+    Ok(recorder)
+}
+
+// 只保留最近的 keep 个会话记录
+fn cleanup_old_transcripts(dir: &Path, keep: usize) {
+    let mut files: Vec<PathBuf> = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "jsonl"))
+            .collect(),
+        Err(_) => return,
+    };
+    if files.len() <= keep {
+        return;
+    }
+    // 文件名包含时间，按名称排序即按时间排序
+    files.sort();
+    let remove = files.len() - keep;
+    for path in files.into_iter().take(remove) {
+        let _ = fs::remove_file(path);
+    }
+}
+
+// 列出所有会话记录（最新的在前）
+pub fn list_transcripts() -> Result<Vec<TranscriptInfo>, String> {
+    let dir = transcript_dir()?;
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let entries = fs::read_dir(&dir)
+        .map_err(|e| format!("Failed to read transcript directory: {}", e))?;
+
+    let mut transcripts: Vec<TranscriptInfo> = entries
+        .flatten()
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "jsonl"))
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            let modified: chrono::DateTime<chrono::Local> = metadata.modified().ok()?.into();
+            Some(TranscriptInfo {
+                path: entry.path().display().to_string(),
+                file_name: entry.file_name().to_string_lossy().to_string(),
+                size: metadata.len(),
+                modified: modified.to_rfc3339(),
+            })
+        })
+        .collect();
+    transcripts.sort_by(|a, b| b.file_name.cmp(&a.file_name));
+    Ok(transcripts)
+}
+
+// 读取会话记录
+pub fn read_transcript(path: &Path) -> Result<Vec<TranscriptEntry>, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open transcript: {}", e))?;
+    let mut entries = Vec::new();
+    for (index, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(|e| format!("Failed to read transcript: {}", e))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        // 进程被强制结束时最后一行可能不完整，忽略即可
+        match serde_json::from_str(&line) {
+            Ok(entry) => entries.push(entry),
+            Err(e) => {
+                let _ = logger::log(
+                    LogSource::Backend,
+                    LogLevel::WARN,
+                    format!("会话记录第 {} 行无法解析: {}", index + 1, e),
+                );
+            }
+        }
+    }
+    Ok(entries)
+}
+
+// 解析要读取的会话记录：list_transcripts 返回的路径或文件名，只允许会话记录目录下的 .jsonl 文件
+pub fn resolve_transcript_path(path: &str) -> Result<PathBuf, String> {
+    let dir = transcript_dir()?;
+    let dir = dir.canonicalize().map_err(|e| format!("Failed to open transcript directory: {}", e))?;
+    let resolved = dir
+        .join(path)
+        .canonicalize()
+        .map_err(|e| format!("Failed to open transcript: {}", e))?;
+    if !resolved.starts_with(&dir) || resolved.extension().is_none_or(|ext| ext != "jsonl") {
+        return Err(format!("不是会话记录文件: {}", path));
+    }
+    Ok(resolved)
+}
+
+// 将会话记录重新送入解析器，返回控制台应显示的日志
+pub fn replay_entries(entries: &[TranscriptEntry]) -> Result<ReplayResult, String> {
+    let (instance, started, output_encoding, carriage_return) = match entries.first() {
+        Some(TranscriptEntry::Header { instance, started, output_encoding, carriage_return, .. }) => {
+            (instance.clone(), started.clone(), *output_encoding, *carriage_return)
+        }
+        _ => return Err("会话记录缺少文件头".to_string()),
+    };
+    let started_time = chrono::DateTime::parse_from_rfc3339(&started).ok();
+
+    let parser = SraLogParser::new();
+    let mut stdout_codec = LineCodec::new(output_encoding, carriage_return);
+    let mut stderr_codec = LineCodec::new(output_encoding, carriage_return);
+    let mut output = Vec::new();
+    let mut pending = Vec::new();
+    let mut last_flush_ms = 0;
+    let mut duration_ms = 0;
+
+    for entry in &entries[1..] {
+        let t_ms = match entry {
+            TranscriptEntry::Stdout { t_ms, .. }
+            | TranscriptEntry::Stderr { t_ms, .. }
+            | TranscriptEntry::Stdin { t_ms, .. }
+            | TranscriptEntry::Exit { t_ms, .. } => *t_ms,
+            TranscriptEntry::Header { .. } => continue,
+        };
+        duration_ms = t_ms;
+
+        // 重现定时刷新：距上次刷新超过刷新间隔时先输出解析器中暂存的多行日志
+        if t_ms.saturating_sub(last_flush_ms) >= FLUSH_INTERVAL_MS {
+            output.extend(parser.flush());
+            last_flush_ms = t_ms;
+        }
+
+        match entry {
+            TranscriptEntry::Stdout { data, .. } => {
+                let bytes = general_purpose::STANDARD
+                    .decode(data)
+                    .map_err(|e| format!("Failed to decode base64: {}", e))?;
+                stdout_codec.push(&bytes, &mut pending);
+                for line in pending.drain(..) {
+                    replay_stdout_line(&parser, &line, &mut output);
+                }
+                if stdout_codec.partial().trim() == "sra>" {
+                    stdout_codec.clear_partial();
+                    output.extend(parser.flush());
+                }
+            }
+            TranscriptEntry::Stderr { data, .. } => {
+                let bytes = general_purpose::STANDARD
+                    .decode(data)
+                    .map_err(|e| format!("Failed to decode base64: {}", e))?;
+                stderr_codec.push(&bytes, &mut pending);
+                for line in pending.drain(..) {
+                    output.extend(parser.parse_line(&line));
+                }
+            }
+            TranscriptEntry::Stdin { text, .. } => {
+                let time = started_time
+                    .map(|t| (t + chrono::Duration::milliseconds(t_ms as i64)).format("%H:%M:%S").to_string())
+                    .unwrap_or_default();
+                output.push(ParsedLog {
+                    level: LogLevel::MSG,
+                    message: format!(">>> {}", text),
+                    time,
+                });
+            }
+            TranscriptEntry::Exit { .. } | TranscriptEntry::Header { .. } => {}
+        }
+    }
+
+    // 会话结束，输出剩余内容
+    stdout_codec.finish(&mut pending);
+    for line in pending.drain(..) {
+        replay_stdout_line(&parser, &line, &mut output);
+    }
+    stderr_codec.finish(&mut pending);
+    for line in pending.drain(..) {
+        output.extend(parser.parse_line(&line));
+    }
+    output.extend(parser.flush());
+
+    Ok(ReplayResult {
+        instance,
+        started,
+        lines: output,
+        duration_ms,
+    })
+}
+
+// 与实时读取相同的 stdout 行处理（prompt 只触发刷新）
+fn replay_stdout_line(parser: &SraLogParser, line: &str, output: &mut Vec<ParsedLog>) {
+    let trimmed = line.trim();
+    if trimmed.is_empty() {
+        return;
+    }
+    if trimmed == "sra>" {
+        output.extend(parser.flush());
+        return;
+    }
+    output.extend(parser.parse_line(line));
+}
+
+// 回放会话记录文件，并将结果写入日志（标记为 replay:<实例>，与实时日志区分）
+pub fn replay_transcript(path: &str) -> Result<ReplayResult, String> {
+    let entries = read_transcript(&resolve_transcript_path(path)?)?;
+    let result = replay_entries(&entries)?;
+
+    let tag = format!("replay:{}", result.instance);
+    let _ = logger::log_instance(
+        &tag,
+        LogSource::Backend,
+        LogLevel::INFO,
+        format!("开始回放会话记录: {}（{} 条输出）", path, result.lines.len()),
+    );
+    for line in &result.lines {
+        let _ = logger::log_instance(&tag, LogSource::Process, line.level.clone(), line.message.clone());
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_backend::tests::{setup, BASIC_SESSION};
+    use crate::process;
+    use crate::settings;
+    use crate::types::{LaunchRequest, DEFAULT_INSTANCE};
+
+    fn stdout(t_ms: u64, text: &str) -> TranscriptEntry {
+        TranscriptEntry::Stdout { t_ms, data: general_purpose::STANDARD.encode(text.as_bytes()) }
+    }
+
+    #[test]
+    fn test_replay_reproduces_parsed_output() {
+        let header = TranscriptEntry::Header {
+            version: TRANSCRIPT_VERSION,
+            instance: "default".to_string(),
+            started: "2025-11-29T13:20:11+08:00".to_string(),
+            program: "SRA-cli.exe".to_string(),
+            args: vec!["--inline".to_string()],
+            output_encoding: OutputEncoding::Auto,
+            carriage_return: CarriageReturnMode::Overwrite,
+        };
+        // 中文字符被截断在两个读取块之间
+        let line = "14:20:08[40401] | INFO | 当前配置: Default\n";
+        let cut = line.find('当').unwrap() + 1;
+        let bytes = line.as_bytes();
+        let entries = [
+            header,
+            stdout(5, "sra> "),
+            TranscriptEntry::Stdin { t_ms: 1500, text: "task run Default".to_string() },
+            TranscriptEntry::Stdout { t_ms: 1510, data: general_purpose::STANDARD.encode(&bytes[..cut]) },
+            TranscriptEntry::Stdout { t_ms: 1511, data: general_purpose::STANDARD.encode(&bytes[cut..]) },
+            stdout(1600, "[Done] Default\nsra> "),
+            TranscriptEntry::Exit { t_ms: 2000, code: Some(0) },
+        ];
+
+        // 经过 JSON 序列化往返，确保文件格式可读回
+        let text: String = entries.iter().map(|e| serde_json::to_string(e).unwrap() + "\n").collect();
+        let entries: Vec<TranscriptEntry> = text.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+
+        let result = replay_entries(&entries).unwrap();
+        assert_eq!(result.duration_ms, 2000);
+        let messages: Vec<&str> = result.lines.iter().map(|l| l.message.as_str()).collect();
+        assert_eq!(messages[0], ">>> task run Default");
+        assert_eq!(result.lines[0].time, "13:20:12");
+        assert!(messages.contains(&"当前配置: Default"));
+    }
+
+    #[test]
+    fn test_replay_requires_header() {
+        assert!(replay_entries(&[stdout(0, "hello\n")]).is_err());
+    }
+
+    #[test]
+    fn test_replay_only_reads_transcript_directory() {
+        let dir = transcript_dir().unwrap();
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("transcript-path-test.jsonl");
+        fs::write(&file, "").unwrap();
+        let outside = dir.parent().unwrap().join("outside-test.jsonl");
+        fs::write(&outside, "").unwrap();
+
+        assert_eq!(resolve_transcript_path("transcript-path-test.jsonl").unwrap(), file.canonicalize().unwrap());
+        assert!(resolve_transcript_path(file.to_str().unwrap()).is_ok());
+        assert!(resolve_transcript_path("../outside-test.jsonl").is_err());
+        assert!(resolve_transcript_path(outside.to_str().unwrap()).is_err());
+        assert!(resolve_transcript_path("../SRA-CE-Settings.json").is_err());
+        let _ = fs::remove_file(&file);
+        let _ = fs::remove_file(&outside);
+    }
+
+    #[test]
+    fn test_transcript_records_and_replays_session() {
+        let (_guard, _mock) = setup(BASIC_SESSION);
+        settings::save_transcript_settings(settings::TranscriptSettings { enabled: true, ..Default::default() }).unwrap();
+
+        process::start_sra_process_command(DEFAULT_INSTANCE, LaunchRequest::default()).unwrap();
+        let live = process::send_command_and_wait(DEFAULT_INSTANCE, "task run Default".to_string(), Some(3000)).unwrap();
+        let path = process::get_sra_status()
+            .unwrap()
+            .into_iter()
+            .find(|s| s.instance_id == DEFAULT_INSTANCE)
+            .and_then(|s| s.transcript)
+            .unwrap();
+        process::stop_sra_process_command(DEFAULT_INSTANCE).unwrap();
+
+        let entries = read_transcript(Path::new(&path)).unwrap();
+        assert!(matches!(entries.first(), Some(TranscriptEntry::Header { .. })));
+        assert!(entries.iter().any(|e| matches!(e, TranscriptEntry::Stdin { text, .. } if text == "task run Default")));
+
+        // 回放得到的解析结果包含实时会话中的输出
+        let replay = replay_transcript(&path).unwrap();
+        let replayed: Vec<&str> = replay.lines.iter().map(|l| l.message.as_str()).collect();
+        assert!(replayed.contains(&">>> task run Default"));
+        for line in &live.lines {
+            assert!(replayed.contains(&line.message.as_str()), "回放缺少: {}", line.message);
+        }
+        assert!(list_transcripts().unwrap().iter().any(|t| t.path == path));
+    }
+}
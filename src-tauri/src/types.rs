@@ -1,20 +1,109 @@
 // 类型定义和常量
 
-use std::process::Child;
+use crate::backend::ProcessHandle;
+use crate::sra_parser::{ParsedLog, SraLogParser};
+use crate::state::SraStateMachine;
+use crate::transcript::TranscriptRecorder;
+use serde::Serialize;
+use std::sync::{mpsc, Arc, Mutex};
 
 // SRA 进程状态
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
 pub enum SraStatus {
     NotRunning,      // 未运行
-    Running,         // 运行中
-    #[allow(dead_code)]
-    TaskRunning,     // 任务执行中（保留用于未来功能）
+    Starting,        // 启动中
+    Idle,            // 运行中（空闲，等待命令）
+    TaskRunning,     // 任务执行中
+    Stopping,        // 停止中
+    Crashed,         // 意外退出
     Error,           // 异常
 }
 
+impl SraStatus {
+    // 前端使用的状态字符串（空闲状态沿用 running 以兼容前端）
+    pub fn as_str(&self) -> &str {
+        match self {
+            SraStatus::NotRunning => "not-running",
+            SraStatus::Starting => "starting",
+            SraStatus::Idle => "running",
+            SraStatus::TaskRunning => "task-running",
+            SraStatus::Stopping => "stopping",
+            SraStatus::Crashed => "crashed",
+            SraStatus::Error => "error",
+        }
+    }
+}
+
+// 默认实例 ID（未指定实例的命令都作用于该实例）
+pub const DEFAULT_INSTANCE: &str = "default";
+
 pub struct SraProcess {
-    pub child: Option<Child>,
-    pub status: SraStatus,
+    pub instance_id: String,
+    pub child: Option<Box<dyn ProcessHandle>>,
+    pub state: SraStateMachine,
+    pub session_id: u64,              // 每次启动递增，用于识别过期的读取线程
+    pub stop_requested: bool,         // 是否为用户主动停止（区分崩溃）
+    pub stopping: bool,               // 停止流程是否正在进行（并发的停止请求只等待，不重复执行）
+    pub launch: LaunchRequest,        // 启动配置和参数（崩溃重启时沿用）
+    pub capture: CaptureSlot,         // 等待命令响应时的输出捕获通道
+    pub parser: Arc<SraLogParser>,    // 本实例的日志解析器
+    pub transcript: Option<Arc<TranscriptRecorder>>, // 会话记录（未开启时为空）
+}
+
+// get_sra_status 返回的实例状态
+#[derive(Debug, Clone, Serialize)]
+pub struct InstanceStatus {
+    pub instance_id: String,
+    pub status: String,
+    pub pid: Option<u32>,
+    pub transcript: Option<String>, // 当前会话记录文件路径
+    pub profile: Option<String>,
+    pub arguments: Option<String>,
+}
+
+// 启动请求：启动配置名称（为空使用默认配置）和附加参数
+#[derive(Debug, Clone, Default)]
+pub struct LaunchRequest {
+    pub profile: Option<String>,
+    pub arguments: Option<String>,
+}
+
+// 命令响应捕获事件（由 stdout 读取线程发送）
+#[derive(Debug, Clone)]
+pub enum CaptureEvent {
+    Line(ParsedLog), // 解析出的一条日志
+    Prompt,          // 出现 sra> 提示符，命令执行完毕
+    Exited,          // 进程已退出
+}
+
+pub type CaptureSlot = Arc<Mutex<Option<mpsc::Sender<CaptureEvent>>>>;
+
+// send_command_and_wait 的返回结果
+#[derive(Debug, Clone, Serialize)]
+pub struct CommandResponse {
+    pub command: String,
+    pub lines: Vec<ParsedLog>,
+    pub elapsed_ms: u64,
+}
+
+// sra-status-changed 事件负载
+#[derive(Debug, Clone, Serialize)]
+pub struct StatusEvent {
+    pub instance_id: String,
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attempt: Option<u32>, // 自动重启的尝试次数
+}
+
+// sra-stop-progress 事件负载
+#[derive(Debug, Clone, Serialize)]
+pub struct StopProgress {
+    pub instance_id: String,
+    pub step: String,               // task-stop / exit / kill / done / failed
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,    // 本步骤的等待时间
 }
 
 // 常量定义
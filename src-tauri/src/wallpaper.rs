@@ -12,14 +12,12 @@ pub struct WallpaperSettings {
 
 // 获取设置文件路径
 fn get_settings_file() -> Result<PathBuf, String> {
-    let appdata = std::env::var("APPDATA").map_err(|_| "Failed to get APPDATA path")?;
-    Ok(PathBuf::from(appdata).join("SRA").join("SRA-CE-Settings.json"))
+    Ok(crate::paths::data_dir()?.join("SRA-CE-Settings.json"))
 }
 
 // 获取缓存目录路径
 fn get_cache_dir() -> Result<PathBuf, String> {
-    let appdata = std::env::var("APPDATA").map_err(|_| "Failed to get APPDATA path")?;
-    Ok(PathBuf::from(appdata).join("SRA").join("SRA-CE-Cache"))
+    Ok(crate::paths::data_dir()?.join("SRA-CE-Cache"))
 }
 
 
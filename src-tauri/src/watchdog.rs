@@ -0,0 +1,116 @@
+// SRA-cli 崩溃监控模块
+// 进程意外退出后按指数退避自动重启，时间窗口内崩溃次数过多则放弃（每个实例单独计数）
+
+use crate::logger::{log_instance, LogLevel, LogSource};
+use crate::process;
+use crate::settings::{self, WatchdogSettings};
+use crate::types::{LaunchRequest, SraStatus};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[derive(Default)]
+struct WatchdogState {
+    crashes: Vec<Instant>, // 时间窗口内的崩溃时间点
+    generation: u64,       // 用户手动启动/停止时递增，使挂起的重启失效
+}
+
+// 实例 ID -> 监控状态
+static WATCHDOG: Mutex<BTreeMap<String, WatchdogState>> = Mutex::new(BTreeMap::new());
+
+// 计算第 attempt 次重启前的等待时间（attempt 从 1 开始）
+fn backoff_delay(policy: &WatchdogSettings, attempt: u32) -> Duration {
+    let factor = 2u64.saturating_pow(attempt.saturating_sub(1));
+    let secs = policy.base_delay_secs.saturating_mul(factor).min(policy.max_delay_secs);
+    Duration::from_secs(secs)
+}
+
+// 用户手动启动或停止进程：清空崩溃记录并取消挂起的重启
+pub fn reset(instance_id: &str) {
+    if let Ok(mut states) = WATCHDOG.lock() {
+        let state = states.entry(instance_id.to_string()).or_default();
+        state.crashes.clear();
+        state.generation += 1;
+    }
+}
+
+// 进程意外退出时调用，决定是否安排重启
+pub fn handle_unexpected_exit(instance_id: &str, launch: LaunchRequest) {
+    let policy = settings::load_ce_settings_or_default().watchdog;
+
+    if !policy.enabled {
+        let _ = log_instance(instance_id, LogSource::Backend, LogLevel::WARN, "自动重启已禁用，SRA 进程保持停止".to_string());
+        return;
+    }
+
+    let (attempt, generation) = {
+        let mut states = match WATCHDOG.lock() {
+            Ok(states) => states,
+            Err(_) => return,
+        };
+        let state = states.entry(instance_id.to_string()).or_default();
+        let now = Instant::now();
+        let window = Duration::from_secs(policy.window_secs);
+        state.crashes.retain(|t| now.duration_since(*t) <= window);
+        state.crashes.push(now);
+        (state.crashes.len() as u32, state.generation)
+    };
+
+    if attempt > policy.max_crashes {
+        let _ = log_instance(
+            instance_id,
+            LogSource::Backend,
+            LogLevel::ERR,
+            format!(
+                "SRA 进程在 {} 秒内崩溃 {} 次，已放弃自动重启",
+                policy.window_secs, attempt
+            ),
+        );
+        let _ = process::transition_state(instance_id, SraStatus::Error, "崩溃次数过多，放弃自动重启", Some(attempt));
+        return;
+    }
+
+    let delay = backoff_delay(&policy, attempt);
+    let _ = log_instance(
+        instance_id,
+        LogSource::Backend,
+        LogLevel::WARN,
+        format!("将在 {} 秒后尝试第 {} 次自动重启 SRA 进程", delay.as_secs(), attempt),
+    );
+    process::emit_status(instance_id, "restarting", Some(attempt));
+
+    let instance_id = instance_id.to_string();
+    thread::spawn(move || {
+        thread::sleep(delay);
+
+        // 等待期间用户手动启动/停止过，放弃本次重启
+        let still_pending = WATCHDOG
+            .lock()
+            .map(|states| states.get(&instance_id).is_some_and(|state| state.generation == generation))
+            .unwrap_or(false);
+        if !still_pending {
+            let _ = log_instance(&instance_id, LogSource::Backend, LogLevel::DEBUG, "自动重启已被用户操作取消".to_string());
+            return;
+        }
+
+        match process::launch_sra_process(&instance_id, launch, Some(attempt)) {
+            Ok(()) => {
+                let _ = log_instance(
+                    &instance_id,
+                    LogSource::Backend,
+                    LogLevel::SUCCESS,
+                    format!("SRA 进程已自动重启（第 {} 次）", attempt),
+                );
+            }
+            Err(e) => {
+                let _ = log_instance(
+                    &instance_id,
+                    LogSource::Backend,
+                    LogLevel::ERR,
+                    format!("第 {} 次自动重启失败: {}", attempt, e),
+                );
+            }
+        }
+    });
+}
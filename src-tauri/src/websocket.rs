@@ -0,0 +1,118 @@
+// WebSocket 帧编解码（RFC 6455）
+// 只实现远程控制接口需要的服务端部分：握手应答、发送不分片的帧、读取客户端（带掩码）的帧
+
+use base64::{engine::general_purpose, Engine as _};
+use sha1::{Digest, Sha1};
+use std::io;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+pub const OP_TEXT: u8 = 0x1;
+pub const OP_CLOSE: u8 = 0x8;
+pub const OP_PING: u8 = 0x9;
+pub const OP_PONG: u8 = 0xA;
+
+// 握手使用的固定 GUID
+const HANDSHAKE_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+// 由客户端的 Sec-WebSocket-Key 计算 Sec-WebSocket-Accept
+pub fn accept_key(key: &str) -> String {
+    let mut hasher = Sha1::new();
+    hasher.update(key.trim().as_bytes());
+    hasher.update(HANDSHAKE_GUID.as_bytes());
+    general_purpose::STANDARD.encode(hasher.finalize())
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame {
+    pub opcode: u8,
+    pub payload: Vec<u8>,
+}
+
+// 编码服务端发送的帧（FIN=1，不带掩码）
+pub fn encode_frame(opcode: u8, payload: &[u8]) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(payload.len() + 10);
+    frame.push(0x80 | opcode);
+    match payload.len() {
+        len if len < 126 => frame.push(len as u8),
+        len if len <= u16::MAX as usize => {
+            frame.push(126);
+            frame.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+        len => {
+            frame.push(127);
+            frame.extend_from_slice(&(len as u64).to_be_bytes());
+        }
+    }
+    frame.extend_from_slice(payload);
+    frame
+}
+
+// 读取一帧，负载超过 max_len 时返回错误
+pub async fn read_frame<R: AsyncRead + Unpin>(reader: &mut R, max_len: usize) -> io::Result<Frame> {
+    let mut header = [0u8; 2];
+    reader.read_exact(&mut header).await?;
+    let opcode = header[0] & 0x0F;
+    let masked = header[1] & 0x80 != 0;
+    let len = match header[1] & 0x7F {
+        126 => {
+            let mut ext = [0u8; 2];
+            reader.read_exact(&mut ext).await?;
+            u16::from_be_bytes(ext) as usize
+        }
+        127 => {
+            let mut ext = [0u8; 8];
+            reader.read_exact(&mut ext).await?;
+            usize::try_from(u64::from_be_bytes(ext)).unwrap_or(usize::MAX)
+        }
+        len => len as usize,
+    };
+    if len > max_len {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, format!("帧过大: {} 字节", len)));
+    }
+
+    let mut mask = [0u8; 4];
+    if masked {
+        reader.read_exact(&mut mask).await?;
+    }
+    let mut payload = vec![0u8; len];
+    reader.read_exact(&mut payload).await?;
+    if masked {
+        for (i, byte) in payload.iter_mut().enumerate() {
+            *byte ^= mask[i % 4];
+        }
+    }
+    Ok(Frame { opcode, payload })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_accept_key() {
+        // RFC 6455 中的示例
+        assert_eq!(accept_key("dGhlIHNhbXBsZSBub25jZQ=="), "s3pPLMBiTxaQ9kYGzzhZRbK+xOo=");
+    }
+
+    #[test]
+    fn test_frames() {
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+
+        // 服务端帧（不带掩码，扩展长度）
+        let payload = vec![b'a'; 300];
+        let encoded = encode_frame(OP_TEXT, &payload);
+        assert_eq!(&encoded[..4], &[0x81, 126, 0x01, 0x2C]);
+        let frame = runtime.block_on(read_frame(&mut encoded.as_slice(), 1024)).unwrap();
+        assert_eq!(frame, Frame { opcode: OP_TEXT, payload });
+
+        // 客户端帧（带掩码）
+        let mask = [0x37, 0xfa, 0x21, 0x3d];
+        let mut client = vec![0x89, 0x80 | 5];
+        client.extend_from_slice(&mask);
+        client.extend(b"Hello".iter().enumerate().map(|(i, b)| b ^ mask[i % 4]));
+        let frame = runtime.block_on(read_frame(&mut client.as_slice(), 1024)).unwrap();
+        assert_eq!(frame, Frame { opcode: OP_PING, payload: b"Hello".to_vec() });
+
+        assert!(runtime.block_on(read_frame(&mut encoded.as_slice(), 100)).is_err());
+    }
+}
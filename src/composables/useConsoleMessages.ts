@@ -15,6 +15,7 @@ export interface ConsoleMessage {
     level: MessageLevel
     message: string
     time: string // ISO 8601 格式
+    instance?: string // SRA-cli 实例 ID（仅实例相关日志）
 }
 
 class ConsoleMessageManager {
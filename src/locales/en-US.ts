@@ -17,6 +17,10 @@ export default {
     notRunning: 'Not Running',
     running: 'Core Running',
     taskRunning: 'Task Running',
+    starting: 'Core Starting',
+    stopping: 'Core Stopping',
+    crashed: 'Core Crashed',
+    restarting: 'Core Restarting',
     error: 'Core Error',
     startTask: 'Start Task',
     stopTask: 'Stop Task',
@@ -17,6 +17,10 @@ export default {
     notRunning: '未运行',
     running: '核心运行中',
     taskRunning: '任务执行中',
+    starting: '核心启动中',
+    stopping: '核心停止中',
+    crashed: '核心意外退出',
+    restarting: '核心重启中',
     error: '核心异常',
     startTask: '开始任务',
     stopTask: '停止任务',